@@ -0,0 +1,446 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+
+use fs_ui_core::audit::{self, AuditAction, AuditEntry, AuditLog};
+use fs_ui_core::client::{self, ClientConfig, IngestClient, SocketClient};
+use fs_ui_core::dataset;
+use fs_ui_core::dialect::Dialect;
+use fs_ui_core::pricing::RateTable;
+use fs_ui_core::profile::{self, ImportStatus, ProfileDocument, ProfileFormat, Resolution};
+use fs_ui_core::session;
+use fs_ui_core::source::FileSource;
+use fs_ui_core::store::VendorStore;
+use fs_ui_core::transport::Transport;
+use fs_ui_core::upload::{self, VendorInfo};
+use fs_ui_core::validation::{self, ValidationReport};
+
+/// Identifier of the desktop app, which names its data directory.
+const APP_IDENTIFIER: &str = "com.fs_ui.app";
+
+/// The file failed validation.
+const EXIT_INVALID: u8 = 1;
+/// The ingest service could not be reached or rejected the upload.
+const EXIT_BACKEND: u8 = 3;
+/// Local problem: unreadable file, missing vendor, broken data directory.
+const EXIT_ERROR: u8 = 4;
+
+#[derive(Parser)]
+#[command(name = "fs_ui-cli", version, about = "Set up vendors and upload stock files to the ingest service")]
+struct Cli {
+    /// Print results as JSON instead of text.
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Directory holding saved vendors; defaults to the desktop app's.
+    #[arg(long, global = true, env = "FS_UI_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Ingest service: a Unix socket path, tcp://host:port or tls://host:port.
+    /// Defaults to the transport in the app's client.json, then SOCKET_LOCATION.
+    #[arg(long, global = true)]
+    socket: Option<String>,
+
+    /// Operator to sign in as for uploads, when the service requires it.
+    #[arg(long, global = true, env = "FS_UI_OPERATOR")]
+    operator: Option<String>,
+
+    #[arg(long, global = true, env = "FS_UI_OPERATOR_PASSWORD", hide_env_values = true, requires = "operator")]
+    operator_password: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage vendors.
+    Vendor {
+        #[command(subcommand)]
+        command: VendorCommand,
+    },
+    /// Upload a stock file for an existing vendor.
+    Upload {
+        file: PathBuf,
+        #[arg(long)]
+        vendor: String,
+        /// Mapping such as "File Key:SKU, Quantity:Qty"; defaults to the vendor's saved one.
+        #[arg(long)]
+        mapping: Option<String>,
+        /// Send only rows that changed since the vendor's last upload.
+        #[arg(long)]
+        delta: bool,
+        /// Why the mapping changed, kept with the vendor's new profile version.
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Check a stock file against a mapping without uploading it.
+    Validate {
+        file: PathBuf,
+        #[arg(long)]
+        vendor: Option<String>,
+        #[arg(long)]
+        mapping: Option<String>,
+    },
+    /// Share vendor mapping profiles with other installations.
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Show or edit the exchange rates prices are converted with.
+    Rates {
+        #[command(subcommand)]
+        command: RatesCommand,
+    },
+    /// Check that the ingest service accepts connections.
+    Ping,
+}
+
+#[derive(Subcommand)]
+enum RatesCommand {
+    /// Print the rate table.
+    Show,
+    /// Set how many units of the base currency one unit of CURRENCY is worth.
+    Set { currency: String, rate: f64 },
+    /// Drop the rate for CURRENCY.
+    Remove { currency: String },
+    /// Convert uploaded prices to CURRENCY, or "none" to send them as vendors do.
+    ConvertTo { currency: String },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// Write profiles to a .json or .toml file.
+    Export {
+        file: PathBuf,
+        /// Vendors to export; defaults to all of them.
+        #[arg(long)]
+        vendor: Vec<String>,
+    },
+    /// Import profiles from a .json or .toml file. Vendors new to this
+    /// installation are always imported.
+    Import {
+        file: PathBuf,
+        /// What to do with a vendor whose saved profile differs.
+        #[arg(long, value_enum, default_value_t = OnConflict::Skip)]
+        on_conflict: OnConflict,
+        /// Only show what would change.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OnConflict {
+    Skip,
+    Replace,
+    Merge,
+}
+
+#[derive(Subcommand)]
+enum VendorCommand {
+    /// Set up a new vendor with its mapping and first stock file.
+    Create {
+        name: String,
+        #[arg(long, env = "FS_UI_VENDOR_PASSWORD", hide_env_values = true)]
+        password: String,
+        #[arg(long)]
+        mapping: String,
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// List vendors with a saved mapping.
+    List,
+}
+
+/// What a command prints on success or failure.
+struct Outcome {
+    code: u8,
+    message: String,
+    data: Value,
+}
+
+impl Outcome {
+    fn ok(message: impl Into<String>, data: Value) -> Self {
+        Outcome { code: 0, message: message.into(), data }
+    }
+
+    fn fail(code: u8, message: impl Into<String>) -> Self {
+        Outcome { code, message: message.into(), data: Value::Null }
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let outcome = run(&cli).await.unwrap_or_else(|outcome| outcome);
+    if cli.json {
+        let output = json!({
+            "ok": outcome.code == 0,
+            "code": outcome.code,
+            "message": outcome.message,
+            "data": outcome.data,
+        });
+        println!("{}", output);
+    } else if outcome.code == 0 {
+        println!("{}", outcome.message);
+    } else {
+        eprintln!("{}", outcome.message);
+    }
+    ExitCode::from(outcome.code)
+}
+
+async fn run(cli: &Cli) -> Result<Outcome, Outcome> {
+    match &cli.command {
+        Command::Vendor { command: VendorCommand::List } => {
+            let store = store(cli)?;
+            let mut vendors = Vec::new();
+            for name in store.list().map_err(local)? {
+                if let Some(record) = store.load(&name).map_err(local)? {
+                    vendors.push(json!({
+                        "vendor_name": record.vendor_name,
+                        "file_mappings": record.file_mappings,
+                        "rows": record.dataset.rows.len(),
+                    }));
+                }
+            }
+            let message = vendors
+                .iter()
+                .map(|v| format!("{}\t{}", v["vendor_name"].as_str().unwrap_or_default(), v["file_mappings"].as_str().unwrap_or_default()))
+                .collect::<Vec<String>>()
+                .join("\n");
+            Ok(Outcome::ok(message, Value::Array(vendors)))
+        }
+        Command::Vendor { command: VendorCommand::Create { name, password, mapping, file } } => {
+            if store(cli)?.load(name).map_err(local)?.is_some() {
+                return Err(Outcome::fail(EXIT_ERROR, format!("Vendor \"{}\" already exists", name)));
+            }
+            send(cli, name, password, mapping, file, false, "").await
+        }
+        Command::Upload { file, vendor, mapping, delta, note } => {
+            let mapping = match mapping {
+                Some(mapping) => mapping.clone(),
+                None => store(cli)?
+                    .load(vendor)
+                    .map_err(local)?
+                    .map(|record| record.file_mappings)
+                    .ok_or_else(|| Outcome::fail(EXIT_ERROR, format!("Vendor \"{}\" has no saved mapping", vendor)))?,
+            };
+            send(cli, vendor, "", &mapping, file, *delta, note.as_deref().unwrap_or_default()).await
+        }
+        Command::Validate { file, vendor, mapping } => {
+            let saved = match vendor {
+                Some(vendor) => store(cli)?.load(vendor).map_err(local)?,
+                None => None,
+            };
+            let mapping = match (mapping, &saved, vendor) {
+                (Some(mapping), _, _) => dataset::parse_mappings(mapping),
+                (None, Some(record), _) => dataset::parse_mappings(&record.file_mappings),
+                (None, None, Some(vendor)) => {
+                    return Err(Outcome::fail(EXIT_ERROR, format!("Vendor \"{}\" has no saved mapping", vendor)));
+                }
+                (None, None, None) => {
+                    let source = FileSource::Path(file.clone());
+                    let headers = dataset::read_headers(source.reader().map_err(local)?).map_err(local)?;
+                    dataset::detect_mapping(&headers, None)
+                }
+            };
+            let report = validate(file, &mapping, &saved.map(|record| record.dialect).unwrap_or_default())?;
+            let data = json!({
+                "file_mappings": dataset::format_mappings(&mapping),
+                "report": report,
+            });
+            if report.is_valid() {
+                Ok(Outcome::ok(describe(&report), data))
+            } else {
+                Err(Outcome { code: EXIT_INVALID, message: describe(&report), data })
+            }
+        }
+        Command::Profile { command: ProfileCommand::Export { file, vendor } } => {
+            let document = ProfileDocument::export(&store(cli)?, vendor).map_err(local)?;
+            let bytes = document.write(ProfileFormat::of_path(file)).map_err(local)?;
+            std::fs::write(file, bytes).map_err(|e| local(format!("{}: {}", file.display(), e)))?;
+            let vendors: Vec<&str> = document.profiles.iter().map(|p| p.vendor_name.as_str()).collect();
+            Ok(Outcome::ok(format!("Exported {} profiles to {}", vendors.len(), file.display()), json!({ "vendors": vendors })))
+        }
+        Command::Profile { command: ProfileCommand::Import { file, on_conflict, dry_run } } => {
+            let bytes = std::fs::read(file).map_err(|e| local(format!("{}: {}", file.display(), e)))?;
+            let document = ProfileDocument::parse(&bytes, ProfileFormat::of_path(file)).map_err(local)?;
+            let store = store(cli)?;
+            let preview = profile::preview(&document, &store).map_err(local)?;
+            let on_conflict = match on_conflict {
+                OnConflict::Skip => Resolution::Skip,
+                OnConflict::Replace => Resolution::Replace,
+                OnConflict::Merge => Resolution::Merge,
+            };
+            let mut lines: Vec<String> = preview.warnings.iter().map(|w| format!("warning: {}", w)).collect();
+            let mut resolutions = BTreeMap::new();
+            for checked in &preview.profiles {
+                let name = &checked.incoming.vendor_name;
+                let resolution = match checked.status {
+                    ImportStatus::New => Resolution::Replace,
+                    ImportStatus::Conflict => on_conflict,
+                    ImportStatus::Unchanged | ImportStatus::Invalid => Resolution::Skip,
+                };
+                resolutions.insert(name.clone(), resolution);
+                lines.push(format!("{}\t{}", name, format!("{:?}\t{:?}", checked.status, resolution).to_lowercase()));
+                lines.extend(checked.warnings.iter().map(|w| format!("warning: {}: {}", name, w)));
+                lines.extend(checked.errors.iter().map(|e| format!("error: {}: {}", name, e)));
+            }
+            if !*dry_run {
+                let audit_log = AuditLog::new(data_dir(cli)?.join(audit::AUDIT_LOG));
+                let summary = profile::import_audited(&document, &resolutions, &store, &audit_log, &actor(cli)).map_err(local)?;
+                lines.push(format!("Imported {} profiles, skipped {}", summary.imported.len(), summary.skipped.len()));
+            }
+            let data = json!({ "preview": preview, "resolutions": resolutions });
+            if preview.profiles.iter().any(|p| p.status == ImportStatus::Invalid) {
+                Err(Outcome { code: EXIT_INVALID, message: lines.join("\n"), data })
+            } else {
+                Ok(Outcome::ok(lines.join("\n"), data))
+            }
+        }
+        Command::Rates { command: RatesCommand::Show } => {
+            let rates = store(cli)?.rates().map_err(local)?;
+            let data = json!(rates);
+            Ok(Outcome::ok(describe_rates(&rates), data))
+        }
+        Command::Rates { command } => {
+            let store = store(cli)?;
+            let mut rates = store.rates().map_err(local)?;
+            let mut entry = AuditEntry::new(actor(cli), AuditAction::SetRates, "currency rates");
+            entry.before = Some(json!(rates));
+            match command {
+                RatesCommand::Set { currency, rate } => {
+                    rates.rates.insert(currency.clone(), *rate);
+                }
+                RatesCommand::Remove { currency } => {
+                    if rates.rates.remove(&currency.to_uppercase()).is_none() {
+                        return Err(local(format!("The rate table has no rate for {}", currency)));
+                    }
+                }
+                RatesCommand::ConvertTo { currency } if currency.eq_ignore_ascii_case("none") => rates.convert_to.clear(),
+                RatesCommand::ConvertTo { currency } => rates.convert_to = currency.clone(),
+                RatesCommand::Show => unreachable!("handled above"),
+            }
+            entry.after = Some(json!(rates));
+            let result = store.save_rates(&mut rates);
+            let audit_log = AuditLog::new(data_dir(cli)?.join(audit::AUDIT_LOG));
+            audit_log.record(entry, result, "Rates saved").map_err(local)?;
+            let data = json!(rates);
+            Ok(Outcome::ok(describe_rates(&rates), data))
+        }
+        Command::Ping => {
+            client(cli)?.ping().await.map_err(|e| Outcome::fail(EXIT_BACKEND, e))?;
+            Ok(Outcome::ok("ok", Value::Null))
+        }
+    }
+}
+
+fn local(message: String) -> Outcome {
+    Outcome::fail(EXIT_ERROR, message)
+}
+
+fn data_dir(cli: &Cli) -> Result<PathBuf, Outcome> {
+    match &cli.data_dir {
+        Some(dir) => Ok(dir.clone()),
+        None => dirs::data_dir()
+            .map(|dir| dir.join(APP_IDENTIFIER))
+            .ok_or_else(|| local("Could not determine the data directory, pass --data-dir".to_string())),
+    }
+}
+
+/// Who the audit log names: the operator, or without one the local user.
+fn actor(cli: &Cli) -> String {
+    cli.operator
+        .clone()
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "fs_ui-cli".to_string())
+}
+
+fn store(cli: &Cli) -> Result<VendorStore, Outcome> {
+    Ok(VendorStore::new(data_dir(cli)?.join("vendors")))
+}
+
+/// Connects like the desktop app, from its `client.json`, unless `--socket` says otherwise.
+fn client(cli: &Cli) -> Result<IngestClient, Outcome> {
+    let config = ClientConfig::load(&data_dir(cli)?.join(client::CLIENT_CONFIG)).map_err(local)?;
+    match &cli.socket {
+        Some(socket) => Ok(SocketClient::new(Transport::parse(socket).map_err(local)?)
+            .with_policy(config.policy)
+            .into()),
+        None => config.client().map_err(|e| Outcome::fail(EXIT_BACKEND, e)),
+    }
+}
+
+/// Reads `file` in the vendor's `dialect`, as an upload would.
+fn validate(file: &Path, mapping: &dataset::Mapping, dialect: &Dialect) -> Result<ValidationReport, Outcome> {
+    let source = dialect.normalize(FileSource::Path(file.to_path_buf())).map_err(local)?;
+    let report = validation::validate(source.reader().map_err(local)?, mapping, dialect.decimal);
+    Ok(report)
+}
+
+fn describe(report: &ValidationReport) -> String {
+    let mut lines = vec![format!("{} rows", report.row_count)];
+    lines.extend(report.errors.iter().map(|e| format!("error: {}", e)));
+    lines.extend(report.warnings.iter().map(|w| format!("warning: {}", w)));
+    if !report.currencies.is_empty() {
+        lines.push(format!("prices in {}", report.currencies.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn describe_rates(rates: &RateTable) -> String {
+    let convert_to = if rates.convert_to.is_empty() { "none" } else { rates.convert_to.as_str() };
+    let mut lines = vec![format!("base {}, converting to {}", rates.base, convert_to)];
+    lines.extend(rates.rates.iter().map(|(currency, rate)| format!("{}\t{}", currency, rate)));
+    lines.join("\n")
+}
+
+async fn send(cli: &Cli, vendor: &str, password: &str, mapping: &str, file: &Path, delta: bool, note: &str) -> Result<Outcome, Outcome> {
+    let payload = VendorInfo {
+        vendor_name: vendor.to_string(),
+        password: password.to_string(),
+        file_mappings: mapping.to_string(),
+        file_path: Some(file.display().to_string()),
+        delta_only: delta,
+        change_note: note.to_string(),
+        ..VendorInfo::default()
+    };
+    let store = store(cli)?;
+    let previous = store.load(vendor).map_err(local)?;
+    let report = validate(file, &dataset::parse_mappings(mapping), &upload::resolve_dialect(&payload, previous.as_ref()))?;
+    if !report.is_valid() {
+        return Err(Outcome {
+            code: EXIT_INVALID,
+            message: describe(&report),
+            data: json!({ "report": report }),
+        });
+    }
+    let client = client(cli)?;
+    let client = match &cli.operator {
+        Some(operator) => {
+            let password = cli.operator_password.as_deref().unwrap_or_default();
+            let session = client.login(operator, password).await.map_err(|e| Outcome::fail(EXIT_BACKEND, e))?;
+            let allowed = session::check(&session, upload::required_permission(&payload, previous.as_ref()));
+            let client = client.with_session_token(session.token);
+            if let Err(e) = allowed {
+                let _ = client.logout().await;
+                return Err(Outcome::fail(EXIT_BACKEND, e));
+            }
+            client
+        }
+        None => client,
+    };
+    let audit_log = AuditLog::new(data_dir(cli)?.join(audit::AUDIT_LOG));
+    let actor = actor(cli);
+    let result = upload::submit_audited(payload, &store, &client, &audit_log, &actor, &actor).await;
+    if cli.operator.is_some() {
+        // The upload's outcome matters more than a failed sign-out.
+        let _ = client.logout().await;
+    }
+    let message = result.map_err(|e| Outcome::fail(EXIT_BACKEND, e))?;
+    Ok(Outcome::ok(message, json!({ "vendor_name": vendor, "rows": report.row_count })))
+}
@@ -0,0 +1,38 @@
+//! Files of vendors saved with another dialect are read in it before they are checked.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use fs_ui_core::dialect::Dialect;
+use fs_ui_core::store::{VendorRecord, VendorStore};
+
+fn cli(data_dir: &Path, args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_fs_ui-cli"))
+        .arg("--data-dir")
+        .arg(data_dir)
+        .args(args)
+        .output()
+        .unwrap();
+    (output.status.code(), String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[test]
+fn semicolon_vendors_validate_and_upload_in_their_dialect() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = VendorStore::new(dir.path().join("vendors"));
+    let mut record = VendorRecord::new("Acme");
+    record.file_mappings = "File Key:SKU, Quantity:Qty, Cost:Cost".to_string();
+    record.dialect = Dialect { delimiter: ';', decimal: Some(','), ..Dialect::default() };
+    store.save(&record).unwrap();
+    let file = dir.path().join("stock.csv");
+    fs::write(&file, "SKU;Qty;Cost\nA1;5;1.500,25\nA2;3;9,99\n").unwrap();
+    let file = file.to_str().unwrap();
+
+    assert_eq!(cli(dir.path(), &["validate", file, "--vendor", "Acme"]), (Some(0), String::new()));
+
+    // Past validation, the upload only fails for want of a service.
+    fs::write(dir.path().join("client.json"), r#"{"max_attempts":1}"#).unwrap();
+    let (code, stderr) = cli(dir.path(), &["--socket", "tcp://127.0.0.1:1", "upload", file, "--vendor", "Acme"]);
+    assert_eq!(code, Some(3), "{}", stderr);
+}
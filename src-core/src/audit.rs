@@ -0,0 +1,253 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// File name of the audit log in the app's data directory.
+pub const AUDIT_LOG: &str = "audit.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Login,
+    Logout,
+    CreateVendor,
+    ChangeMapping,
+    Upload,
+    SetWatchFolders,
+    SetRates,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditAction::Login => "login",
+            AuditAction::Logout => "logout",
+            AuditAction::CreateVendor => "create_vendor",
+            AuditAction::ChangeMapping => "change_mapping",
+            AuditAction::Upload => "upload",
+            AuditAction::SetWatchFolders => "set_watch_folders",
+            AuditAction::SetRates => "set_rates",
+        })
+    }
+}
+
+/// One operation, successful or not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub actor: String,
+    pub action: AuditAction,
+    /// The vendor, or the operator for logins.
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_sha256: Option<String>,
+    pub ok: bool,
+    /// The service's reply, or why the operation failed.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
+
+impl AuditEntry {
+    pub fn new(actor: impl Into<String>, action: AuditAction, target: impl Into<String>) -> Self {
+        AuditEntry {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            actor: actor.into(),
+            action,
+            target: target.into(),
+            before: None,
+            after: None,
+            file_sha256: None,
+            ok: true,
+            message: String::new(),
+        }
+    }
+
+    pub fn outcome<T>(mut self, result: &Result<T, String>) -> Self {
+        if let Err(e) = result {
+            self.ok = false;
+            self.message = e.clone();
+        }
+        self
+    }
+}
+
+/// Which entries to show or export; unset fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditFilter {
+    /// Matches part of the actor's name, ignoring case.
+    #[serde(default)]
+    pub actor: Option<String>,
+    #[serde(default)]
+    pub action: Option<AuditAction>,
+    /// Matches part of the target, ignoring case.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Inclusive bounds, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub since: Option<u64>,
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let contains = |value: &str, part: &Option<String>| {
+            part.as_ref().is_none_or(|part| value.to_lowercase().contains(&part.to_lowercase()))
+        };
+        contains(&entry.actor, &self.actor)
+            && self.action.is_none_or(|action| action == entry.action)
+            && contains(&entry.target, &self.target)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+/// Append-only JSON Lines file; entries are never rewritten or removed.
+pub struct AuditLog {
+    path: PathBuf,
+    /// Keeps lines written from several threads whole.
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        AuditLog { path, lock: Mutex::new(()) }
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> Result<(), String> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+        line.push(b'\n');
+        let _guard = self.lock.lock().unwrap();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        file.write_all(&line).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+
+    /// Appends the entry with the result's outcome and hands the result back.
+    /// A failed write is reported alongside the result, never in place of it.
+    pub fn record<T>(&self, entry: AuditEntry, result: Result<T, String>, done: &str) -> Result<T, String> {
+        match (self.append(&entry.outcome(&result)), result) {
+            (Ok(()), result) => result,
+            (Err(e), Ok(_)) => Err(format!("{}, but the audit log could not be written: {}", done, e)),
+            (Err(e), Err(failed)) => Err(format!("{} (the audit log could not be written either: {})", failed, e)),
+        }
+    }
+
+    /// Matching entries, oldest first. Lines that do not parse, e.g. one cut
+    /// short by a crash, are skipped.
+    pub fn read(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let text = fs::read_to_string(&self.path).map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        Ok(text
+            .lines()
+            .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+            .filter(|entry| filter.matches(entry))
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+pub fn export(entries: &[AuditEntry], format: ExportFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(entries).map_err(|e| e.to_string()),
+        ExportFormat::Csv => {
+            let mut wtr = csv::Writer::from_writer(Vec::new());
+            wtr.write_record(["time", "actor", "action", "target", "before", "after", "file_sha256", "ok", "message"])
+                .map_err(|e| e.to_string())?;
+            let json = |value: &Option<Value>| value.as_ref().map(Value::to_string).unwrap_or_default();
+            for entry in entries {
+                wtr.write_record([
+                    format_utc(entry.timestamp),
+                    entry.actor.clone(),
+                    entry.action.to_string(),
+                    entry.target.clone(),
+                    json(&entry.before),
+                    json(&entry.after),
+                    entry.file_sha256.clone().unwrap_or_default(),
+                    entry.ok.to_string(),
+                    entry.message.clone(),
+                ])
+                .map_err(|e| e.to_string())?;
+            }
+            wtr.into_inner().map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Hex SHA-256 of everything `reader` yields.
+pub fn sha256(mut reader: impl Read) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// `2024-05-01T12:00:00Z` for seconds since the Unix epoch.
+pub fn format_utc(timestamp: u64) -> String {
+    let (year, month, day) = civil_date(timestamp);
+    let secs = timestamp % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+/// UTC year, month and day for seconds since the Unix epoch.
+pub(crate) fn civil_date(timestamp: u64) -> (i64, u64, u64) {
+    // After Howard Hinnant's `civil_from_days`.
+    let z = (timestamp / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month as u64, day as u64)
+}
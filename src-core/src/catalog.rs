@@ -0,0 +1,291 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dataset::{self, Mapping};
+use crate::export::Table;
+use crate::pricing::CURRENCY;
+use crate::store::VendorStore;
+
+/// Quantities above zero and at most this are low stock.
+pub const LOW_STOCK: f64 = 5.0;
+
+/// Names the vendor in exported items, ahead of the schema's fields.
+pub const VENDOR_COLUMN: &str = "Vendor";
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StockLevel {
+    #[default]
+    Any,
+    InStock,
+    Low,
+    /// Zero, negative or no quantity at all.
+    OutOfStock,
+}
+
+impl StockLevel {
+    fn matches(self, quantity: Option<f64>) -> bool {
+        match self {
+            StockLevel::Any => true,
+            StockLevel::InStock => quantity.is_some_and(|q| q > 0.0),
+            StockLevel::Low => quantity.is_some_and(|q| q > 0.0 && q <= LOW_STOCK),
+            StockLevel::OutOfStock => quantity.is_none_or(|q| q <= 0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Vendor,
+    Key,
+    Catalog,
+    Brand,
+    Description,
+    Quantity,
+}
+
+/// One page of the catalog; unset filters match everything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogQuery {
+    /// Words that must each appear in the description or brand, ignoring case.
+    pub search: String,
+    pub vendor: Option<String>,
+    pub brand: Option<String>,
+    pub catalog: Option<String>,
+    pub stock: StockLevel,
+    pub sort: SortField,
+    pub descending: bool,
+    /// Zero-based.
+    pub page: usize,
+    pub page_size: usize,
+}
+
+impl Default for CatalogQuery {
+    fn default() -> Self {
+        CatalogQuery {
+            search: String::new(),
+            vendor: None,
+            brand: None,
+            catalog: None,
+            stock: StockLevel::Any,
+            sort: SortField::Vendor,
+            descending: false,
+            page: 0,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// A row of a vendor's last accepted upload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogItem {
+    pub vendor_name: String,
+    pub key: String,
+    pub catalog: String,
+    pub brand: String,
+    pub description: String,
+    /// `None` when the file left it empty.
+    pub quantity: Option<f64>,
+    pub ecommerce: String,
+    /// Prices as plain numbers, empty when the vendor sends none.
+    #[serde(default)]
+    pub cost: String,
+    #[serde(default)]
+    pub msrp: String,
+    #[serde(default)]
+    pub map: String,
+    #[serde(default)]
+    pub currency: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogPage {
+    pub items: Vec<CatalogItem>,
+    /// Items matching the query, on all pages.
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    /// Every vendor, brand and catalog in the catalog, to filter by.
+    pub vendors: Vec<String>,
+    pub brands: Vec<String>,
+    pub catalogs: Vec<String>,
+}
+
+/// An item with where each of its fields came from in the vendor's file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemDetail {
+    pub item: CatalogItem,
+    /// Target field -> value, for every field the vendor maps, `File Key` first.
+    pub fields: Vec<(String, String)>,
+    pub mapping: Mapping,
+}
+
+fn item(vendor_name: &str, key: &str, fields: &BTreeMap<String, String>) -> CatalogItem {
+    let field = |name: &str| fields.get(name).cloned().unwrap_or_default();
+    CatalogItem {
+        vendor_name: vendor_name.to_string(),
+        key: key.to_string(),
+        catalog: field("Catalog"),
+        brand: field("Brand"),
+        description: field("Description"),
+        quantity: fields.get("Quantity").and_then(|q| q.parse().ok()),
+        ecommerce: field("Ecommerce"),
+        cost: field("Cost"),
+        msrp: field("MSRP"),
+        map: field("MAP"),
+        currency: field(CURRENCY),
+    }
+}
+
+/// Everything the vendors' saved uploads hold, read from the vendor store.
+pub struct Catalog {
+    items: Vec<CatalogItem>,
+}
+
+impl Catalog {
+    pub fn load(store: &VendorStore) -> Result<Self, String> {
+        let mut items = Vec::new();
+        for name in store.list()? {
+            let Some(record) = store.load(&name)? else { continue };
+            items.extend(record.dataset.rows.iter().map(|(key, fields)| item(&record.vendor_name, key, fields)));
+        }
+        Ok(Catalog { items })
+    }
+
+    /// Every item matching `query`, sorted; its page is ignored.
+    fn matching(&self, query: &CatalogQuery) -> Vec<&CatalogItem> {
+        let words: Vec<String> = query.search.split_whitespace().map(str::to_lowercase).collect();
+        let same = |value: &str, wanted: &Option<String>| wanted.as_ref().is_none_or(|wanted| value.eq_ignore_ascii_case(wanted));
+        let mut matches: Vec<&CatalogItem> = self
+            .items
+            .iter()
+            .filter(|item| {
+                let text = format!("{} {}", item.description, item.brand).to_lowercase();
+                words.iter().all(|word| text.contains(word))
+                    && same(&item.vendor_name, &query.vendor)
+                    && same(&item.brand, &query.brand)
+                    && same(&item.catalog, &query.catalog)
+                    && query.stock.matches(item.quantity)
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            compare(a, b, query.sort, query.descending)
+                .then_with(|| a.vendor_name.cmp(&b.vendor_name))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        matches
+    }
+
+    pub fn query(&self, query: &CatalogQuery) -> CatalogPage {
+        let matches = self.matching(query);
+        let page_size = query.page_size.clamp(1, MAX_PAGE_SIZE);
+        let facet = |value: fn(&CatalogItem) -> &String| -> Vec<String> {
+            self.items
+                .iter()
+                .map(value)
+                .filter(|v| !v.is_empty())
+                .cloned()
+                .collect::<BTreeSet<String>>()
+                .into_iter()
+                .collect()
+        };
+        CatalogPage {
+            total: matches.len(),
+            items: matches.into_iter().skip(query.page * page_size).take(page_size).cloned().collect(),
+            page: query.page,
+            page_size,
+            vendors: facet(|item| &item.vendor_name),
+            brands: facet(|item| &item.brand),
+            catalogs: facet(|item| &item.catalog),
+        }
+    }
+
+    /// All items matching `query`, not just one page, for export.
+    pub fn table(&self, query: &CatalogQuery) -> Table {
+        let columns = std::iter::once(VENDOR_COLUMN).chain(dataset::TARGET_FIELDS).chain([CURRENCY]);
+        Table {
+            columns: columns.map(String::from).collect(),
+            rows: self
+                .matching(query)
+                .into_iter()
+                .map(|item| {
+                    vec![
+                        item.vendor_name.clone(),
+                        item.key.clone(),
+                        item.catalog.clone(),
+                        item.brand.clone(),
+                        item.description.clone(),
+                        item.quantity.map(|q| q.to_string()).unwrap_or_default(),
+                        item.ecommerce.clone(),
+                        item.cost.clone(),
+                        item.msrp.clone(),
+                        item.map.clone(),
+                        item.currency.clone(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The catalog as last loaded, read again only once a vendor was saved since.
+#[derive(Default)]
+pub struct CatalogCache {
+    loaded: Mutex<Option<(u64, Arc<Catalog>)>>,
+}
+
+impl CatalogCache {
+    pub fn get(&self, store: &VendorStore) -> Result<Arc<Catalog>, String> {
+        let mut loaded = self.loaded.lock().unwrap();
+        // Read before loading, so a save during the load makes the next call load again.
+        let generation = store.generation();
+        match loaded.as_ref() {
+            Some((at, catalog)) if *at == generation => Ok(catalog.clone()),
+            _ => {
+                let catalog = Arc::new(Catalog::load(store)?);
+                *loaded = Some((generation, catalog.clone()));
+                Ok(catalog)
+            }
+        }
+    }
+}
+
+pub fn detail(store: &VendorStore, vendor_name: &str, key: &str) -> Result<Option<ItemDetail>, String> {
+    let Some(record) = store.load(vendor_name)? else { return Ok(None) };
+    let Some(fields) = record.dataset.rows.get(key) else { return Ok(None) };
+    Ok(Some(ItemDetail {
+        item: item(&record.vendor_name, key, fields),
+        fields: std::iter::once((dataset::FILE_KEY.to_string(), key.to_string()))
+            .chain(fields.clone())
+            .collect(),
+        mapping: dataset::parse_mappings(&record.file_mappings),
+    }))
+}
+
+fn compare(a: &CatalogItem, b: &CatalogItem, field: SortField, descending: bool) -> Ordering {
+    let order = |ordering: Ordering| if descending { ordering.reverse() } else { ordering };
+    let text = |a: &str, b: &str| order(a.to_lowercase().cmp(&b.to_lowercase()));
+    match field {
+        SortField::Vendor => text(&a.vendor_name, &b.vendor_name),
+        SortField::Key => text(&a.key, &b.key),
+        SortField::Catalog => text(&a.catalog, &b.catalog),
+        SortField::Brand => text(&a.brand, &b.brand),
+        SortField::Description => text(&a.description, &b.description),
+        // Items without a quantity sort after all others, either way round.
+        SortField::Quantity => match (a.quantity, b.quantity) {
+            (Some(a), Some(b)) => order(a.total_cmp(&b)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+    }
+}
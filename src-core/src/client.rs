@@ -0,0 +1,340 @@
+use std::{env, fs, path::Path, time::Duration};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::{sleep, timeout};
+
+use crate::http::{HttpClient, HttpConfig};
+use crate::logging::LogConfig;
+use crate::protocol::{Reply, Session, ACTION, IDEMPOTENCY_KEY, SESSION_TOKEN};
+use crate::source::{FileSource, Payload};
+use crate::transport::{Connection, Transport};
+
+/// Environment variable locating the ingest service: a Unix socket path, or
+/// `tcp://host:port` / `tls://host:port`.
+pub const SOCKET_ENV: &str = "SOCKET_LOCATION";
+
+/// File in the app's data directory holding the [`ClientConfig`].
+pub const CLIENT_CONFIG: &str = "client.json";
+
+/// Contents of `client.json`: where the ingest service is, how patiently to
+/// talk to it and how long operators stay signed in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+    /// Takes precedence over `SOCKET_LOCATION` when set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport: Option<Transport>,
+    /// Talk to an HTTP API instead; takes precedence over `transport`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpConfig>,
+    /// Operators are signed out after this long without activity.
+    pub idle_timeout_minutes: u64,
+    /// Account watched folders upload as, since files arrive whether or not
+    /// an operator is signed in. Without one they upload without a session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watch_account: Option<WatchAccount>,
+    /// The service predates reply objects: it answers in plain text, or closes
+    /// the connection without a reply, once it has the request. Either then
+    /// counts as accepted.
+    pub legacy_replies: bool,
+    pub log: LogConfig,
+    #[serde(flatten)]
+    pub policy: RetryPolicy,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            transport: None,
+            http: None,
+            idle_timeout_minutes: 15,
+            watch_account: None,
+            legacy_replies: false,
+            log: LogConfig::default(),
+            policy: RetryPolicy::default(),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Reads the config from `path`, falling back to the defaults when the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(ClientConfig::default());
+        }
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_minutes * 60)
+    }
+
+    pub fn client(&self) -> Result<IngestClient, String> {
+        if let Some(http) = &self.http {
+            return Ok(HttpClient::new(http.clone(), self.policy.clone())?.into());
+        }
+        let client = match &self.transport {
+            Some(transport) => SocketClient::new(transport.clone()),
+            None => SocketClient::from_env()?,
+        };
+        Ok(IngestClient::Socket(client.with_policy(self.policy.clone()).with_legacy_replies(self.legacy_replies)))
+    }
+}
+
+/// Credentials of the operator account watched folders sign in as.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchAccount {
+    pub username: String,
+    pub password: String,
+}
+
+/// Timeouts and retries for requests to the ingest service. Only requests
+/// that are safe to repeat are retried: pings, and uploads carrying an
+/// idempotency key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub connect_timeout_ms: u64,
+    /// Longest a single write may stall while the request is sent.
+    pub write_timeout_ms: u64,
+    /// How long to wait for the reply once the request is sent; the service
+    /// validates the whole file before answering.
+    pub read_timeout_ms: u64,
+    /// Attempts in total, including the first.
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            connect_timeout_ms: 5_000,
+            write_timeout_ms: 30_000,
+            read_timeout_ms: 120_000,
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (starting at 1): exponential backoff
+    /// capped at `max_backoff_ms`, with full jitter so clients that failed
+    /// together do not retry together.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .initial_backoff_ms
+            .saturating_mul(1u64.checked_shl(retry.saturating_sub(1)).unwrap_or(u64::MAX))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(fastrand::u64(0..=ceiling))
+    }
+
+    /// Runs `attempt` until it succeeds, retrying failures only when the
+    /// request is `idempotent`. Errors from `attempt` must be ones worth retrying.
+    pub(crate) async fn retry<T, F, Fut>(&self, idempotent: bool, mut attempt: F) -> Result<T, String>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, String>>,
+    {
+        let attempts = if idempotent { self.max_attempts.max(1) } else { 1 };
+        let mut retry = 0;
+        loop {
+            match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(e) if retry + 1 >= attempts => {
+                    return Err(match retry {
+                        0 => e,
+                        _ => format!("{} (after {} attempts)", e, retry + 1),
+                    })
+                }
+                Err(e) => {
+                    retry += 1;
+                    tracing::warn!(error = %e, retry, "ingest request failed, retrying");
+                    sleep(self.backoff(retry)).await;
+                }
+            }
+        }
+    }
+}
+
+/// Connection to the ingest service over any [`Transport`].
+#[derive(Debug, Clone)]
+pub struct SocketClient {
+    transport: Transport,
+    policy: RetryPolicy,
+    session_token: Option<String>,
+    legacy_replies: bool,
+}
+
+impl SocketClient {
+    pub fn new(transport: impl Into<Transport>) -> Self {
+        SocketClient {
+            transport: transport.into(),
+            policy: RetryPolicy::default(),
+            session_token: None,
+            legacy_replies: false,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let location = env::var(SOCKET_ENV).map_err(|e| format!("{}: {}", SOCKET_ENV, e))?;
+        Ok(SocketClient::new(Transport::parse(&location)?))
+    }
+
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Takes a connection closed without a reply as an accepted request, as
+    /// services that predate replies answer.
+    pub fn with_legacy_replies(mut self, legacy_replies: bool) -> Self {
+        self.legacy_replies = legacy_replies;
+        self
+    }
+
+    /// Attaches the operator's session to every request.
+    pub fn with_session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+
+    async fn connect(&self) -> Result<Box<dyn Connection>, String> {
+        let connect = self.transport.connect();
+        match timeout(Duration::from_millis(self.policy.connect_timeout_ms), connect).await {
+            Ok(stream) => stream,
+            Err(_) => Err(format!("{}: connection timed out", self.transport)),
+        }
+    }
+
+    /// Checks that the service accepts connections.
+    pub async fn ping(&self) -> Result<(), String> {
+        self.policy.retry(true, || async { self.connect().await.map(|_| ()) }).await
+    }
+
+    /// Sends `fields` with the file streamed in as `file_bytes` and returns
+    /// the service's reply message. Retried on connection failures and
+    /// timeouts when `fields` carries an idempotency key, never once the
+    /// service has answered.
+    pub async fn send(&self, mut fields: Map<String, Value>, source: &FileSource) -> Result<String, String> {
+        if let Some(token) = &self.session_token {
+            fields.insert(SESSION_TOKEN.to_string(), Value::String(token.clone()));
+        }
+        let idempotent = fields.contains_key(IDEMPOTENCY_KEY);
+        let reply = self.policy.retry(idempotent, || self.attempt(&fields, Some(source))).await?;
+        reply.into_result()
+    }
+
+    /// Signs an operator in. The session is only valid with this service.
+    pub async fn login(&self, username: &str, password: &str) -> Result<Session, String> {
+        let mut fields = Map::new();
+        fields.insert(ACTION.to_string(), Value::from("login"));
+        fields.insert("username".to_string(), Value::from(username));
+        fields.insert("password".to_string(), Value::from(password));
+        let reply = self.policy.retry(true, || self.attempt(&fields, None)).await?;
+        reply.into_session()
+    }
+
+    /// Ends the session this client carries.
+    pub async fn logout(&self) -> Result<(), String> {
+        let Some(token) = &self.session_token else {
+            return Ok(());
+        };
+        let mut fields = Map::new();
+        fields.insert(ACTION.to_string(), Value::from("logout"));
+        fields.insert(SESSION_TOKEN.to_string(), Value::from(token.as_str()));
+        let reply = self.policy.retry(true, || self.attempt(&fields, None)).await?;
+        reply.into_result().map(|_| ())
+    }
+
+    /// Sends `fields`, with the file appended when there is one.
+    async fn attempt(&self, fields: &Map<String, Value>, source: Option<&FileSource>) -> Result<Reply, String> {
+        let chunks: Box<dyn Iterator<Item = Result<Vec<u8>, String>> + Send + '_> = match source {
+            Some(source) => Box::new(Payload::new(fields, source)?),
+            None => Box::new(std::iter::once(serde_json::to_vec(fields).map_err(|e| e.to_string()))),
+        };
+        let mut stream = self.connect().await?;
+        let write_timeout = Duration::from_millis(self.policy.write_timeout_ms);
+        for chunk in chunks {
+            match timeout(write_timeout, stream.write_all(&chunk?)).await {
+                Ok(written) => written.map_err(|e| e.to_string())?,
+                Err(_) => return Err("Timed out sending the request to the ingest service".to_string()),
+            }
+        }
+        stream.shutdown().await.map_err(|e| e.to_string())?;
+
+        let mut reply = Vec::new();
+        let read = stream.read_to_end(&mut reply);
+        match timeout(Duration::from_millis(self.policy.read_timeout_ms), read).await {
+            Ok(read) => read.map_err(|e| e.to_string())?,
+            Err(_) => return Err("Timed out waiting for the ingest service to reply".to_string()),
+        };
+        if reply.is_empty() && self.legacy_replies {
+            return Ok(Reply::ok("Sent to the ingest service"));
+        }
+        if reply.is_empty() {
+            return Err("The ingest service closed the connection without a reply".to_string());
+        }
+        Reply::parse(&reply, self.legacy_replies)
+    }
+}
+
+/// The ingest service behind whichever protocol it speaks.
+#[derive(Debug, Clone)]
+pub enum IngestClient {
+    Socket(SocketClient),
+    Http(Box<HttpClient>),
+}
+
+impl IngestClient {
+    pub async fn ping(&self) -> Result<(), String> {
+        match self {
+            IngestClient::Socket(client) => client.ping().await,
+            IngestClient::Http(client) => client.ping().await,
+        }
+    }
+
+    pub async fn send(&self, fields: Map<String, Value>, source: &FileSource) -> Result<String, String> {
+        match self {
+            IngestClient::Socket(client) => client.send(fields, source).await,
+            IngestClient::Http(client) => client.send(fields, source).await,
+        }
+    }
+
+    pub async fn login(&self, username: &str, password: &str) -> Result<Session, String> {
+        match self {
+            IngestClient::Socket(client) => client.login(username, password).await,
+            IngestClient::Http(client) => client.login(username, password).await,
+        }
+    }
+
+    pub async fn logout(&self) -> Result<(), String> {
+        match self {
+            IngestClient::Socket(client) => client.logout().await,
+            IngestClient::Http(client) => client.logout().await,
+        }
+    }
+
+    pub fn with_session_token(self, token: impl Into<String>) -> Self {
+        match self {
+            IngestClient::Socket(client) => IngestClient::Socket(client.with_session_token(token)),
+            IngestClient::Http(client) => IngestClient::Http(Box::new(client.with_session_token(token))),
+        }
+    }
+}
+
+impl From<SocketClient> for IngestClient {
+    fn from(client: SocketClient) -> Self {
+        IngestClient::Socket(client)
+    }
+}
+
+impl From<HttpClient> for IngestClient {
+    fn from(client: HttpClient) -> Self {
+        IngestClient::Http(Box::new(client))
+    }
+}
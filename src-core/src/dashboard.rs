@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{AuditAction, AuditEntry};
+use crate::store::{Snapshot, VendorRecord};
+
+/// Vendors listed by volume.
+pub const TOP_VENDORS: usize = 5;
+
+/// Where "today" and "this week" begin, in seconds since the Unix epoch. The
+/// UI works them out, since only it knows the operator's calendar.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Periods {
+    pub today: u64,
+    pub week: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VendorVolume {
+    pub vendor_name: String,
+    pub uploads: usize,
+    pub rows: usize,
+}
+
+/// Stock over time, one point per accepted upload.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VendorSeries {
+    pub vendor_name: String,
+    pub points: Vec<Snapshot>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub vendors: usize,
+    pub uploads_today: usize,
+    pub uploads_this_week: usize,
+    /// Failed uploads and rejected watched files since the start of the week.
+    pub failed_this_week: usize,
+    /// Rows of every accepted upload, counting full files for delta uploads.
+    pub rows_ingested: usize,
+    /// By rows ingested, most first.
+    pub top_vendors: Vec<VendorVolume>,
+    /// Vendors with at least one accepted upload, by name.
+    pub series: Vec<VendorSeries>,
+}
+
+impl Metrics {
+    /// Uploads come from the vendors' history and failures from the audit log.
+    pub fn compute(records: &[VendorRecord], audit: &[AuditEntry], periods: Periods) -> Self {
+        let mut metrics = Metrics {
+            vendors: records.len(),
+            ..Metrics::default()
+        };
+        let mut volumes = BTreeMap::new();
+        for record in records {
+            let volume = volumes.entry(record.vendor_name.clone()).or_insert_with(|| VendorVolume {
+                vendor_name: record.vendor_name.clone(),
+                ..VendorVolume::default()
+            });
+            for snapshot in &record.history {
+                volume.uploads += 1;
+                volume.rows += snapshot.rows;
+                metrics.uploads_today += usize::from(snapshot.timestamp >= periods.today);
+                metrics.uploads_this_week += usize::from(snapshot.timestamp >= periods.week);
+            }
+            metrics.rows_ingested += volume.rows;
+            if !record.history.is_empty() {
+                metrics.series.push(VendorSeries {
+                    vendor_name: record.vendor_name.clone(),
+                    points: record.history.clone(),
+                });
+            }
+        }
+        metrics.series.sort_by(|a, b| a.vendor_name.cmp(&b.vendor_name));
+
+        let mut top: Vec<VendorVolume> = volumes.into_values().filter(|volume| volume.uploads > 0).collect();
+        top.sort_by(|a, b| b.rows.cmp(&a.rows).then_with(|| a.vendor_name.cmp(&b.vendor_name)));
+        top.truncate(TOP_VENDORS);
+        metrics.top_vendors = top;
+
+        let uploads = [AuditAction::CreateVendor, AuditAction::ChangeMapping, AuditAction::Upload];
+        for entry in audit {
+            if !entry.ok && uploads.contains(&entry.action) {
+                metrics.add_failure(entry.timestamp, periods);
+            }
+        }
+        metrics
+    }
+
+    /// Counts a failure the audit log does not hold, such as a quarantined watched file.
+    pub fn add_failure(&mut self, timestamp: u64, periods: Periods) {
+        self.failed_this_week += usize::from(timestamp >= periods.week);
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Read;
+
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::formula::{format_number, Source};
+use crate::pricing::{self, CURRENCY, PRICE_FIELDS};
+
+/// Target field that identifies a row across uploads of the same vendor.
+pub const FILE_KEY: &str = "File Key";
+
+/// Fields of the ingest schema, in the order the UI lists them.
+pub const TARGET_FIELDS: [&str; 9] = [FILE_KEY, "Catalog", "Brand", "Description", "Quantity", "Ecommerce", "Cost", "MSRP", "MAP"];
+
+/// Optional target naming the column that holds each row's warehouse, for
+/// files that list an item once per location.
+pub const LOCATION: &str = "Location";
+
+/// Rows looked at to find the decimal separator of a file's prices.
+const DETECT_ROWS: usize = 100;
+
+/// Target field of the quantity at one warehouse, as in `Quantity@North`.
+pub fn location_field(location: &str) -> String {
+    format!("Quantity@{}", location)
+}
+
+/// The warehouse of a per-location quantity field.
+pub fn location_of(field: &str) -> Option<&str> {
+    field.strip_prefix("Quantity@")
+}
+
+/// Whether `field` can be mapped: a schema field, a warehouse quantity, the
+/// location or the currency.
+pub fn is_target(field: &str) -> bool {
+    TARGET_FIELDS.contains(&field) || field == LOCATION || field == CURRENCY || location_of(field).is_some()
+}
+
+pub fn is_quantity(field: &str) -> bool {
+    field == "Quantity" || location_of(field).is_some()
+}
+
+/// Target field name -> source column header, or a formula (see [`crate::formula`]).
+pub type Mapping = BTreeMap<String, String>;
+
+/// Parses the `"Target:Source, Target:Source"` string sent by the UI.
+pub fn parse_mappings(mappings: &str) -> Mapping {
+    let mut mapping = Mapping::new();
+    let mut rest = mappings;
+    while !rest.is_empty() {
+        let Some((target, spec)) = rest.split_once(':').filter(|(target, _)| !target.contains(", ")) else {
+            // No target on this pair, skip it.
+            rest = rest.split_once(", ").map_or("", |(_, rest)| rest);
+            continue;
+        };
+        let end = crate::formula::spec_len(spec);
+        if !target.is_empty() && end > 0 {
+            mapping.insert(target.to_string(), spec[..end].to_string());
+        }
+        rest = spec[end..].strip_prefix(", ").unwrap_or_default();
+    }
+    mapping
+}
+
+pub fn format_mappings(mapping: &Mapping) -> String {
+    mapping
+        .iter()
+        .map(|(target, source)| format!("{}:{}", target, source))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Header spellings vendors commonly use for each target field, normalized.
+fn synonyms(target: &str) -> &'static [&'static str] {
+    match target {
+        FILE_KEY => &["filekey", "key", "sku", "partnumber", "partno", "itemnumber", "itemno", "productid", "upc"],
+        "Catalog" => &["catalog", "catalogue", "category", "productline"],
+        "Brand" => &["brand", "manufacturer", "mfr", "make"],
+        "Description" => &["description", "desc", "productname", "name", "title"],
+        "Quantity" => &["quantity", "qty", "stock", "onhand", "available", "inventory"],
+        "Ecommerce" => &["ecommerce", "ecom", "online", "web"],
+        "Cost" => &["cost", "unitcost", "dealercost", "netprice", "dealerprice", "wholesale"],
+        "MSRP" => &["msrp", "listprice", "retailprice", "rrp"],
+        "MAP" => &["map", "mapprice", "minimumadvertisedprice"],
+        _ => &[],
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Picks a source column for each target field. A saved mapping wins when all
+/// of its columns are present; otherwise headers are matched by name.
+pub fn detect_mapping(headers: &[String], saved: Option<&Mapping>) -> Mapping {
+    if let Some(saved) = saved {
+        let present = |spec: &String| {
+            Source::parse(spec).is_ok_and(|source| source.columns().iter().all(|column| headers.iter().any(|h| h == column)))
+        };
+        if !saved.is_empty() && saved.values().all(present) {
+            return saved.clone();
+        }
+    }
+    let normalized: Vec<String> = headers.iter().map(|h| normalize_header(h)).collect();
+    let mut mapping = Mapping::new();
+    for target in TARGET_FIELDS {
+        let names = synonyms(target);
+        let exact = normalized.iter().position(|h| names.contains(&h.as_str()));
+        let partial = || normalized.iter().position(|h| names.iter().any(|name| h.contains(name)));
+        if let Some(index) = exact.or_else(partial) {
+            if !mapping.values().any(|source| source == &headers[index]) {
+                mapping.insert(target.to_string(), headers[index].clone());
+            }
+        }
+    }
+    mapping
+}
+
+pub fn read_headers(reader: impl Read) -> Result<Vec<String>, String> {
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+    let headers = rdr.headers().map_err(|e| e.to_string())?;
+    Ok(headers.iter().map(|h| h.to_string()).collect())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Row {
+    pub key: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+/// The mapped contents of one stock file, keyed by `FILE_KEY`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dataset {
+    pub rows: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Dataset {
+    /// Rows sharing a key in a file with a `LOCATION` column are one item,
+    /// with a quantity per location. Items with quantities per location get
+    /// their total as `Quantity`. Prices, read with the `decimal` separator,
+    /// become plain numbers in a `Currency`.
+    pub fn from_csv(reader: impl Read, mapping: &Mapping, decimal: Option<char>) -> Result<Self, String> {
+        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+        let headers = rdr.headers().map_err(|e| e.to_string())?.clone();
+        let mapper = RowMapper::new(&headers, mapping).map_err(|errors| errors.join("\n"))?;
+        if !mapping.contains_key(FILE_KEY) {
+            return Err(format!("\"{}\" is not mapped", FILE_KEY));
+        }
+
+        let mut rows: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for (index, record) in rdr.records().enumerate() {
+            let record = record.map_err(|e| e.to_string())?;
+            let mut fields = mapper.fields(&record).map_err(|e| format!("Line {}: {}", index + 2, e))?;
+            mapper.normalize_prices(&mut fields, decimal).map_err(|e| format!("Line {}: {}", index + 2, e))?;
+            let key = fields.remove(FILE_KEY).unwrap_or_default();
+            if key.is_empty() {
+                continue;
+            }
+            match fields.remove(LOCATION) {
+                Some(location) => {
+                    let quantity = fields.remove("Quantity").unwrap_or_default();
+                    rows.entry(key).or_insert(fields).insert(location_field(&location), quantity);
+                }
+                None => {
+                    rows.insert(key, fields);
+                }
+            }
+        }
+        for fields in rows.values_mut() {
+            // A location without a quantity is left out, whichever way the file lists it.
+            fields.retain(|field, quantity| location_of(field).is_none() || !quantity.is_empty());
+            let quantities: Vec<&String> = fields.iter().filter(|(field, _)| location_of(field).is_some()).map(|(_, q)| q).collect();
+            if !quantities.is_empty() {
+                let total: f64 = quantities.iter().filter_map(|q| q.parse::<f64>().ok()).sum();
+                fields.insert("Quantity".to_string(), format_number(total));
+            }
+        }
+        Ok(Dataset { rows })
+    }
+
+    /// One row per item and one column per field, named after it, with the
+    /// plain mapping that reads it back.
+    pub fn to_csv(&self) -> Result<(Vec<u8>, Mapping), String> {
+        let fields: BTreeSet<&String> = self.rows.values().flat_map(|fields| fields.keys()).collect();
+        let mut wtr = WriterBuilder::new().from_writer(Vec::new());
+        wtr.write_record(std::iter::once(FILE_KEY).chain(fields.iter().map(|field| field.as_str())))
+            .map_err(|e| e.to_string())?;
+        for (key, values) in &self.rows {
+            let values = fields.iter().map(|field| values.get(*field).map(String::as_str).unwrap_or_default());
+            wtr.write_record(std::iter::once(key.as_str()).chain(values)).map_err(|e| e.to_string())?;
+        }
+        let bytes = wtr.into_inner().map_err(|e| e.to_string())?;
+        let plain = std::iter::once(FILE_KEY)
+            .chain(fields.iter().map(|field| field.as_str()))
+            .map(|field| (field.to_string(), field.to_string()))
+            .collect();
+        Ok((bytes, plain))
+    }
+
+    /// Every warehouse some item has a quantity at.
+    pub fn locations(&self) -> BTreeSet<String> {
+        self.rows
+            .values()
+            .flat_map(|fields| fields.keys().filter_map(|field| location_of(field)))
+            .map(String::from)
+            .collect()
+    }
+
+    fn row(&self, key: &str) -> Row {
+        Row {
+            key: key.to_string(),
+            fields: self.rows.get(key).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// A mapping bound to the header row of one file.
+pub struct RowMapper {
+    columns: HashMap<String, usize>,
+    fields: Vec<(String, Source)>,
+}
+
+impl RowMapper {
+    /// Fails with every formula that does not parse and every column that is not in the file.
+    pub fn new(headers: &StringRecord, mapping: &Mapping) -> Result<Self, Vec<String>> {
+        let mut columns = HashMap::new();
+        for (index, header) in headers.iter().enumerate() {
+            columns.entry(header.to_string()).or_insert(index);
+        }
+        let mut errors = Vec::new();
+        let mut fields = Vec::new();
+        for (target, spec) in mapping {
+            match Source::parse(spec) {
+                Ok(source) => {
+                    for column in source.columns().into_iter().filter(|column| !columns.contains_key(*column)) {
+                        errors.push(format!("Column \"{}\" mapped to \"{}\" is not in the file", column, target));
+                    }
+                    fields.push((target.clone(), source));
+                }
+                Err(e) => errors.push(format!("Formula for \"{}\": {}", target, e)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(RowMapper { columns, fields })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The value of `target` in `record`, empty when it is not mapped.
+    pub fn value(&self, target: &str, record: &StringRecord) -> Result<String, String> {
+        match self.fields.iter().find(|(name, _)| name == target) {
+            Some((_, source)) => self.eval(source, record),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Every mapped field of `record`, by target name.
+    pub fn fields(&self, record: &StringRecord) -> Result<BTreeMap<String, String>, String> {
+        self.fields
+            .iter()
+            .map(|(target, source)| {
+                self.eval(source, record)
+                    .map(|value| (target.clone(), value))
+                    .map_err(|e| format!("{}: {}", target, e))
+            })
+            .collect()
+    }
+
+    /// Rewrites the prices of a row's `fields`, see [`pricing::normalize`].
+    pub fn normalize_prices(&self, fields: &mut BTreeMap<String, String>, decimal: Option<char>) -> Result<(), String> {
+        let computed = |field: &str| self.fields.iter().any(|(target, source)| target == field && source.is_formula());
+        pricing::normalize(fields, &computed, decimal)
+    }
+
+    fn eval(&self, source: &Source, record: &StringRecord) -> Result<String, String> {
+        let value = |column: &str| {
+            self.columns
+                .get(column)
+                .and_then(|index| record.get(*index))
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        source.eval(&value)
+    }
+}
+
+pub fn has_formulas(mapping: &Mapping) -> bool {
+    mapping.values().any(|spec| spec.starts_with('='))
+}
+
+pub fn has_locations(mapping: &Mapping) -> bool {
+    mapping.keys().any(|target| target == LOCATION || location_of(target).is_some())
+}
+
+pub fn has_prices(mapping: &Mapping) -> bool {
+    mapping.keys().any(|target| target == CURRENCY || PRICE_FIELDS.contains(&target.as_str()))
+}
+
+/// The decimal separator the prices in the first rows of a stock file show,
+/// see [`pricing::detect_decimal`]. Prices from formulas always use `.` and
+/// say nothing about the file.
+pub fn detect_decimal(reader: impl Read, mapping: &Mapping) -> Option<char> {
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+    let headers = rdr.headers().ok()?.clone();
+    let mapper = RowMapper::new(&headers, mapping).ok()?;
+    let fields: Vec<&str> =
+        PRICE_FIELDS.into_iter().filter(|field| mapping.get(*field).is_some_and(|spec| !spec.starts_with('='))).collect();
+    let mut prices = Vec::new();
+    for record in rdr.records().take(DETECT_ROWS).flatten() {
+        prices.extend(fields.iter().filter_map(|field| mapper.value(field, &record).ok()));
+    }
+    pricing::detect_decimal(prices.iter().map(String::as_str))
+}
+
+/// Rewrites a stock file into one row per item and one column per field,
+/// named after it, and returns it with the plain mapping that reads it back.
+pub fn apply_mapping(reader: impl Read, mapping: &Mapping, decimal: Option<char>) -> Result<(Vec<u8>, Mapping), String> {
+    Dataset::from_csv(reader, mapping, decimal)?.to_csv()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RowChange {
+    pub key: String,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatasetDiff {
+    pub added: Vec<Row>,
+    pub removed: Vec<Row>,
+    pub changed: Vec<RowChange>,
+}
+
+impl DatasetDiff {
+    /// Keys of the rows that must be sent for the backend to catch up.
+    pub fn upserted_keys(&self) -> BTreeSet<String> {
+        self.added
+            .iter()
+            .map(|row| row.key.clone())
+            .chain(self.changed.iter().map(|change| change.key.clone()))
+            .collect()
+    }
+
+    pub fn removed_keys(&self) -> Vec<String> {
+        self.removed.iter().map(|row| row.key.clone()).collect()
+    }
+}
+
+pub fn diff(previous: &Dataset, current: &Dataset) -> DatasetDiff {
+    let mut result = DatasetDiff::default();
+    for (key, fields) in &current.rows {
+        match previous.rows.get(key) {
+            None => result.added.push(current.row(key)),
+            Some(old_fields) => {
+                let names: BTreeSet<&String> = old_fields.keys().chain(fields.keys()).collect();
+                let changes: Vec<FieldChange> = names
+                    .into_iter()
+                    .filter_map(|name| {
+                        let old = old_fields.get(name).cloned().unwrap_or_default();
+                        let new = fields.get(name).cloned().unwrap_or_default();
+                        (old != new).then(|| FieldChange { field: name.clone(), old, new })
+                    })
+                    .collect();
+                if !changes.is_empty() {
+                    result.changed.push(RowChange { key: key.clone(), fields: changes });
+                }
+            }
+        }
+    }
+    for key in previous.rows.keys() {
+        if !current.rows.contains_key(key) {
+            result.removed.push(previous.row(key));
+        }
+    }
+    result
+}
+
+/// Rewrites a stock file keeping the header and only the rows whose key is in `keys`.
+pub fn filter_csv(reader: impl Read, mapping: &Mapping, keys: &BTreeSet<String>) -> Result<Vec<u8>, String> {
+    if !mapping.contains_key(FILE_KEY) {
+        return Err(format!("\"{}\" is not mapped", FILE_KEY));
+    }
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+    let headers = rdr.headers().map_err(|e| e.to_string())?.clone();
+    let mapper = RowMapper::new(&headers, mapping).map_err(|errors| errors.join("\n"))?;
+
+    let mut wtr = WriterBuilder::new().from_writer(Vec::new());
+    wtr.write_record(&headers).map_err(|e| e.to_string())?;
+    for record in rdr.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        if keys.contains(&mapper.value(FILE_KEY, &record)?) {
+            wtr.write_record(&record).map_err(|e| e.to_string())?;
+        }
+    }
+    wtr.into_inner().map_err(|e| e.to_string())
+}
@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::client::CLIENT_CONFIG;
+use crate::logging::{self, LOG_DIR};
+use crate::zip::ZipWriter;
+
+/// Settings files from the data directory worth shipping, redacted.
+const CONFIG_FILES: [&str; 2] = [CLIENT_CONFIG, "watch.json"];
+
+/// Zips the log files, the redacted config and `version` for a bug report.
+pub fn collect(data_dir: &Path, version: &Value) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::default();
+    zip.add("version.json", &serde_json::to_vec_pretty(version).map_err(|e| e.to_string())?)?;
+
+    for name in CONFIG_FILES {
+        let path = data_dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let contents = match serde_json::from_str::<Value>(&text) {
+            Ok(mut config) => {
+                logging::redact(&mut config);
+                serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?
+            }
+            // Shipped as-is would risk leaking a secret from a half-edited file.
+            Err(e) => format!("Unreadable: {}", e).into_bytes(),
+        };
+        zip.add(&format!("config/{}", name), &contents)?;
+    }
+
+    let log_dir = data_dir.join(LOG_DIR);
+    if log_dir.exists() {
+        let mut logs: Vec<_> = fs::read_dir(&log_dir)
+            .map_err(|e| format!("{}: {}", log_dir.display(), e))?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect();
+        logs.sort();
+        for path in logs {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let contents = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            zip.add(&format!("logs/{}", name), &contents)?;
+        }
+    }
+    zip.finish()
+}
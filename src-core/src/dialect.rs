@@ -0,0 +1,144 @@
+use std::io::Read;
+
+use csv::{ReaderBuilder, WriterBuilder};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+use crate::source::FileSource;
+
+/// Delimiters tried when sniffing, in order of preference on a tie.
+const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Lines looked at when sniffing.
+const SNIFF_LINES: usize = 10;
+
+/// Bytes read from the start of a file to sniff it.
+const SNIFF_BYTES: u64 = 64 * 1024;
+
+/// How a vendor's stock file is written. Everything past the upload step
+/// reads and sends UTF-8 with commas, so other dialects are converted first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dialect {
+    pub delimiter: char,
+    pub quote: char,
+    /// A WHATWG encoding label, e.g. `utf-8` or `windows-1252`.
+    pub encoding: String,
+    /// Decimal separator of prices, `.` or `,`; the other one groups thousands.
+    /// Unset for vendors set up before it was asked, whose prices are guessed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimal: Option<char>,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect {
+            delimiter: ',',
+            quote: '"',
+            encoding: "utf-8".to_string(),
+            decimal: None,
+        }
+    }
+}
+
+fn ascii(c: char, what: &str) -> Result<u8, String> {
+    u8::try_from(c)
+        .ok()
+        .filter(u8::is_ascii)
+        .ok_or_else(|| format!("The {} must be a single ASCII character, not \"{}\"", what, c))
+}
+
+impl Dialect {
+    /// Whether files are written as everything past the upload step reads them.
+    pub fn is_default(&self) -> bool {
+        Dialect { decimal: None, ..self.clone() } == Dialect::default()
+    }
+
+    /// Fails when a file could not be read in this dialect at all.
+    pub fn check(&self) -> Result<(), String> {
+        ascii(self.delimiter, "delimiter")?;
+        ascii(self.quote, "quote")?;
+        if let Some(decimal) = self.decimal.filter(|c| !matches!(c, '.' | ',')) {
+            return Err(format!("The decimal separator must be \".\" or \",\", not \"{}\"", decimal));
+        }
+        self.encoding().map(|_| ())
+    }
+
+    fn encoding(&self) -> Result<&'static Encoding, String> {
+        Encoding::for_label(self.encoding.as_bytes()).ok_or_else(|| format!("Unknown encoding \"{}\"", self.encoding))
+    }
+
+    /// `source` as UTF-8 with commas. Files in another dialect are read whole
+    /// to convert them; files already in the default one are passed through.
+    pub fn normalize(&self, source: FileSource) -> Result<FileSource, String> {
+        if self.is_default() {
+            return Ok(source);
+        }
+        let encoding = self.encoding()?;
+        let (delimiter, quote) = (ascii(self.delimiter, "delimiter")?, ascii(self.quote, "quote")?);
+
+        let mut bytes = Vec::new();
+        source.reader()?.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        let (text, _, malformed) = encoding.decode(&bytes);
+        if malformed {
+            return Err(format!("The file is not valid {}", encoding.name()));
+        }
+
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .delimiter(delimiter)
+            .quote(quote)
+            .from_reader(text.as_bytes());
+        let mut wtr = WriterBuilder::new().from_writer(Vec::new());
+        for (index, record) in rdr.records().enumerate() {
+            let record = record.map_err(|e| format!("Line {}: {}", index + 1, e))?;
+            wtr.write_record(&record).map_err(|e| e.to_string())?;
+        }
+        wtr.into_inner().map(FileSource::Bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Guesses the dialect from the start of a file: the encoding from its byte
+/// order mark or whether it is valid UTF-8, and the delimiter that splits the
+/// first lines into the same number of columns.
+pub fn sniff(sample: &[u8]) -> Dialect {
+    let encoding = match Encoding::for_bom(sample) {
+        Some((encoding, _)) => encoding,
+        None => match std::str::from_utf8(sample) {
+            Ok(_) => UTF_8,
+            // Cut off mid-character at the end of the sample.
+            Err(e) if e.error_len().is_none() => UTF_8,
+            Err(_) => WINDOWS_1252,
+        },
+    };
+    let (text, _) = encoding.decode_with_bom_removal(sample);
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).take(SNIFF_LINES).collect();
+
+    let score = |delimiter: char| {
+        let counts: Vec<usize> = lines.iter().map(|line| line.matches(delimiter).count()).collect();
+        let header = counts.first().copied().unwrap_or_default();
+        // Data lines that agree with the header count for more than a busy header.
+        let consistent = counts.iter().all(|&count| count == header);
+        (header > 0, consistent, header)
+    };
+    let delimiter = DELIMITERS
+        .into_iter()
+        .rev()
+        .max_by_key(|&delimiter| score(delimiter))
+        .filter(|&delimiter| score(delimiter).0)
+        .unwrap_or(',');
+
+    Dialect {
+        delimiter,
+        quote: '"',
+        encoding: encoding.name().to_lowercase(),
+        // Found in the prices once the file is mapped, see `upload::inspect`.
+        decimal: None,
+    }
+}
+
+pub fn sniff_source(source: &FileSource) -> Result<Dialect, String> {
+    let mut sample = Vec::new();
+    source.reader()?.take(SNIFF_BYTES).read_to_end(&mut sample).map_err(|e| e.to_string())?;
+    Ok(sniff(&sample))
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::dataset::{self, Mapping};
+use crate::dialect::Dialect;
+
+/// A vendor setup saved part way through the wizard. Passwords are never
+/// kept, and files dropped into the window only by name, since their
+/// contents are not on disk.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Draft {
+    pub id: String,
+    /// Seconds since the Unix epoch, set when saved.
+    pub updated: u64,
+    /// The wizard step to resume at.
+    pub step: String,
+    pub is_new_vendor: bool,
+    pub vendor_name: String,
+    pub file_name: String,
+    pub file_path: Option<String>,
+    /// Unset until the file was read or a dialect picked.
+    pub dialect: Option<Dialect>,
+    /// Drafts saved before the mapping was kept parsed hold it as a string.
+    #[serde(alias = "file_mappings", deserialize_with = "mapping")]
+    pub mapping: Mapping,
+    pub delta_only: bool,
+}
+
+fn mapping<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Mapping, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Saved {
+        Parsed(Mapping),
+        Text(String),
+    }
+    Ok(match Saved::deserialize(deserializer)? {
+        Saved::Parsed(mapping) => mapping,
+        Saved::Text(mappings) => dataset::parse_mappings(&mappings),
+    })
+}
+
+/// One JSON file per draft under the app data directory.
+#[derive(Clone)]
+pub struct DraftStore {
+    dir: PathBuf,
+}
+
+impl DraftStore {
+    pub fn new(dir: PathBuf) -> Self {
+        DraftStore { dir }
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Invalid draft id \"{}\"", id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    /// Saves `draft`, replacing any earlier version of it, and returns it as stored.
+    pub fn save(&self, mut draft: Draft) -> Result<Draft, String> {
+        let path = self.path(&draft.id)?;
+        draft.updated = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string(&draft).map_err(|e| e.to_string())?;
+        // Drafts are autosaved while the operator works, so a crash mid-write
+        // must leave the previous version rather than half of the new one.
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, json).map_err(|e| e.to_string())?;
+        fs::rename(partial, path).map_err(|e| e.to_string())?;
+        Ok(draft)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        let path = self.path(id)?;
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Newest first. Files that no longer parse are skipped.
+    pub fn list(&self) -> Result<Vec<Draft>, String> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut drafts = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let json = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+                if let Ok(draft) = serde_json::from_str::<Draft>(&json) {
+                    drafts.push(draft);
+                }
+            }
+        }
+        drafts.sort_by(|a, b| b.updated.cmp(&a.updated).then_with(|| a.id.cmp(&b.id)));
+        Ok(drafts)
+    }
+}
@@ -0,0 +1,140 @@
+use rust_xlsxwriter::Workbook;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::dataset::{self, Dataset, Mapping, FILE_KEY, TARGET_FIELDS};
+use crate::pricing::{CURRENCY, PRICE_FIELDS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// Rows to export, every value already mapped and trimmed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// The file key, then each mapped field in schema order, then the
+    /// currency of the prices and the quantity at each location.
+    pub fn from_dataset(dataset: &Dataset, mapping: &Mapping) -> Self {
+        let locations: Vec<String> = dataset.locations().iter().map(|location| dataset::location_field(location)).collect();
+        let priced = dataset.rows.values().any(|values| values.contains_key(CURRENCY));
+        let fields: Vec<&str> = TARGET_FIELDS
+            .into_iter()
+            .filter(|field| *field != FILE_KEY && (mapping.contains_key(*field) || (*field == "Quantity" && !locations.is_empty())))
+            .chain(priced.then_some(CURRENCY))
+            .chain(locations.iter().map(String::as_str))
+            .collect();
+        Table {
+            columns: std::iter::once(FILE_KEY).chain(fields.iter().copied()).map(String::from).collect(),
+            rows: dataset
+                .rows
+                .iter()
+                .map(|(key, values)| {
+                    std::iter::once(key.clone())
+                        .chain(fields.iter().map(|field| values.get(*field).cloned().unwrap_or_default()))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Keeps only `columns`, in that order.
+    pub fn select(self, columns: &[String]) -> Result<Table, String> {
+        if columns.is_empty() {
+            return Err("Pick at least one column to export".to_string());
+        }
+        let mut indexes = Vec::new();
+        for column in columns {
+            let index = self
+                .columns
+                .iter()
+                .position(|c| c == column)
+                .ok_or_else(|| format!("There is no column \"{}\" to export", column))?;
+            if indexes.contains(&index) {
+                return Err(format!("Column \"{}\" is picked twice", column));
+            }
+            indexes.push(index);
+        }
+        Ok(Table {
+            columns: columns.to_vec(),
+            rows: self
+                .rows
+                .into_iter()
+                .map(|row| indexes.iter().map(|&i| row[i].clone()).collect())
+                .collect(),
+        })
+    }
+
+    pub fn write(&self, format: ExportFormat) -> Result<Vec<u8>, String> {
+        match format {
+            ExportFormat::Csv => {
+                let mut wtr = csv::Writer::from_writer(Vec::new());
+                wtr.write_record(&self.columns).map_err(|e| e.to_string())?;
+                for row in &self.rows {
+                    wtr.write_record(row).map_err(|e| e.to_string())?;
+                }
+                wtr.into_inner().map_err(|e| e.to_string())
+            }
+            ExportFormat::Jsonl => {
+                let mut out = Vec::new();
+                for row in &self.rows {
+                    serde_json::to_writer(&mut out, &JsonRow { columns: &self.columns, values: row }).map_err(|e| e.to_string())?;
+                    out.push(b'\n');
+                }
+                Ok(out)
+            }
+            ExportFormat::Xlsx => self.xlsx(),
+        }
+    }
+
+    /// One sheet named "Export", with the column names as its first row.
+    fn xlsx(&self) -> Result<Vec<u8>, String> {
+        // Quantities and prices are written as numbers when they are one.
+        let numeric: Vec<bool> =
+            self.columns.iter().map(|c| dataset::is_quantity(c) || PRICE_FIELDS.contains(&c.as_str())).collect();
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Export").map_err(|e| e.to_string())?;
+        for (r, row) in std::iter::once(&self.columns).chain(&self.rows).enumerate() {
+            let r = u32::try_from(r).map_err(|_| "Too many rows for a spreadsheet".to_string())?;
+            for (c, value) in row.iter().enumerate().filter(|(_, value)| !value.is_empty()) {
+                let c = u16::try_from(c).map_err(|_| "Too many columns for a spreadsheet".to_string())?;
+                match value.parse::<f64>() {
+                    Ok(number) if r > 0 && numeric[c as usize] && number.is_finite() => sheet.write_number(r, c, number),
+                    _ => sheet.write_string(r, c, value),
+                }
+                .map_err(|e| e.to_string())?;
+            }
+        }
+        workbook.save_to_buffer().map_err(|e| e.to_string())
+    }
+}
+
+/// A row as a JSON object, its fields in column order; a JSON map would sort them.
+struct JsonRow<'a> {
+    columns: &'a [String],
+    values: &'a [String],
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.columns.iter().zip(self.values))
+    }
+}
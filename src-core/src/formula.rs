@@ -0,0 +1,357 @@
+//! Where a target field takes its value from: a source column, or a formula
+//! starting with `=` such as `=concat(" ", [Brand], [Model])`, `=sum([WH1], [WH2])`,
+//! `=first([EAN], [UPC])`, `="Acme"` or `=[Qty] - [Reserved]`.
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    /// Joins the non-empty values with the separator given as first argument.
+    Concat,
+    Sum,
+    /// The first non-empty value.
+    First,
+}
+
+impl Function {
+    fn named(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "concat" => Some(Function::Concat),
+            "sum" => Some(Function::Sum),
+            "first" => Some(Function::First),
+            _ => None,
+        }
+    }
+
+    /// Why `args` is not enough to call the function, if it is not.
+    fn check_args(self, args: usize) -> Result<(), String> {
+        match self {
+            Function::Concat if args < 2 => Err("concat needs a separator and at least one value".to_string()),
+            Function::Sum | Function::First if args < 1 => Err(format!("{:?} needs at least one value", self).to_lowercase()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Text(String),
+    Number(f64),
+    Column(String),
+    Negate(Box<Expr>),
+    /// `+`, `-`, `*` or `/`.
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Column(String),
+    Formula(Expr),
+}
+
+impl Source {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        match spec.strip_prefix('=') {
+            Some(formula) => Parser::new(formula)?.formula().map(Source::Formula),
+            None => Ok(Source::Column(spec.to_string())),
+        }
+    }
+
+    pub fn is_formula(&self) -> bool {
+        matches!(self, Source::Formula(_))
+    }
+
+    /// Every column the source reads.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns = Vec::new();
+        match self {
+            Source::Column(column) => columns.push(column.as_str()),
+            Source::Formula(expr) => expr.collect_columns(&mut columns),
+        }
+        columns
+    }
+
+    /// The value for one row; `value` returns the trimmed contents of a column.
+    pub fn eval(&self, value: &dyn Fn(&str) -> String) -> Result<String, String> {
+        match self {
+            Source::Column(column) => Ok(value(column)),
+            Source::Formula(expr) => expr.eval(value),
+        }
+    }
+}
+
+impl Expr {
+    fn collect_columns<'a>(&'a self, columns: &mut Vec<&'a str>) {
+        match self {
+            Expr::Text(_) | Expr::Number(_) => {}
+            Expr::Column(column) => columns.push(column),
+            Expr::Negate(expr) => expr.collect_columns(columns),
+            Expr::Binary(_, left, right) => {
+                left.collect_columns(columns);
+                right.collect_columns(columns);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.collect_columns(columns)),
+        }
+    }
+
+    fn eval(&self, value: &dyn Fn(&str) -> String) -> Result<String, String> {
+        match self {
+            Expr::Text(text) => Ok(text.clone()),
+            Expr::Number(number) => Ok(format_number(*number)),
+            Expr::Column(column) => Ok(value(column)),
+            Expr::Negate(expr) => Ok(format_number(-number(&expr.eval(value)?)?)),
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (number(&left.eval(value)?)?, number(&right.eval(value)?)?);
+                let result = match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    _ if right == 0.0 => return Err("Division by zero".to_string()),
+                    _ => left / right,
+                };
+                Ok(format_number(result))
+            }
+            Expr::Call(function, args) => {
+                let values = args.iter().map(|arg| arg.eval(value)).collect::<Result<Vec<String>, String>>()?;
+                match function {
+                    Function::Concat => {
+                        let parts: Vec<&str> = values[1..].iter().map(String::as_str).filter(|v| !v.is_empty()).collect();
+                        Ok(parts.join(&values[0]))
+                    }
+                    Function::Sum => values.iter().map(|v| number(v)).sum::<Result<f64, String>>().map(format_number),
+                    Function::First => Ok(values.into_iter().find(|v| !v.is_empty()).unwrap_or_default()),
+                }
+            }
+        }
+    }
+}
+
+/// Empty values count as zero in arithmetic.
+fn number(value: &str) -> Result<f64, String> {
+    if value.is_empty() {
+        return Ok(0.0);
+    }
+    value.parse().map_err(|_| format!("\"{}\" is not a number", value))
+}
+
+/// Rounds away floating point noise such as `0.30000000000000004`.
+pub(crate) fn format_number(number: f64) -> String {
+    let rounded = (number * 1e6).round() / 1e6;
+    // Avoids printing "-0".
+    format!("{}", rounded + 0.0)
+}
+
+/// Length of the source spec at the start of `spec`, which ends at the first
+/// `", "` outside quotes, brackets and parentheses of a formula.
+pub(crate) fn spec_len(spec: &str) -> usize {
+    let formula = spec.starts_with('=');
+    let (mut quoted, mut bracketed, mut depth) = (false, false, 0usize);
+    let mut chars = spec.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' if formula && !bracketed => quoted = !quoted,
+            '[' if formula && !quoted => bracketed = true,
+            ']' if bracketed => {
+                if chars.peek().map(|(_, c)| *c) == Some(']') {
+                    chars.next();
+                } else {
+                    bracketed = false;
+                }
+            }
+            '(' if formula && !quoted && !bracketed => depth += 1,
+            ')' if formula && !quoted && !bracketed => depth = depth.saturating_sub(1),
+            ',' if !quoted && !bracketed && depth == 0 && spec[index + 1..].starts_with(' ') => return index,
+            _ => {}
+        }
+    }
+    spec.len()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Text(String),
+    Number(f64),
+    Column(String),
+    Name(String),
+    Symbol(char),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Text(text) => format!("\"{}\"", text),
+            Token::Number(number) => number.to_string(),
+            Token::Column(column) => format!("[{}]", column),
+            Token::Name(name) => name.clone(),
+            Token::Symbol(symbol) => symbol.to_string(),
+        }
+    }
+}
+
+/// Reads up to `close`; a doubled `close` stands for the character itself.
+fn delimited(chars: &mut Peekable<CharIndices>, close: char) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some((_, c)) if c == close => {
+                if chars.peek().map(|(_, c)| *c) != Some(close) {
+                    return Ok(text);
+                }
+                chars.next();
+                text.push(close);
+            }
+            Some((_, c)) => text.push(c),
+            None => return Err(format!("Missing closing {}", close)),
+        }
+    }
+}
+
+fn tokenize(formula: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = formula.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '[' {
+            chars.next();
+            tokens.push(if c == '"' {
+                Token::Text(delimited(&mut chars, '"')?)
+            } else {
+                Token::Column(delimited(&mut chars, ']')?)
+            });
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(index, c)) = chars.peek().filter(|(_, c)| c.is_ascii_digit() || *c == '.') {
+                end = index + c.len_utf8();
+                chars.next();
+            }
+            let text = &formula[start..end];
+            tokens.push(Token::Number(text.parse().map_err(|_| format!("\"{}\" is not a number", text))?));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(index, c)) = chars.peek().filter(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                end = index + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Name(formula[start..end].to_string()));
+        } else if "+-*/(),".contains(c) {
+            chars.next();
+            tokens.push(Token::Symbol(c));
+        } else {
+            return Err(format!("Unexpected \"{}\"", c));
+        }
+    }
+    Ok(tokens)
+}
+
+/// How deeply negations, parentheses and calls may nest, so that a formula
+/// such as `=------…1` cannot exhaust the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn new(formula: &str) -> Result<Self, String> {
+        Ok(Parser { tokens: tokenize(formula)?, position: 0, depth: 0 })
+    }
+
+    fn formula(mut self) -> Result<Expr, String> {
+        if self.tokens.is_empty() {
+            return Err("The formula is empty".to_string());
+        }
+        let expr = self.sum()?;
+        match self.next() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected {}", token.describe())),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.tokens.get(self.position) == Some(&Token::Symbol(symbol));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Symbol(found)) if found == symbol => Ok(()),
+            Some(token) => Err(format!("Expected \"{}\" but found {}", symbol, token.describe())),
+            None => Err(format!("Expected \"{}\"", symbol)),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Some(op) = ['+', '-'].into_iter().find(|op| self.eat(*op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while let Some(op) = ['*', '/'].into_iter().find(|op| self.eat(*op)) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.depth == MAX_DEPTH {
+            return Err("The formula is nested too deeply".to_string());
+        }
+        self.depth += 1;
+        let expr = self.operand();
+        self.depth -= 1;
+        expr
+    }
+
+    fn operand(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        match self.next() {
+            Some(Token::Text(text)) => Ok(Expr::Text(text)),
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::Column(column)) => Ok(Expr::Column(column)),
+            Some(Token::Symbol('(')) => {
+                let expr = self.sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Name(name)) => {
+                let function = Function::named(&name)
+                    .ok_or_else(|| format!("Unknown function \"{}\", use concat, sum or first", name))?;
+                self.expect('(')?;
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.sum()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                function.check_args(args.len())?;
+                Ok(Expr::Call(function, args))
+            }
+            Some(token) => Err(format!("Unexpected {}", token.describe())),
+            None => Err("The formula ends too early".to_string()),
+        }
+    }
+}
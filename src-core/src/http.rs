@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use reqwest::multipart::{Form, Part};
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::client::RetryPolicy;
+use crate::protocol::{Reply, Session, IDEMPOTENCY_KEY};
+use crate::source::FileSource;
+use crate::transport;
+
+/// Header carrying the request's idempotency key.
+const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+/// Header carrying the signed-in operator's session token.
+const SESSION_HEADER: &str = "X-Session-Token";
+
+/// An ingest service exposed as an HTTP API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// e.g. `https://ingest.example.com/api/`; endpoint paths are relative to it.
+    pub base_url: String,
+    /// Receives a new vendor's details as JSON.
+    #[serde(default = "default_setup_path")]
+    pub setup_path: String,
+    /// Receives stock files as multipart/form-data; `{vendor}` is replaced by the vendor name.
+    #[serde(default = "default_upload_path")]
+    pub upload_path: String,
+    /// Starts an operator's session on POST and ends it on DELETE.
+    #[serde(default = "default_session_path")]
+    pub session_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<HttpAuth>,
+    /// Extra headers sent with every request.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Same as for the TLS transport, for `https` URLs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+}
+
+fn default_setup_path() -> String {
+    "vendors".to_string()
+}
+
+fn default_upload_path() -> String {
+    "vendors/{vendor}/uploads".to_string()
+}
+
+fn default_session_path() -> String {
+    "session".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum HttpAuth {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+    /// Any other scheme, e.g. an `X-Api-Key` header.
+    Header { name: String, value: String },
+}
+
+/// JSON body the service may answer with, successful or not.
+#[derive(Default, Deserialize)]
+struct ResponseBody {
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    errors: Vec<String>,
+    #[serde(default)]
+    session: Option<Session>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    config: HttpConfig,
+    base_url: Url,
+    policy: RetryPolicy,
+    http: reqwest::Client,
+    session_token: Option<String>,
+}
+
+impl HttpClient {
+    pub fn new(config: HttpConfig, policy: RetryPolicy) -> Result<Self, String> {
+        let base_url = Url::parse(&config.base_url).map_err(|e| format!("{}: {}", config.base_url, e))?;
+        let tls = transport::tls_config(config.ca_cert.as_deref(), config.client_cert.as_deref(), config.client_key.as_deref())?;
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_millis(policy.connect_timeout_ms))
+            .read_timeout(Duration::from_millis(policy.read_timeout_ms))
+            .use_preconfigured_tls(tls)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(HttpClient { config, base_url, policy, http, session_token: None })
+    }
+
+    /// Attaches the operator's session to every request.
+    pub fn with_session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+
+    fn url(&self, path: &str) -> Result<Url, String> {
+        self.base_url.join(path).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn request(&self, builder: RequestBuilder, idempotency_key: Option<&str>) -> RequestBuilder {
+        let mut builder = match &self.config.auth {
+            Some(HttpAuth::Bearer { token }) => builder.bearer_auth(token),
+            Some(HttpAuth::Basic { username, password }) => builder.basic_auth(username, Some(password)),
+            Some(HttpAuth::Header { name, value }) => builder.header(name, value),
+            None => builder,
+        };
+        for (name, value) in &self.config.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(token) = &self.session_token {
+            builder = builder.header(SESSION_HEADER, token);
+        }
+        match idempotency_key {
+            Some(key) => builder.header(IDEMPOTENCY_HEADER, key),
+            None => builder,
+        }
+    }
+
+    /// Checks that the service answers HTTP requests at all.
+    pub async fn ping(&self) -> Result<(), String> {
+        self.policy
+            .retry(true, || async {
+                let response = self.request(self.http.get(self.base_url.clone()), None).send().await;
+                response.map(|_| ()).map_err(request_error)
+            })
+            .await
+    }
+
+    pub async fn login(&self, username: &str, password: &str) -> Result<Session, String> {
+        let url = self.url(&self.config.session_path)?;
+        let credentials = serde_json::json!({ "username": username, "password": password });
+        self.policy
+            .retry(true, || async {
+                let request = self.request(self.http.post(url.clone()), None).json(&credentials);
+                reply(request.send().await.map_err(request_error)?).await
+            })
+            .await?
+            .into_session()
+    }
+
+    pub async fn logout(&self) -> Result<(), String> {
+        if self.session_token.is_none() {
+            return Ok(());
+        }
+        let url = self.url(&self.config.session_path)?;
+        self.policy
+            .retry(true, || async {
+                let request = self.request(self.http.delete(url.clone()), None);
+                reply(request.send().await.map_err(request_error)?).await
+            })
+            .await?
+            .into_result()
+            .map(|_| ())
+    }
+
+    /// Posts the vendor's details as JSON when `fields` carries a password,
+    /// i.e. when setting up a new vendor, then posts the file with the other
+    /// fields as multipart/form-data. Returns the upload's reply message.
+    pub async fn send(&self, mut fields: Map<String, Value>, source: &FileSource) -> Result<String, String> {
+        let idempotency_key = fields.get(IDEMPOTENCY_KEY).and_then(Value::as_str).map(str::to_string);
+        let idempotent = idempotency_key.is_some();
+        // Each request gets its own key, or a service would take the upload
+        // for a repeat of the setup.
+        let request_key = |request: &str| idempotency_key.as_ref().map(|key| format!("{}-{}", key, request));
+        let (setup_key, upload_key) = (request_key("setup"), request_key("upload"));
+        let vendor_name = fields.get("vendor_name").and_then(Value::as_str).unwrap_or_default().to_string();
+
+        let password = fields.remove("password");
+        if let Some(password) = password.filter(|password| password.as_str().is_some_and(|p| !p.is_empty())) {
+            let url = self.url(&self.config.setup_path)?;
+            let mut setup = fields.clone();
+            setup.insert("password".to_string(), password);
+            self.policy
+                .retry(idempotent, || async {
+                    let request = self.request(self.http.post(url.clone()), setup_key.as_deref()).json(&setup);
+                    reply(request.send().await.map_err(request_error)?).await
+                })
+                .await?
+                .into_result()?;
+        }
+
+        let url = self.url(&self.config.upload_path.replace("{vendor}", &encode_segment(&vendor_name)))?;
+        let metadata = Value::Object(fields).to_string();
+        let reply = self
+            .policy
+            .retry(idempotent, || async {
+                let form = Form::new().text("metadata", metadata.clone()).part("file", file_part(source).await?);
+                let request = self.request(self.http.post(url.clone()), upload_key.as_deref()).multipart(form);
+                reply(request.send().await.map_err(request_error)?).await
+            })
+            .await?;
+        reply.into_result()
+    }
+}
+
+/// Streams files picked by path from disk instead of loading them.
+async fn file_part(source: &FileSource) -> Result<Part, String> {
+    match source {
+        FileSource::Path(path) => Part::file(path).await.map_err(|e| format!("{}: {}", path.display(), e)),
+        FileSource::Bytes(bytes) => Part::bytes(bytes.clone())
+            .file_name("upload.csv")
+            .mime_str("text/csv")
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Maps an HTTP response onto the service's reply. `Err` is kept for
+/// answers worth retrying: rate limiting and server errors.
+async fn reply(response: Response) -> Result<Reply, String> {
+    let status = response.status();
+    let url = response.url().clone();
+    let text = response.text().await.map_err(request_error)?;
+    let body: ResponseBody = serde_json::from_str(&text).unwrap_or_default();
+
+    if status.is_success() {
+        return Ok(Reply::Ok {
+            message: body.message.unwrap_or_else(|| text.trim().to_string()),
+            session: body.session,
+        });
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        return Err(format!("The ingest service is unavailable ({})", status));
+    }
+    let message = match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => format!("The ingest service refused the credentials ({})", status),
+        StatusCode::NOT_FOUND => format!("{} was not found on the ingest service", url),
+        StatusCode::PAYLOAD_TOO_LARGE => "The file is too large for the ingest service".to_string(),
+        _ => body
+            .message
+            .unwrap_or_else(|| format!("The ingest service rejected the request ({})", status)),
+    };
+    Ok(Reply::Error { message, errors: body.errors })
+}
+
+fn request_error(e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        return "Timed out waiting for the ingest service".to_string();
+    }
+    // reqwest keeps the useful part, e.g. "connection refused", in the source chain.
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// Percent-encodes a vendor name for use as one URL path segment.
+fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,24 @@
+//! Vendor, mapping and upload logic shared by the desktop app and `fs_ui-cli`.
+
+pub mod audit;
+pub mod catalog;
+pub mod client;
+pub mod dashboard;
+pub mod dataset;
+pub mod diagnostics;
+pub mod dialect;
+pub mod drafts;
+pub mod export;
+pub mod formula;
+pub mod http;
+pub mod logging;
+pub mod pricing;
+pub mod profile;
+pub mod protocol;
+pub mod session;
+pub mod source;
+pub mod store;
+pub mod transport;
+pub mod upload;
+pub mod validation;
+mod zip;
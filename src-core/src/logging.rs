@@ -0,0 +1,169 @@
+use std::fmt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::audit;
+
+/// Directory of the log files in the app's data directory.
+pub const LOG_DIR: &str = "logs";
+
+/// Log files are named `fs_ui.<date>.log`, or `fs_ui.log` when they never rotate.
+const LOG_FILE_PREFIX: &str = "fs_ui";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Fields never written as they are, wherever they turn up.
+const SECRET_FIELDS: [&str; 5] = ["password", "token", "session_token", "authorization", "file_bytes"];
+
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// `error`, `warn`, `info`, `debug` or `trace`.
+    pub level: String,
+    /// When a new file is started: `hourly`, `daily` or `never`.
+    pub rotation: String,
+    /// Files kept, counting the current one.
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: "info".to_string(),
+            rotation: "daily".to_string(),
+            max_files: 5,
+        }
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    SECRET_FIELDS.contains(&name)
+}
+
+/// Replaces secret fields anywhere in `value`, e.g. a config about to be shared.
+/// Header values, and the value of a header `auth`, are all redacted since any
+/// of them may carry a key.
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_secret(name) {
+                    *field = Value::String(REDACTED.to_string());
+                } else if name == "headers" {
+                    if let Value::Object(headers) = field {
+                        headers.values_mut().for_each(|v| *v = Value::String(REDACTED.to_string()));
+                    }
+                } else if name == "auth" {
+                    if let Some(key) = field.get_mut("value") {
+                        *key = Value::String(REDACTED.to_string());
+                    }
+                    redact(field);
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Writes events as JSON lines to `dir`, starting a new file as `config.rotation`
+/// says and keeping the newest `config.max_files`. Secret fields are redacted.
+pub fn subscriber(dir: &Path, config: &LogConfig) -> Result<impl Subscriber + Send + Sync, String> {
+    let level = config
+        .level
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level \"{}\"", config.level))?;
+    let rotation = match config.rotation.as_str() {
+        "hourly" => Rotation::HOURLY,
+        "daily" => Rotation::DAILY,
+        "never" => Rotation::NEVER,
+        other => return Err(format!("Unknown log rotation \"{}\"", other)),
+    };
+    let appender = RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(config.max_files.max(1))
+        .build(dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Ok(tracing_subscriber::fmt()
+        .with_max_level(level)
+        .event_format(JsonLines)
+        .with_writer(appender)
+        .finish())
+}
+
+/// Logs to `dir` for the rest of the process.
+pub fn init(dir: &Path, config: &LogConfig) -> Result<(), String> {
+    tracing::subscriber::set_global_default(subscriber(dir, config)?).map_err(|e| e.to_string())
+}
+
+struct Fields(Map<String, Value>);
+
+impl Fields {
+    fn insert(&mut self, field: &Field, value: Value) {
+        let value = if is_secret(field.name()) { Value::String(REDACTED.to_string()) } else { value };
+        self.0.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::String(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+}
+
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = audit::format_utc(now.as_secs());
+    format!("{}.{:03}Z", seconds.trim_end_matches('Z'), now.subsec_millis())
+}
+
+/// One JSON object per event: time, level, target, then the event's fields.
+struct JsonLines;
+
+impl<S, N> FormatEvent<S, N> for JsonLines
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, _ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut fields = Fields(Map::new());
+        event.record(&mut fields);
+        let mut line = Map::new();
+        line.insert("timestamp".to_string(), Value::String(timestamp()));
+        line.insert("level".to_string(), Value::String(event.metadata().level().to_string()));
+        line.insert("target".to_string(), Value::String(event.metadata().target().to_string()));
+        line.extend(fields.0);
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
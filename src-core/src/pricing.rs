@@ -0,0 +1,282 @@
+//! Prices as vendors write them, in any locale and currency, and converting
+//! them with the locally maintained rate table.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dataset::Dataset;
+use crate::formula::format_number;
+
+/// Target fields holding prices, all optional.
+pub const PRICE_FIELDS: [&str; 3] = ["Cost", "MSRP", "MAP"];
+
+/// Optional target with the currency of a row's prices: a column of codes or
+/// symbols, or a constant for vendors pricing everything in one currency.
+pub const CURRENCY: &str = "Currency";
+
+/// File of the rate table, next to the vendors it converts prices for.
+pub const RATES_FILE: &str = "rates.toml";
+
+/// A price and the currency its text named, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Price {
+    pub amount: f64,
+    pub currency: Option<String>,
+}
+
+/// The ISO code for a currency code or symbol, such as `eur`, `$` or `€`.
+pub fn currency_code(text: &str) -> Result<String, String> {
+    let code = match text.trim() {
+        "$" | "US$" => "USD",
+        "€" => "EUR",
+        "£" => "GBP",
+        "¥" => "JPY",
+        "C$" => "CAD",
+        "A$" => "AUD",
+        "Fr" | "Fr." => "CHF",
+        code if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) => return Ok(code.to_ascii_uppercase()),
+        other => return Err(format!("Unknown currency \"{}\"", other)),
+    };
+    Ok(code.to_string())
+}
+
+/// Reads a price such as `$1,234.50`, `1.234,50 €`, `EUR 1 234,50` or `1'234.50`.
+/// `decimal`, `.` or `,`, is the decimal separator and the other one groups
+/// thousands. Without it the separator is guessed from the price alone, see
+/// `shown_decimal`, and a lone one that could be either groups thousands.
+pub fn parse_price(text: &str, decimal: Option<char>) -> Result<Price, String> {
+    let not_a_price = || format!("\"{}\" is not a price", text);
+    let marker: String = chars(text).filter(|(_, number)| !number).map(|(c, _)| c).collect();
+    let currency = if marker.is_empty() { None } else { Some(currency_code(&marker).map_err(|_| not_a_price())?) };
+
+    let number = number(text);
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, number.as_str()),
+    };
+    let decimal = match decimal {
+        Some(decimal) => {
+            let group = if decimal == ',' { '.' } else { ',' };
+            // Another separator after the decimal one is a price written some other way.
+            if digits.split_once(decimal).is_some_and(|(_, decimals)| decimals.contains([decimal, group])) {
+                return Err(not_a_price());
+            }
+            Some(decimal)
+        }
+        None => shown_decimal(digits),
+    };
+    let plain: String = digits
+        .chars()
+        .filter_map(|c| match c {
+            _ if Some(c) == decimal => Some('.'),
+            '.' | ',' => None,
+            c => Some(c),
+        })
+        .collect();
+    if plain.is_empty() || !plain.chars().any(|c| c.is_ascii_digit()) || plain.contains('-') {
+        return Err(not_a_price());
+    }
+    let amount: f64 = plain.parse().map_err(|_| not_a_price())?;
+    Ok(Price { amount: if negative { -amount } else { amount }, currency })
+}
+
+fn is_number(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '\'' | ' ' | '\u{a0}' | '\u{202f}')
+}
+
+/// Each char of `text` and whether it belongs to the number. A dot right
+/// after a letter ends an abbreviation such as `Fr.` and belongs to the currency.
+fn chars(text: &str) -> impl Iterator<Item = (char, bool)> + '_ {
+    let mut previous = None;
+    text.chars().map(move |c| {
+        let abbreviation = c == '.' && previous.is_some_and(char::is_alphabetic);
+        previous = Some(c);
+        (c, is_number(c) && !abbreviation)
+    })
+}
+
+/// The sign, digits and separators of `text`, without the spaces and
+/// apostrophes that only ever group thousands.
+fn number(text: &str) -> String {
+    chars(text)
+        .filter(|(c, number)| *number && !matches!(c, '\'' | ' ' | '\u{a0}' | '\u{202f}'))
+        .map(|(c, _)| c)
+        .collect()
+}
+
+/// The decimal separator `digits` show, if any. When both `.` and `,` appear,
+/// the last one is; a repeated one groups thousands, so the other is. A lone
+/// separator followed by exactly three digits, after one to three others, as
+/// in `1.500`, could be either and shows nothing; anywhere else it is decimal.
+fn shown_decimal(digits: &str) -> Option<char> {
+    let lone = |separator: char, other: char| {
+        let parts: Vec<&str> = digits.split(separator).collect();
+        if parts.len() > 2 {
+            return Some(other);
+        }
+        let thousands = parts[1].len() == 3 && (1..=3).contains(&parts[0].len()) && !parts[0].starts_with('0');
+        (!thousands).then_some(separator)
+    };
+    match (digits.rfind('.'), digits.rfind(',')) {
+        (Some(dot), Some(comma)) => Some(if dot > comma { '.' } else { ',' }),
+        (Some(_), None) => lone('.', ','),
+        (None, Some(_)) => lone(',', '.'),
+        (None, None) => None,
+    }
+}
+
+/// The decimal separator most of `prices` show, for the operator to confirm
+/// as the vendor's. `None` when they show none, or as many of each.
+pub fn detect_decimal<'a>(prices: impl IntoIterator<Item = &'a str>) -> Option<char> {
+    let (mut dots, mut commas) = (0, 0);
+    for price in prices {
+        match shown_decimal(number(price).trim_start_matches('-')) {
+            Some('.') => dots += 1,
+            Some(_) => commas += 1,
+            None => {}
+        }
+    }
+    match dots.cmp(&commas) {
+        Ordering::Greater => Some('.'),
+        Ordering::Less => Some(','),
+        Ordering::Equal => None,
+    }
+}
+
+/// Rewrites a row's prices as plain numbers and its currency as an ISO code,
+/// taken from the `Currency` field or else the prices' own symbols. Prices are
+/// read with the vendor's `decimal` separator, except those of `computed`
+/// fields, which come from formulas and always use `.`.
+pub fn normalize(fields: &mut BTreeMap<String, String>, computed: &dyn Fn(&str) -> bool, decimal: Option<char>) -> Result<(), String> {
+    let mut currency = match fields.get(CURRENCY).filter(|c| !c.is_empty()) {
+        Some(currency) => Some(currency_code(currency)?),
+        None => None,
+    };
+    for field in PRICE_FIELDS {
+        let Some(value) = fields.get_mut(field).filter(|value| !value.is_empty()) else { continue };
+        let decimal = if computed(field) { Some('.') } else { decimal };
+        let price = parse_price(value, decimal).map_err(|e| format!("{} {}", field, e))?;
+        match (&price.currency, &currency) {
+            (Some(named), Some(current)) if named != current => {
+                return Err(format!("{} \"{}\" is in {}, not {}", field, value, named, current));
+            }
+            (Some(named), None) => currency = Some(named.clone()),
+            _ => {}
+        }
+        *value = format_number(price.amount);
+    }
+    if let Some(currency) = currency {
+        fields.insert(CURRENCY.to_string(), currency);
+    }
+    Ok(())
+}
+
+/// Exchange rates kept on this machine, as `rates.toml` with the vendors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateTable {
+    /// Currency the rates are quoted in.
+    pub base: String,
+    /// Currency uploads convert prices to; empty leaves them as vendors send them.
+    pub convert_to: String,
+    /// Units of `base` one unit of each currency is worth.
+    pub rates: BTreeMap<String, f64>,
+    /// Seconds since the Unix epoch; 0 when never saved.
+    pub updated: u64,
+}
+
+impl Default for RateTable {
+    fn default() -> Self {
+        RateTable {
+            base: "USD".to_string(),
+            convert_to: String::new(),
+            rates: BTreeMap::new(),
+            updated: 0,
+        }
+    }
+}
+
+impl RateTable {
+    /// Reads the table from `path`, empty when the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(RateTable::default());
+        }
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut table: RateTable = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        // The file is edited by hand too.
+        table.check().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(table)
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<(), String> {
+        self.check()?;
+        self.updated = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| e.to_string())
+    }
+
+    /// Upper-cases the codes and refuses rates that could not convert anything.
+    fn check(&mut self) -> Result<(), String> {
+        self.base = currency_code(&self.base)?;
+        self.convert_to = if self.convert_to.trim().is_empty() { String::new() } else { currency_code(&self.convert_to)? };
+        let mut rates = BTreeMap::new();
+        for (code, rate) in &self.rates {
+            if !rate.is_finite() || *rate <= 0.0 {
+                return Err(format!("The rate for {} must be above zero", code));
+            }
+            rates.insert(currency_code(code)?, *rate);
+        }
+        self.rates = rates;
+        if !self.convert_to.is_empty() {
+            self.rate(&self.convert_to)?;
+        }
+        Ok(())
+    }
+
+    fn rate(&self, currency: &str) -> Result<f64, String> {
+        if currency == self.base {
+            return Ok(1.0);
+        }
+        self.rates.get(currency).copied().ok_or_else(|| format!("The rate table has no rate for {}", currency))
+    }
+
+    /// Converts every item's prices to `convert_to`, rounded to cents.
+    pub fn convert(&self, dataset: &mut Dataset) -> Result<(), String> {
+        if self.convert_to.is_empty() {
+            return Ok(());
+        }
+        let target = self.rate(&self.convert_to)?;
+        for (key, fields) in dataset.rows.iter_mut() {
+            if !PRICE_FIELDS.iter().any(|field| fields.get(*field).is_some_and(|v| !v.is_empty())) {
+                continue;
+            }
+            let currency = fields
+                .get(CURRENCY)
+                .cloned()
+                .ok_or_else(|| format!("{}: no currency to convert its prices from, map a Currency column or a fixed currency", key))?;
+            let factor = self.rate(&currency).map_err(|e| format!("{}: {}", key, e))? / target;
+            for field in PRICE_FIELDS {
+                if let Some(value) = fields.get_mut(field).filter(|value| !value.is_empty()) {
+                    let amount: f64 = value.parse().map_err(|_| format!("{}: {} \"{}\" is not a price", key, field, value))?;
+                    *value = format_number((amount * factor * 100.0).round() / 100.0);
+                }
+            }
+            fields.insert(CURRENCY.to_string(), self.convert_to.clone());
+        }
+        Ok(())
+    }
+
+    /// Whether prices in `currency` upload as they are or can be converted.
+    pub fn converts(&self, currency: &str) -> bool {
+        self.convert_to.is_empty() || self.rate(currency).is_ok()
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::audit::{AuditAction, AuditEntry, AuditLog};
+use crate::dataset::{self, FieldChange, Mapping, FILE_KEY, TARGET_FIELDS};
+use crate::dialect::Dialect;
+use crate::formula::Source;
+use crate::store::{ProfileVersion, VendorRecord, VendorStore};
+
+/// Note kept with profile versions saved by an import.
+const IMPORT_NOTE: &str = "Imported from a profile document";
+
+/// Version of the profile document written; documents from a later version are refused.
+pub const PROFILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileFormat {
+    Json,
+    Toml,
+}
+
+impl ProfileFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ProfileFormat::Json => "json",
+            ProfileFormat::Toml => "toml",
+        }
+    }
+
+    /// By file extension; anything but `.toml` is read as JSON.
+    pub fn of_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ProfileFormat::Toml,
+            _ => ProfileFormat::Json,
+        }
+    }
+}
+
+/// How one vendor's stock files are read and mapped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub vendor_name: String,
+    #[serde(default)]
+    pub dialect: Dialect,
+    /// Target field -> source column.
+    #[serde(default)]
+    pub mapping: Mapping,
+}
+
+impl Profile {
+    pub fn of(record: &VendorRecord) -> Self {
+        Profile {
+            vendor_name: record.vendor_name.clone(),
+            dialect: record.dialect.clone(),
+            mapping: dataset::parse_mappings(&record.file_mappings),
+        }
+    }
+}
+
+/// Profiles as exported from one installation, to import on another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileDocument {
+    pub version: u32,
+    /// Target fields of the installation that wrote the document.
+    #[serde(default)]
+    pub schema: Vec<String>,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+impl ProfileDocument {
+    /// The profiles of `vendors`, or of every saved vendor when it is empty.
+    pub fn export(store: &VendorStore, vendors: &[String]) -> Result<Self, String> {
+        let names = if vendors.is_empty() { store.list()? } else { vendors.to_vec() };
+        let mut profiles = Vec::new();
+        for name in names {
+            let record = store.load(&name)?.ok_or_else(|| format!("Vendor \"{}\" has no saved mapping", name))?;
+            profiles.push(Profile::of(&record));
+        }
+        Ok(ProfileDocument {
+            version: PROFILE_VERSION,
+            schema: TARGET_FIELDS.map(String::from).to_vec(),
+            profiles,
+        })
+    }
+
+    pub fn write(&self, format: ProfileFormat) -> Result<Vec<u8>, String> {
+        match format {
+            ProfileFormat::Json => serde_json::to_vec_pretty(self).map_err(|e| e.to_string()),
+            ProfileFormat::Toml => toml::to_string_pretty(self).map(String::into_bytes).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn parse(bytes: &[u8], format: ProfileFormat) -> Result<Self, String> {
+        let document: ProfileDocument = match format {
+            ProfileFormat::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string())?,
+            ProfileFormat::Toml => {
+                let text = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+                toml::from_str(text).map_err(|e| e.to_string())?
+            }
+        };
+        match document.version {
+            0 => Err("The document has no profile version".to_string()),
+            version if version > PROFILE_VERSION => Err(format!(
+                "The document is profile version {}, newer than this app reads ({})",
+                version, PROFILE_VERSION
+            )),
+            _ => Ok(document),
+        }
+    }
+}
+
+/// What to do with an incoming profile; vendors without one are skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    /// Keep the saved profile, or do not import a new one.
+    #[default]
+    Skip,
+    Replace,
+    /// Take the incoming dialect and mapped fields, keeping saved fields it leaves unmapped.
+    Merge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    /// No saved vendor by that name.
+    New,
+    Unchanged,
+    /// Differs from the saved profile.
+    Conflict,
+    /// Cannot be imported; see its errors.
+    Invalid,
+}
+
+/// One incoming profile checked against this installation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileImport {
+    /// As it would be saved, without fields this installation does not know.
+    pub incoming: Profile,
+    pub current: Option<Profile>,
+    pub status: ImportStatus,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportPreview {
+    /// Differences between the document's schema and this installation's.
+    pub warnings: Vec<String>,
+    pub profiles: Vec<ProfileImport>,
+}
+
+/// Checks every profile in `document` against the current schema and the
+/// saved vendors, without changing anything.
+pub fn preview(document: &ProfileDocument, store: &VendorStore) -> Result<ImportPreview, String> {
+    let mut warnings = Vec::new();
+    for field in &document.schema {
+        if !TARGET_FIELDS.contains(&field.as_str()) {
+            warnings.push(format!("The document's schema has \"{}\", which is not a target field here", field));
+        }
+    }
+    if !document.schema.is_empty() {
+        for field in TARGET_FIELDS {
+            if !document.schema.iter().any(|f| f == field) {
+                warnings.push(format!("The document's schema has no \"{}\"", field));
+            }
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut profiles = Vec::new();
+    for profile in &document.profiles {
+        let mut incoming = profile.clone();
+        incoming.vendor_name = incoming.vendor_name.trim().to_string();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        if incoming.vendor_name.is_empty() {
+            errors.push("The profile has no vendor name".to_string());
+        } else if !seen.insert(incoming.vendor_name.clone()) {
+            errors.push(format!("\"{}\" is in the document more than once", incoming.vendor_name));
+        }
+        if let Err(e) = incoming.dialect.check() {
+            errors.push(e);
+        }
+        incoming.mapping.retain(|target, source| {
+            let known = dataset::is_target(target);
+            if !known {
+                warnings.push(format!("Unknown target field \"{}\" (from column \"{}\") is left out", target, source));
+            }
+            known && !source.is_empty()
+        });
+        if !incoming.mapping.contains_key(FILE_KEY) {
+            errors.push(format!("The mapping has no {}", FILE_KEY));
+        }
+        for (target, source) in &incoming.mapping {
+            if let Err(e) = Source::parse(source) {
+                errors.push(format!("Formula for \"{}\": {}", target, e));
+            }
+        }
+
+        let current = if incoming.vendor_name.is_empty() {
+            None
+        } else {
+            store.load(&incoming.vendor_name)?.map(|record| Profile::of(&record))
+        };
+        let status = match &current {
+            _ if !errors.is_empty() => ImportStatus::Invalid,
+            None => ImportStatus::New,
+            Some(current) if *current == incoming => ImportStatus::Unchanged,
+            Some(_) => ImportStatus::Conflict,
+        };
+        profiles.push(ProfileImport { incoming, current, status, warnings, errors });
+    }
+    Ok(ImportPreview { warnings, profiles })
+}
+
+/// A profile saved by an import, with what it replaced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedProfile {
+    pub before: Option<Profile>,
+    pub after: Profile,
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub imported: Vec<ImportedProfile>,
+    /// Vendors left as they were: skipped, unchanged or invalid.
+    pub skipped: Vec<String>,
+}
+
+/// Saves the profiles of `document` as `resolutions` say, keyed by vendor
+/// name, each as a new profile version by `author`. Invalid and unchanged
+/// profiles are always skipped. A vendor new to this installation is saved
+/// without a dataset, so its first upload sends every row.
+pub fn import(
+    document: &ProfileDocument,
+    resolutions: &BTreeMap<String, Resolution>,
+    store: &VendorStore,
+    author: &str,
+) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary::default();
+    for checked in preview(document, store)?.profiles {
+        let resolution = resolutions.get(&checked.incoming.vendor_name).copied().unwrap_or_default();
+        if matches!(checked.status, ImportStatus::Invalid | ImportStatus::Unchanged) || resolution == Resolution::Skip {
+            summary.skipped.push(checked.incoming.vendor_name);
+            continue;
+        }
+        let mut after = checked.incoming;
+        if let (Resolution::Merge, Some(current)) = (resolution, &checked.current) {
+            for (target, source) in &current.mapping {
+                after.mapping.entry(target.clone()).or_insert_with(|| source.clone());
+            }
+        }
+
+        let mut record = store.load(&after.vendor_name)?.unwrap_or_else(|| VendorRecord::new(&after.vendor_name));
+        let version = record.set_profile(&dataset::format_mappings(&after.mapping), &after.dialect, author, IMPORT_NOTE);
+        store.save(&record)?;
+        summary.imported.push(ImportedProfile { before: checked.current, after, version });
+    }
+    Ok(summary)
+}
+
+/// Whether importing `document` as `resolutions` say would create a vendor
+/// not saved here yet, which takes `Permission::CreateVendor`.
+pub fn creates_vendors(document: &ProfileDocument, resolutions: &BTreeMap<String, Resolution>, store: &VendorStore) -> Result<bool, String> {
+    Ok(preview(document, store)?.profiles.iter().any(|checked| {
+        checked.status == ImportStatus::New && resolutions.get(&checked.incoming.vendor_name).copied().unwrap_or_default() != Resolution::Skip
+    }))
+}
+
+/// Like `import`, and records each saved profile in `audit` by `actor`: a
+/// vendor new here as created, any other as a mapping change.
+pub fn import_audited(
+    document: &ProfileDocument,
+    resolutions: &BTreeMap<String, Resolution>,
+    store: &VendorStore,
+    audit: &AuditLog,
+    actor: &str,
+) -> Result<ImportSummary, String> {
+    let summary = import(document, resolutions, store, actor)?;
+    let mut written = Ok(());
+    for imported in &summary.imported {
+        let action = if imported.before.is_some() { AuditAction::ChangeMapping } else { AuditAction::CreateVendor };
+        let mut entry = AuditEntry::new(actor, action, &imported.after.vendor_name);
+        entry.before = imported.before.as_ref().map(|before| json!({ "file_mappings": dataset::format_mappings(&before.mapping) }));
+        entry.after = Some(json!({
+            "file_mappings": dataset::format_mappings(&imported.after.mapping),
+            "profile_version": imported.version,
+            "imported": true,
+        }));
+        written = written.and(audit.append(&entry));
+    }
+    written.map_err(|e| format!("Imported, but the audit log could not be written: {}", e))?;
+    Ok(summary)
+}
+
+/// A profile version with the accepted uploads that were mapped with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionUse {
+    #[serde(flatten)]
+    pub version: ProfileVersion,
+    pub uploads: usize,
+    /// Seconds since the Unix epoch.
+    pub last_upload: Option<u64>,
+}
+
+/// The vendor's profile versions, newest first.
+pub fn versions(store: &VendorStore, vendor_name: &str) -> Result<Vec<VersionUse>, String> {
+    let record = load(store, vendor_name)?;
+    Ok(record
+        .versions
+        .iter()
+        .rev()
+        .map(|version| {
+            let uploads: Vec<u64> = record.history.iter().filter(|s| s.version == version.version).map(|s| s.timestamp).collect();
+            VersionUse {
+                version: version.clone(),
+                uploads: uploads.len(),
+                last_upload: uploads.into_iter().max(),
+            }
+        })
+        .collect())
+}
+
+/// What changed from version `from` to version `to`: each target field whose
+/// source column differs, empty where unmapped, then the dialect's settings.
+pub fn diff(store: &VendorStore, vendor_name: &str, from: u32, to: u32) -> Result<Vec<FieldChange>, String> {
+    let record = load(store, vendor_name)?;
+    let (from, to) = (find(&record, from)?, find(&record, to)?);
+    let (before, after) = (dataset::parse_mappings(&from.file_mappings), dataset::parse_mappings(&to.file_mappings));
+    let targets: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    // Schema fields first, in schema order, then any the schema no longer has.
+    let order = |target: &&String| TARGET_FIELDS.iter().position(|f| f == target).unwrap_or(TARGET_FIELDS.len());
+    let mut targets: Vec<&String> = targets.into_iter().collect();
+    targets.sort_by_key(order);
+
+    let mut changes: Vec<FieldChange> = targets
+        .into_iter()
+        .map(|target| FieldChange {
+            field: target.clone(),
+            old: before.get(target).cloned().unwrap_or_default(),
+            new: after.get(target).cloned().unwrap_or_default(),
+        })
+        .collect();
+    changes.push(FieldChange { field: "Delimiter".to_string(), old: from.dialect.delimiter.to_string(), new: to.dialect.delimiter.to_string() });
+    changes.push(FieldChange { field: "Quote".to_string(), old: from.dialect.quote.to_string(), new: to.dialect.quote.to_string() });
+    changes.push(FieldChange { field: "Encoding".to_string(), old: from.dialect.encoding.clone(), new: to.dialect.encoding.clone() });
+    let decimal = |dialect: &Dialect| dialect.decimal.map(String::from).unwrap_or_default();
+    changes.push(FieldChange { field: "Decimal separator".to_string(), old: decimal(&from.dialect), new: decimal(&to.dialect) });
+    changes.retain(|change| change.old != change.new);
+    Ok(changes)
+}
+
+/// Makes `version` the vendor's profile again. The rollback is recorded as a
+/// new version, so the versions in between are kept; returns its number.
+pub fn rollback(store: &VendorStore, vendor_name: &str, version: u32, author: &str, note: &str) -> Result<u32, String> {
+    let mut record = load(store, vendor_name)?;
+    let target = find(&record, version)?.clone();
+    let note = match note.trim() {
+        "" => format!("Rolled back to version {}", version),
+        note => note.to_string(),
+    };
+    let before = record.version();
+    if record.set_profile(&target.file_mappings, &target.dialect, author, &note) == before {
+        return Err(format!("Version {} is the profile in use", version));
+    }
+    store.save(&record)?;
+    Ok(record.version())
+}
+
+/// Like `rollback`, and records it in `audit` as a mapping change by `actor`.
+pub fn rollback_audited(store: &VendorStore, audit: &AuditLog, actor: &str, vendor_name: &str, version: u32, note: &str) -> Result<u32, String> {
+    let before = load(store, vendor_name)?;
+    let result = rollback(store, vendor_name, version, actor, note);
+    let mut entry = AuditEntry::new(actor, AuditAction::ChangeMapping, vendor_name);
+    entry.before = Some(json!({ "file_mappings": before.file_mappings, "profile_version": before.version() }));
+    if let Ok(new_version) = &result {
+        entry.after = Some(json!({
+            "file_mappings": find(&before, version)?.file_mappings,
+            "profile_version": new_version,
+            "rolled_back_to": version,
+        }));
+    }
+    audit.record(entry, result, "Rolled back")
+}
+
+fn load(store: &VendorStore, vendor_name: &str) -> Result<VendorRecord, String> {
+    store.load(vendor_name)?.ok_or_else(|| format!("Vendor \"{}\" has no saved mapping", vendor_name))
+}
+
+fn find(record: &VendorRecord, version: u32) -> Result<&ProfileVersion, String> {
+    record
+        .versions
+        .iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| format!("\"{}\" has no profile version {}", record.vendor_name, version))
+}
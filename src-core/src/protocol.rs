@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::session::Role;
+
+/// Request field identifying one upload. The client sends the same key on
+/// every retry so the service can answer a repeat without applying it twice.
+pub const IDEMPOTENCY_KEY: &str = "idempotency_key";
+
+/// Request field carrying the signed-in operator's session token.
+pub const SESSION_TOKEN: &str = "session_token";
+
+/// Request field naming what a request without a file asks for, e.g.
+/// `{"action": "login", "username": ..., "password": ...}`. Uploads have none.
+pub const ACTION: &str = "action";
+
+/// An operator's session with the ingest service, issued on login.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub token: String,
+    pub username: String,
+    /// Services that predate roles grant none beyond viewing.
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// What the ingest service answers after reading a request. The client writes
+/// the request as one JSON object, shuts down its write half, then reads the
+/// reply as one JSON object until the service closes the connection. Services
+/// that predate these replies answer with plain text or nothing at all.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Reply {
+    Ok {
+        #[serde(default)]
+        message: String,
+        /// Set in answer to a login.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session: Option<Session>,
+    },
+    Error {
+        message: String,
+        #[serde(default)]
+        errors: Vec<String>,
+    },
+}
+
+impl Reply {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Reply::Ok { message: message.into(), session: None }
+    }
+
+    /// Reads a reply object. Only a `legacy` service, which predates them,
+    /// answers in plain text, taken as the message of an accepted request;
+    /// from any other, such as a truncated reply, it is an error.
+    pub fn parse(bytes: &[u8], legacy: bool) -> Result<Self, String> {
+        match serde_json::from_slice(bytes) {
+            Ok(reply) => Ok(reply),
+            Err(_) if legacy => Ok(Reply::ok(String::from_utf8_lossy(bytes).trim())),
+            Err(e) => Err(format!("Could not read the ingest service's reply: {}", e)),
+        }
+    }
+
+    pub fn into_result(self) -> Result<String, String> {
+        match self {
+            Reply::Ok { message, .. } => Ok(message),
+            Reply::Error { message, errors } if errors.is_empty() => Err(message),
+            Reply::Error { message, errors } => Err(format!("{}\n{}", message, errors.join("\n"))),
+        }
+    }
+
+    pub fn into_session(self) -> Result<Session, String> {
+        match self {
+            Reply::Ok { session: Some(session), .. } => Ok(session),
+            Reply::Ok { .. } => Err("The ingest service did not start a session".to_string()),
+            error => error.into_result().map(|_| unreachable!()),
+        }
+    }
+}
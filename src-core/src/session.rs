@@ -0,0 +1,169 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::Session;
+
+/// What an operator may do, granted by the ingest service on login.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Viewer,
+    Uploader,
+    Admin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Upload stock files for existing vendors with their saved mapping.
+    Upload,
+    CreateVendor,
+    /// Upload with a mapping other than the vendor's saved one.
+    EditMapping,
+    ManageWatchFolders,
+    ViewAudit,
+}
+
+impl Role {
+    pub fn permissions(self) -> &'static [Permission] {
+        match self {
+            Role::Viewer => &[],
+            Role::Uploader => &[Permission::Upload],
+            Role::Admin => &[Permission::Upload, Permission::CreateVendor, Permission::EditMapping, Permission::ManageWatchFolders, Permission::ViewAudit],
+        }
+    }
+
+    pub fn allows(self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Role::Viewer => "viewer",
+            Role::Uploader => "uploader",
+            Role::Admin => "admin",
+        })
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "uploader" => Ok(Role::Uploader),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("Unknown role \"{}\"; expected viewer, uploader or admin", other)),
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Permission::Upload => "uploading files",
+            Permission::CreateVendor => "creating vendors",
+            Permission::EditMapping => "changing a vendor's mapping",
+            Permission::ManageWatchFolders => "managing watch folders",
+            Permission::ViewAudit => "viewing the audit log",
+        })
+    }
+}
+
+/// Checks that the session's role grants `permission`.
+pub fn check(session: &Session, permission: Permission) -> Result<(), String> {
+    if session.role.allows(permission) {
+        Ok(())
+    } else {
+        Err(format!("The {} role does not allow {}", session.role, permission))
+    }
+}
+
+/// What the UI may know about the signed-in operator; the token stays in the backend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Operator {
+    pub username: String,
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+}
+
+impl From<&Session> for Operator {
+    fn from(session: &Session) -> Self {
+        Operator {
+            username: session.username.clone(),
+            role: session.role,
+            permissions: session.role.permissions().to_vec(),
+        }
+    }
+}
+
+struct Active {
+    session: Session,
+    last_active: Instant,
+}
+
+/// The operator signed in to this app, signed out after `idle_timeout` without activity.
+pub struct Sessions {
+    idle_timeout: Duration,
+    active: Mutex<Option<Active>>,
+}
+
+impl Sessions {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Sessions { idle_timeout, active: Mutex::new(None) }
+    }
+
+    pub fn start(&self, session: Session) -> Operator {
+        let operator = Operator::from(&session);
+        *self.active.lock().unwrap() = Some(Active { session, last_active: Instant::now() });
+        operator
+    }
+
+    pub fn end(&self) -> Option<Session> {
+        self.active.lock().unwrap().take().map(|active| active.session)
+    }
+
+    /// The current session, without counting as activity. Drops it once idle for too long.
+    pub fn current(&self) -> Option<Session> {
+        let mut active = self.active.lock().unwrap();
+        if active.as_ref().is_some_and(|a| a.last_active.elapsed() > self.idle_timeout) {
+            *active = None;
+        }
+        active.as_ref().map(|a| a.session.clone())
+    }
+
+    pub fn operator(&self) -> Option<Operator> {
+        self.current().as_ref().map(Operator::from)
+    }
+
+    /// The current session, counting as activity; for anything done on the operator's behalf.
+    pub fn touch(&self) -> Result<Session, String> {
+        let mut active = self.active.lock().unwrap();
+        match active.as_mut() {
+            Some(a) if a.last_active.elapsed() <= self.idle_timeout => {
+                a.last_active = Instant::now();
+                Ok(a.session.clone())
+            }
+            Some(_) => {
+                *active = None;
+                Err("Signed out after a period of inactivity; sign in again".to_string())
+            }
+            None => Err("Not signed in".to_string()),
+        }
+    }
+
+    /// Like `touch`, but only for an operator whose role grants `permission`.
+    pub fn authorize(&self, permission: Permission) -> Result<Session, String> {
+        let session = self.touch()?;
+        check(&session, permission)?;
+        Ok(session)
+    }
+}
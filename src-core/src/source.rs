@@ -0,0 +1,156 @@
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use csv::ReaderBuilder;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Rows of the file returned to the UI for previewing.
+pub const SAMPLE_ROWS: usize = 5;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Where the stock file of an upload comes from. Files picked through the
+/// native dialog are read from disk by path; files dropped inside the webview
+/// only exist as bytes.
+pub enum FileSource {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl FileSource {
+    pub fn reader(&self) -> Result<Box<dyn Read + Send + '_>, String> {
+        match self {
+            FileSource::Path(path) => {
+                let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                Ok(Box::new(BufReader::new(file)))
+            }
+            FileSource::Bytes(bytes) => Ok(Box::new(Cursor::new(bytes.as_slice()))),
+        }
+    }
+}
+
+pub fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileSummary {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub headers: Vec<String>,
+    pub sample: Vec<Vec<String>>,
+    pub row_count: usize,
+}
+
+/// Reads a stock file once, keeping only its header, a few rows and counts.
+pub fn summarize(path: &Path) -> Result<FileSummary, String> {
+    if !is_csv(path) {
+        return Err(format!("{} is not a CSV file", path.display()));
+    }
+    summarize_source(&FileSource::Path(path.to_path_buf()))
+}
+
+/// Like `summarize`, for a file that may only exist in memory, in which case
+/// its path and name are left empty.
+pub fn summarize_source(source: &FileSource) -> Result<FileSummary, String> {
+    let (path, name, size) = match source {
+        FileSource::Path(path) => (
+            path.display().to_string(),
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path.metadata().map_err(|e| e.to_string())?.len(),
+        ),
+        FileSource::Bytes(bytes) => (String::new(), String::new(), bytes.len() as u64),
+    };
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(source.reader()?);
+    let headers = rdr
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    let mut sample = Vec::new();
+    let mut row_count = 0;
+    for record in rdr.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        if sample.len() < SAMPLE_ROWS {
+            sample.push(record.iter().map(|field| field.to_string()).collect());
+        }
+        row_count += 1;
+    }
+
+    Ok(FileSummary {
+        path,
+        name,
+        size,
+        headers,
+        sample,
+        row_count,
+    })
+}
+
+/// `fields` as a JSON object with the file appended as its `file_bytes`
+/// array, produced in chunks so the file is never loaded whole.
+pub struct Payload<'a> {
+    head: Option<Vec<u8>>,
+    reader: Box<dyn Read + Send + 'a>,
+    chunk: Vec<u8>,
+    first: bool,
+    done: bool,
+}
+
+impl<'a> Payload<'a> {
+    pub fn new(fields: &Map<String, Value>, source: &'a FileSource) -> Result<Self, String> {
+        let mut head = String::from("{");
+        for (name, value) in fields {
+            head.push_str(&format!("{}:{},", Value::String(name.clone()), value));
+        }
+        head.push_str("\"file_bytes\":[");
+        Ok(Payload {
+            head: Some(head.into_bytes()),
+            reader: source.reader()?,
+            chunk: vec![0u8; CHUNK_SIZE],
+            first: true,
+            done: false,
+        })
+    }
+}
+
+impl Iterator for Payload<'_> {
+    type Item = Result<Vec<u8>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(head) = self.head.take() {
+            return Some(Ok(head));
+        }
+        if self.done {
+            return None;
+        }
+        let read = match self.reader.read(&mut self.chunk) {
+            Ok(read) => read,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e.to_string()));
+            }
+        };
+        if read == 0 {
+            self.done = true;
+            return Some(Ok(b"]}".to_vec()));
+        }
+        let mut text = String::with_capacity(read * 4);
+        for byte in &self.chunk[..read] {
+            if !self.first {
+                text.push(',');
+            }
+            self.first = false;
+            text.push_str(&byte.to_string());
+        }
+        Some(Ok(text.into_bytes()))
+    }
+}
@@ -0,0 +1,279 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::dataset::{self, Dataset};
+use crate::dialect::Dialect;
+use crate::pricing::{RateTable, RATES_FILE};
+
+/// Snapshots kept per vendor; the oldest are dropped beyond this.
+pub const MAX_HISTORY: usize = 1000;
+
+/// Size of one accepted upload, kept to chart a vendor over time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub rows: usize,
+    /// Sum of the Quantity column; rows without a number count as zero.
+    pub quantity: f64,
+    /// Profile version the upload was mapped with; 0 for uploads from before
+    /// versions were kept.
+    #[serde(default)]
+    pub version: u32,
+}
+
+impl Snapshot {
+    pub fn of(dataset: &Dataset) -> Self {
+        Snapshot {
+            timestamp: now(),
+            rows: dataset.rows.len(),
+            quantity: dataset
+                .rows
+                .values()
+                .filter_map(|fields| fields.get("Quantity")?.parse::<f64>().ok())
+                .sum(),
+            version: 0,
+        }
+    }
+}
+
+/// One state of a vendor's mapping and dialect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileVersion {
+    /// Counts up from 1.
+    pub version: u32,
+    /// Empty when not known, as for the profile a record had before versions were kept.
+    #[serde(default)]
+    pub author: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    #[serde(default)]
+    pub note: String,
+    pub file_mappings: String,
+    #[serde(default)]
+    pub dialect: Dialect,
+}
+
+/// Last accepted upload of a vendor, used to diff the next one against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VendorRecord {
+    pub vendor_name: String,
+    pub file_mappings: String,
+    #[serde(default)]
+    pub dialect: Dialect,
+    pub dataset: Dataset,
+    /// Every accepted upload, oldest first.
+    #[serde(default)]
+    pub history: Vec<Snapshot>,
+    /// Every profile the vendor has had, oldest first; the last is the one in use.
+    #[serde(default)]
+    pub versions: Vec<ProfileVersion>,
+}
+
+impl VendorRecord {
+    /// A vendor with no profile and nothing uploaded yet.
+    pub fn new(vendor_name: impl Into<String>) -> Self {
+        VendorRecord {
+            vendor_name: vendor_name.into(),
+            file_mappings: String::new(),
+            dialect: Dialect::default(),
+            dataset: Dataset::default(),
+            history: Vec::new(),
+            versions: Vec::new(),
+        }
+    }
+
+    /// The profile version in use; 0 until one is recorded.
+    pub fn version(&self) -> u32 {
+        self.versions.last().map_or(0, |v| v.version)
+    }
+
+    /// Switches to `file_mappings` and `dialect`, recording them as a new
+    /// version unless they are the profile in use, and returns its version.
+    pub fn set_profile(&mut self, file_mappings: &str, dialect: &Dialect, author: &str, note: &str) -> u32 {
+        self.seed_first_version(now());
+        let mapping = dataset::parse_mappings(file_mappings);
+        let current = self
+            .versions
+            .last()
+            .is_some_and(|v| dataset::parse_mappings(&v.file_mappings) == mapping && v.dialect == *dialect);
+        if !current {
+            self.versions.push(ProfileVersion {
+                version: self.version() + 1,
+                author: author.to_string(),
+                timestamp: now(),
+                note: note.trim().to_string(),
+                file_mappings: file_mappings.to_string(),
+                dialect: dialect.clone(),
+            });
+        }
+        self.file_mappings = file_mappings.to_string();
+        self.dialect = dialect.clone();
+        self.version()
+    }
+
+    /// A record saved before versions were kept starts with its profile as
+    /// version 1, dated `timestamp`.
+    fn seed_first_version(&mut self, timestamp: u64) {
+        if self.versions.is_empty() && !self.file_mappings.is_empty() {
+            self.versions.push(ProfileVersion {
+                version: 1,
+                author: String::new(),
+                timestamp,
+                note: String::new(),
+                file_mappings: self.file_mappings.clone(),
+                dialect: self.dialect.clone(),
+            });
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// A saved record read only as far as whose it is.
+#[derive(Deserialize)]
+struct VendorName {
+    vendor_name: String,
+}
+
+trait Named {
+    fn vendor_name(&self) -> &str;
+}
+
+impl Named for VendorName {
+    fn vendor_name(&self) -> &str {
+        &self.vendor_name
+    }
+}
+
+impl Named for VendorRecord {
+    fn vendor_name(&self) -> &str {
+        &self.vendor_name
+    }
+}
+
+/// One JSON file per vendor under the app data directory.
+#[derive(Clone)]
+pub struct VendorStore {
+    dir: PathBuf,
+    /// Shared by every clone, so a save anywhere in the process shows.
+    generation: Arc<AtomicU64>,
+}
+
+impl VendorStore {
+    pub fn new(dir: PathBuf) -> Self {
+        VendorStore { dir, generation: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Changes whenever a vendor is saved through this store or a clone of it.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// The vendor's file, its name percent-encoded so that no two vendors share one.
+    fn path(&self, vendor_name: &str) -> PathBuf {
+        let mut file_name = String::new();
+        for byte in vendor_name.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+                file_name.push(char::from(byte));
+            } else {
+                file_name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    /// Where vendors were saved before file names were encoded, with every
+    /// other character replaced by `_`.
+    fn legacy_path(&self, vendor_name: &str) -> PathBuf {
+        let file_name: String = vendor_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    /// The record in `path`, if it is the one of `vendor_name`; a file system
+    /// that ignores case may hold another vendor's there.
+    fn read<T: DeserializeOwned + Named>(path: &Path, vendor_name: &str) -> Result<Option<T>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let record: T = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        if record.vendor_name() != vendor_name {
+            return Err(format!("{} holds vendor \"{}\", not \"{}\"", path.display(), record.vendor_name(), vendor_name));
+        }
+        Ok(Some(record))
+    }
+
+    pub fn load(&self, vendor_name: &str) -> Result<Option<VendorRecord>, String> {
+        let mut path = self.path(vendor_name);
+        let mut record = Self::read::<VendorRecord>(&path, vendor_name)?;
+        if record.is_none() {
+            path = self.legacy_path(vendor_name);
+            // Another vendor in the legacy file just means this one was never saved.
+            record = Self::read(&path, vendor_name).ok().flatten();
+        }
+        let Some(mut record) = record else { return Ok(None) };
+        // The file was last written with that profile, so it is as old as the file.
+        let modified = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(now, |d| d.as_secs());
+        record.seed_first_version(modified);
+        Ok(Some(record))
+    }
+
+    /// Refuses to write over another vendor's file.
+    pub fn save(&self, record: &VendorRecord) -> Result<(), String> {
+        let path = self.path(&record.vendor_name);
+        Self::read::<VendorName>(&path, &record.vendor_name)?;
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        let written = fs::write(&path, json).map_err(|e| e.to_string());
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        written?;
+        // The record has moved out of its legacy file, if it had one.
+        let legacy = self.legacy_path(&record.vendor_name);
+        if legacy != path && Self::read::<VendorName>(&legacy, &record.vendor_name).is_ok_and(|found| found.is_some()) {
+            fs::remove_file(legacy).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// The exchange rates prices are converted with, kept in the same directory.
+    pub fn rates(&self) -> Result<RateTable, String> {
+        RateTable::load(&self.dir.join(RATES_FILE))
+    }
+
+    pub fn save_rates(&self, rates: &mut RateTable) -> Result<(), String> {
+        rates.save(&self.dir.join(RATES_FILE))
+    }
+
+    pub fn list(&self) -> Result<Vec<String>, String> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut vendors = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let json = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+                if let Ok(record) = serde_json::from_str::<VendorRecord>(&json) {
+                    vendors.push(record.vendor_name);
+                }
+            }
+        }
+        vendors.sort();
+        Ok(vendors)
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio_rustls::rustls::{self, ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+/// A connected stream to the ingest service, whatever carries it.
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// How to reach the ingest service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Transport {
+    /// A Unix socket on this machine.
+    #[cfg(unix)]
+    Unix { path: PathBuf },
+    /// Plain TCP to `host:port`.
+    Tcp { address: String },
+    /// TLS over TCP to `host:port`.
+    Tls {
+        address: String,
+        /// Name checked against the server's certificate; defaults to the host of `address`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        server_name: Option<String>,
+        /// PEM file of the CA that signed the server's certificate; defaults
+        /// to the public web roots.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ca_cert: Option<PathBuf>,
+        /// PEM certificate chain and private key presented to services that
+        /// require client authentication.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_cert: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_key: Option<PathBuf>,
+    },
+}
+
+impl Transport {
+    /// Reads `tcp://host:port`, `tls://host:port` or, on Unix, a socket path.
+    pub fn parse(location: &str) -> Result<Self, String> {
+        if let Some(address) = location.strip_prefix("tcp://") {
+            return Ok(Transport::Tcp { address: address.to_string() });
+        }
+        if let Some(address) = location.strip_prefix("tls://") {
+            return Ok(Transport::Tls {
+                address: address.to_string(),
+                server_name: None,
+                ca_cert: None,
+                client_cert: None,
+                client_key: None,
+            });
+        }
+        #[cfg(unix)]
+        {
+            Ok(Transport::Unix { path: PathBuf::from(location) })
+        }
+        #[cfg(not(unix))]
+        {
+            Err(format!("{}: expected tcp://host:port or tls://host:port", location))
+        }
+    }
+
+    pub async fn connect(&self) -> Result<Box<dyn Connection>, String> {
+        match self {
+            #[cfg(unix)]
+            Transport::Unix { path } => {
+                let stream = tokio::net::UnixStream::connect(path).await.map_err(|e| format!("{}: {}", self, e))?;
+                Ok(Box::new(stream))
+            }
+            Transport::Tcp { address } => {
+                let stream = TcpStream::connect(address).await.map_err(|e| format!("{}: {}", self, e))?;
+                Ok(Box::new(stream))
+            }
+            Transport::Tls { address, server_name, ca_cert, client_cert, client_key } => {
+                let config = tls_config(ca_cert.as_deref(), client_cert.as_deref(), client_key.as_deref())?;
+                let connector = TlsConnector::from(Arc::new(config));
+                let name = match server_name {
+                    Some(name) => name.clone(),
+                    None => host(address).to_string(),
+                };
+                let name = ServerName::try_from(name).map_err(|e| format!("{}: {}", self, e))?;
+                let stream = TcpStream::connect(address).await.map_err(|e| format!("{}: {}", self, e))?;
+                let stream = connector.connect(name, stream).await.map_err(|e| format!("{}: {}", self, e))?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(unix)]
+            Transport::Unix { path } => write!(f, "{}", path.display()),
+            Transport::Tcp { address } => write!(f, "tcp://{}", address),
+            Transport::Tls { address, .. } => write!(f, "tls://{}", address),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl From<&Path> for Transport {
+    fn from(path: &Path) -> Self {
+        Transport::Unix { path: path.to_path_buf() }
+    }
+}
+
+#[cfg(unix)]
+impl From<PathBuf> for Transport {
+    fn from(path: PathBuf) -> Self {
+        Transport::Unix { path }
+    }
+}
+
+/// `host` of `host:port`, without the brackets of an IPv6 address.
+fn host(address: &str) -> &str {
+    let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Trusts `ca_cert`, or the public web roots without one, and presents the
+/// client certificate when both of its files are given.
+pub(crate) fn tls_config(ca_cert: Option<&Path>, client_cert: Option<&Path>, client_key: Option<&Path>) -> Result<ClientConfig, String> {
+    let mut roots = RootCertStore::empty();
+    match ca_cert {
+        Some(path) => {
+            for cert in read_certs(path)? {
+                roots.add(cert).map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_root_certificates(roots);
+    match (client_cert, client_key) {
+        (Some(cert), Some(key)) => {
+            let key = PrivateKeyDer::from_pem_file(key).map_err(|e| format!("{}: {}", key.display(), e))?;
+            builder.with_client_auth_cert(read_certs(cert)?, key).map_err(|e| e.to_string())
+        }
+        (None, None) => Ok(builder.with_no_client_auth()),
+        _ => Err("client_cert and client_key must be set together".to_string()),
+    }
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
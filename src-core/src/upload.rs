@@ -0,0 +1,303 @@
+use std::fmt;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::audit::{self, AuditAction, AuditEntry, AuditLog};
+use crate::client::IngestClient;
+use crate::dataset::{self, Dataset, Mapping};
+use crate::dialect::Dialect;
+use crate::export::Table;
+use crate::protocol::IDEMPOTENCY_KEY;
+use crate::session::Permission;
+use crate::source::{self, FileSource, FileSummary};
+use crate::store::{Snapshot, VendorRecord, VendorStore, MAX_HISTORY};
+use crate::validation::{self, ValidationReport};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct VendorInfo {
+    pub vendor_name: String,
+    pub password: String,
+    pub file_mappings: String,
+    #[serde(default)]
+    pub file_bytes: Vec<u8>,
+    /// Set when the file was picked natively; the backend then reads it itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    #[serde(default)]
+    pub delta_only: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_keys: Vec<String>,
+    /// Unset to use the vendor's saved dialect, or the default for a new vendor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<Dialect>,
+    /// Why the mapping or dialect changed, kept with the new profile version.
+    /// Never sent to the service.
+    #[serde(default, skip_serializing)]
+    pub change_note: String,
+}
+
+/// Leaves out the password and the file's contents, so payloads can be logged.
+impl fmt::Debug for VendorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VendorInfo")
+            .field("vendor_name", &self.vendor_name)
+            .field("password", &if self.password.is_empty() { "" } else { "[redacted]" })
+            .field("file_mappings", &self.file_mappings)
+            .field("file_bytes", &format_args!("[{} bytes]", self.file_bytes.len()))
+            .field("file_path", &self.file_path)
+            .field("delta_only", &self.delta_only)
+            .field("removed_keys", &self.removed_keys.len())
+            .field("dialect", &self.dialect)
+            .field("change_note", &self.change_note)
+            .finish()
+    }
+}
+
+impl VendorInfo {
+    pub fn take_source(&mut self) -> FileSource {
+        match self.file_path.take() {
+            Some(path) => FileSource::Path(PathBuf::from(path)),
+            None => FileSource::Bytes(std::mem::take(&mut self.file_bytes)),
+        }
+    }
+}
+
+/// Falls back to the vendor's stored mappings when the caller did not send any.
+pub fn resolve_mappings(payload: &VendorInfo, previous: Option<&VendorRecord>) -> String {
+    match previous {
+        Some(record) if payload.file_mappings.is_empty() => record.file_mappings.clone(),
+        _ => payload.file_mappings.clone(),
+    }
+}
+
+/// Falls back to the vendor's saved dialect when the caller did not send one.
+pub fn resolve_dialect(payload: &VendorInfo, previous: Option<&VendorRecord>) -> Dialect {
+    match (&payload.dialect, previous) {
+        (Some(dialect), _) => dialect.clone(),
+        (None, Some(record)) => record.dialect.clone(),
+        (None, None) => Dialect::default(),
+    }
+}
+
+/// What the wizard shows of a file before it is sent: its first rows read in
+/// the resolved dialect, the mapping (detected when the caller sent none) and
+/// how the file validates against it. An unset decimal separator is the one
+/// the file's prices show.
+#[derive(Debug, Serialize)]
+pub struct Inspection {
+    pub summary: FileSummary,
+    pub dialect: Dialect,
+    pub mapping: Mapping,
+    pub validation: ValidationReport,
+}
+
+pub fn inspect(mut payload: VendorInfo, store: &VendorStore) -> Result<Inspection, String> {
+    let previous = store.load(&payload.vendor_name)?;
+    let mut dialect = resolve_dialect(&payload, previous.as_ref());
+    dialect.check()?;
+    let original = payload.take_source();
+    let mut summary = source::summarize_source(&original)?;
+    let normalized = dialect.normalize(original)?;
+    let (path, name, size) = (summary.path, summary.name, summary.size);
+    summary = FileSummary { path, name, size, ..source::summarize_source(&normalized)? };
+
+    let mapping = if payload.file_mappings.is_empty() {
+        let saved = previous.as_ref().map(|record| dataset::parse_mappings(&record.file_mappings));
+        dataset::detect_mapping(&summary.headers, saved.as_ref())
+    } else {
+        dataset::parse_mappings(&payload.file_mappings)
+    };
+    if dialect.decimal.is_none() {
+        // Offered to the operator to confirm, rather than guessing price by price.
+        dialect.decimal = dataset::detect_decimal(normalized.reader()?, &mapping);
+    }
+    let mut validation = validation::validate(normalized.reader()?, &mapping, dialect.decimal);
+    let rates = store.rates()?;
+    let missing: Vec<&str> =
+        validation.currencies.iter().map(String::as_str).filter(|currency| !rates.converts(currency)).collect();
+    if !missing.is_empty() {
+        validation.errors.push(format!(
+            "Prices are converted to {}, but the rate table has no rate for {}",
+            rates.convert_to,
+            missing.join(", ")
+        ));
+    }
+    Ok(Inspection {
+        summary,
+        dialect,
+        mapping,
+        validation,
+    })
+}
+
+/// The rows `payload` would send, mapped and trimmed, to hand to someone
+/// else instead. A file that fails validation is not exported.
+pub fn export_table(mut payload: VendorInfo, store: &VendorStore) -> Result<Table, String> {
+    let previous = store.load(&payload.vendor_name)?;
+    let mapping = dataset::parse_mappings(&resolve_mappings(&payload, previous.as_ref()));
+    let dialect = resolve_dialect(&payload, previous.as_ref());
+    let source = dialect.normalize(payload.take_source())?;
+    let report = validation::validate(source.reader()?, &mapping, dialect.decimal);
+    if !report.is_valid() {
+        return Err(report.errors.join("\n"));
+    }
+    let mut dataset = Dataset::from_csv(source.reader()?, &mapping, dialect.decimal)?;
+    store.rates()?.convert(&mut dataset)?;
+    Ok(Table::from_dataset(&dataset, &mapping))
+}
+
+/// What an operator needs to send `payload`: setting up a new vendor, or
+/// uploading with a mapping other than the saved one, takes more than an upload.
+pub fn required_permission(payload: &VendorInfo, previous: Option<&VendorRecord>) -> Permission {
+    match previous {
+        None => Permission::CreateVendor,
+        Some(record) if dataset::parse_mappings(&resolve_mappings(payload, previous)) != dataset::parse_mappings(&record.file_mappings) => {
+            Permission::EditMapping
+        }
+        Some(_) => Permission::Upload,
+    }
+}
+
+/// Validates the file, sends it (or only its delta) to the ingest service and
+/// records it as the vendor's last accepted dataset. Each call is one upload
+/// with its own idempotency key, so the client may retry it safely. A new
+/// profile version is recorded as `author`'s.
+pub async fn submit(mut payload: VendorInfo, store: &VendorStore, client: &IngestClient, author: &str) -> Result<String, String> {
+    let prepared = {
+        let store = store.clone();
+        blocking(move || {
+            let previous = store.load(&payload.vendor_name)?;
+            let source = payload.take_source();
+            prepare(payload, source, previous, &store)
+        })
+        .await??
+    };
+    send(prepared, store, client, author).await.map(|(message, _)| message)
+}
+
+/// Like `submit`, and records the upload in `audit` as done by `actor`,
+/// whether it went through or not, with the profile version it used.
+pub async fn submit_audited(
+    mut payload: VendorInfo,
+    store: &VendorStore,
+    client: &IngestClient,
+    audit: &AuditLog,
+    actor: &str,
+    author: &str,
+) -> Result<String, String> {
+    let (mut entry, prepared) = {
+        let (store, actor) = (store.clone(), actor.to_string());
+        blocking(move || {
+            let previous = store.load(&payload.vendor_name)?;
+            let action = match required_permission(&payload, previous.as_ref()) {
+                Permission::CreateVendor => AuditAction::CreateVendor,
+                Permission::EditMapping => AuditAction::ChangeMapping,
+                _ => AuditAction::Upload,
+            };
+            let mut entry = AuditEntry::new(actor, action, &payload.vendor_name);
+            entry.before = previous
+                .as_ref()
+                .map(|record| json!({ "file_mappings": record.file_mappings, "profile_version": record.version() }));
+            entry.after = Some(json!({
+                "file_mappings": resolve_mappings(&payload, previous.as_ref()),
+                "file_path": payload.file_path,
+                "delta_only": payload.delta_only,
+            }));
+            let source = payload.take_source();
+            entry.file_sha256 = Some(audit::sha256(source.reader()?)?);
+            Ok::<_, String>((entry, prepare(payload, source, previous, &store)))
+        })
+        .await??
+    };
+
+    let result = match prepared {
+        Ok(prepared) => send(prepared, store, client, author).await,
+        Err(e) => Err(e),
+    };
+    let result = result.map(|(message, version)| {
+        if let Some(after) = entry.after.as_mut() {
+            after["profile_version"] = json!(version);
+        }
+        message
+    });
+    audit.record(entry, result, "Uploaded")
+}
+
+/// Runs file work that blocks, such as reading and validating a large file,
+/// off the async runtime's workers.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| e.to_string())
+}
+
+/// An upload read, validated and mapped, ready to send.
+struct Prepared {
+    payload: VendorInfo,
+    source: FileSource,
+    previous: Option<VendorRecord>,
+    /// The mapping and dialect as given, saved with the vendor's profile.
+    file_mappings: String,
+    dialect: Dialect,
+    current: Dataset,
+}
+
+fn prepare(mut payload: VendorInfo, source: FileSource, previous: Option<VendorRecord>, store: &VendorStore) -> Result<Prepared, String> {
+    payload.file_mappings = resolve_mappings(&payload, previous.as_ref());
+    let dialect = resolve_dialect(&payload, previous.as_ref());
+    dialect.check()?;
+    // The service only ever gets UTF-8 with commas.
+    let mut source = dialect.normalize(source)?;
+    payload.dialect = None;
+    let file_mappings = payload.file_mappings.clone();
+    let mut mapping = dataset::parse_mappings(&file_mappings);
+    let report = validation::validate(source.reader()?, &mapping, dialect.decimal);
+    if !report.is_valid() {
+        return Err(report.errors.join("\n"));
+    }
+    let mut current = Dataset::from_csv(source.reader()?, &mapping, dialect.decimal)?;
+    store.rates()?.convert(&mut current)?;
+    // Nor does it know formulas, locations or prices as vendors write them, so
+    // it gets one plain column per field and one row per item.
+    if dataset::has_formulas(&mapping) || dataset::has_locations(&mapping) || dataset::has_prices(&mapping) {
+        let (bytes, plain) = current.to_csv()?;
+        source = FileSource::Bytes(bytes);
+        payload.file_mappings = dataset::format_mappings(&plain);
+        mapping = plain;
+    }
+
+    if payload.delta_only {
+        if let Some(record) = &previous {
+            let delta = dataset::diff(&record.dataset, &current);
+            let delta_bytes = dataset::filter_csv(source.reader()?, &mapping, &delta.upserted_keys())?;
+            source = FileSource::Bytes(delta_bytes);
+            payload.removed_keys = delta.removed_keys();
+        }
+    }
+    Ok(Prepared { payload, source, previous, file_mappings, dialect, current })
+}
+
+async fn send(prepared: Prepared, store: &VendorStore, client: &IngestClient, author: &str) -> Result<(String, u32), String> {
+    let Prepared { payload, source, previous, file_mappings, dialect, current } = prepared;
+    let fields = match serde_json::to_value(&payload).map_err(|e| e.to_string())? {
+        Value::Object(mut fields) => {
+            fields.remove("file_bytes");
+            fields.insert(IDEMPOTENCY_KEY.to_string(), Value::String(Uuid::new_v4().to_string()));
+            fields
+        }
+        _ => unreachable!("VendorInfo serializes to an object"),
+    };
+    tracing::info!(vendor = %payload.vendor_name, ?payload, "sending upload");
+    let message = client.send(fields, &source).await.inspect_err(|e| {
+        tracing::warn!(vendor = %payload.vendor_name, error = %e, "upload failed");
+    })?;
+
+    let mut record = previous.unwrap_or_else(|| VendorRecord::new(&payload.vendor_name));
+    let version = record.set_profile(&file_mappings, &dialect, author, &payload.change_note);
+    record.history.push(Snapshot { version, ..Snapshot::of(&current) });
+    record.history.drain(..record.history.len().saturating_sub(MAX_HISTORY));
+    record.dataset = current;
+    let store = store.clone();
+    blocking(move || store.save(&record)).await??;
+    Ok((message, version))
+}
@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Read;
+
+use csv::ReaderBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::dataset::{self, Mapping, RowMapper, FILE_KEY, LOCATION, TARGET_FIELDS};
+use crate::pricing::{CURRENCY, PRICE_FIELDS};
+
+/// Stop listing row-level problems after this many, the count is still reported.
+const MAX_ROW_ERRORS: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub row_count: usize,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Warehouses the file has quantities for, from warehouse columns or the location column.
+    #[serde(default)]
+    pub locations: Vec<String>,
+    /// Currencies the file's prices are in.
+    #[serde(default)]
+    pub currencies: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+pub fn validate(reader: impl Read, mapping: &Mapping, decimal: Option<char>) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+    let headers = match rdr.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            report.errors.push(format!("Could not read the header row: {}", e));
+            return report;
+        }
+    };
+
+    if !mapping.contains_key(FILE_KEY) {
+        report.errors.push(format!("\"{}\" is not mapped", FILE_KEY));
+    }
+    let warehouses: BTreeSet<&str> = mapping.keys().filter_map(|target| dataset::location_of(target)).collect();
+    let pivot = mapping.contains_key(LOCATION);
+    for target in TARGET_FIELDS {
+        // Prices are optional.
+        let optional = PRICE_FIELDS.contains(&target) || (target == "Quantity" && !warehouses.is_empty());
+        if target != FILE_KEY && !optional && !mapping.contains_key(target) {
+            report.warnings.push(format!("\"{}\" is not mapped", target));
+        }
+    }
+    if warehouses.contains("") {
+        report.errors.push("A warehouse quantity has no warehouse name".to_string());
+    }
+    if pivot && !warehouses.is_empty() {
+        report.errors.push(format!("Map quantities per warehouse or a \"{}\" column, not both", LOCATION));
+    } else if pivot && !mapping.contains_key("Quantity") {
+        report.errors.push(format!("\"{}\" needs \"Quantity\" mapped", LOCATION));
+    } else if !warehouses.is_empty() && mapping.contains_key("Quantity") {
+        report.warnings.push("\"Quantity\" is replaced by the total of the warehouse quantities".to_string());
+    }
+    let mapper = match RowMapper::new(&headers, mapping) {
+        Ok(mapper) if report.errors.is_empty() => mapper,
+        Ok(_) => return report,
+        Err(errors) => {
+            report.errors.extend(errors);
+            return report;
+        }
+    };
+
+    let mut seen = HashSet::new();
+    let mut row_errors = Vec::new();
+    let mut empty_keys = 0;
+    let mut zero_prices: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut currencies = BTreeSet::new();
+    let mut locations: BTreeSet<String> = warehouses.iter().map(|warehouse| warehouse.to_string()).collect();
+    for (index, record) in rdr.records().enumerate() {
+        // Header is line 1.
+        let line = index + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                row_errors.push(format!("Line {}: {}", line, e));
+                continue;
+            }
+        };
+        report.row_count += 1;
+
+        let (mut key, mut location) = (None, String::new());
+        let mut row = BTreeMap::new();
+        for target in mapping.keys() {
+            let value = match mapper.value(target, &record) {
+                Ok(value) => value,
+                Err(e) => {
+                    row_errors.push(format!("Line {}: {}: {}", line, target, e));
+                    continue;
+                }
+            };
+            row.insert(target.clone(), value.clone());
+            if target == FILE_KEY {
+                key = Some(value);
+            } else if target == LOCATION {
+                location = value;
+            } else if dataset::is_quantity(target) && !value.is_empty() && value.parse::<f64>().is_err() {
+                row_errors.push(format!("Line {}: {} \"{}\" is not a number", line, target, value));
+            }
+        }
+        match mapper.normalize_prices(&mut row, decimal) {
+            Ok(()) => {
+                for field in PRICE_FIELDS {
+                    match row.get(field).and_then(|price| price.parse::<f64>().ok()) {
+                        Some(price) if price < 0.0 => row_errors.push(format!("Line {}: {} {} is below zero", line, field, price)),
+                        Some(0.0) => *zero_prices.entry(field).or_insert(0) += 1,
+                        _ => {}
+                    }
+                }
+                currencies.extend(row.remove(CURRENCY));
+            }
+            Err(e) => row_errors.push(format!("Line {}: {}", line, e)),
+        }
+        match key {
+            None => {}
+            Some(key) if key.is_empty() => empty_keys += 1,
+            Some(key) if pivot && location.is_empty() => {
+                row_errors.push(format!("Line {}: {} \"{}\" has no {}", line, FILE_KEY, key, LOCATION));
+            }
+            Some(key) if !seen.insert((key.clone(), location.clone())) => {
+                if pivot {
+                    row_errors.push(format!("Line {}: duplicate {} \"{}\" at \"{}\"", line, FILE_KEY, key, location));
+                } else {
+                    row_errors.push(format!("Line {}: duplicate {} \"{}\"", line, FILE_KEY, key));
+                }
+            }
+            Some(_) => {}
+        }
+        if pivot && !location.is_empty() {
+            locations.insert(location);
+        }
+    }
+
+    if report.row_count == 0 {
+        report.errors.push("The file has no data rows".to_string());
+    }
+    if empty_keys > 0 {
+        report.warnings.push(format!("{} rows without a {} will be skipped", empty_keys, FILE_KEY));
+    }
+    for (field, count) in zero_prices {
+        report.warnings.push(format!("{} rows have a {} of zero", count, field));
+    }
+    if row_errors.len() > MAX_ROW_ERRORS {
+        let hidden = row_errors.len() - MAX_ROW_ERRORS;
+        row_errors.truncate(MAX_ROW_ERRORS);
+        row_errors.push(format!("... and {} more", hidden));
+    }
+    report.errors.extend(row_errors);
+    report.locations = locations.into_iter().collect();
+    report.currencies = currencies.into_iter().collect();
+    report
+}
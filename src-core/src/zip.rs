@@ -0,0 +1,42 @@
+use std::io::{Cursor, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime};
+
+use crate::audit;
+
+/// Deflated entries built up in memory; large bundles switch to zip64 as needed.
+pub(crate) struct ZipWriter {
+    zip: zip::ZipWriter<Cursor<Vec<u8>>>,
+}
+
+impl Default for ZipWriter {
+    fn default() -> Self {
+        ZipWriter { zip: zip::ZipWriter::new(Cursor::new(Vec::new())) }
+    }
+}
+
+impl ZipWriter {
+    pub(crate) fn add(&mut self, name: &str, contents: &[u8]) -> Result<(), String> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(now())
+            .large_file(contents.len() as u64 >= u32::MAX as u64);
+        self.zip.start_file(name, options).map_err(|e| e.to_string())?;
+        self.zip.write_all(contents).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn finish(self) -> Result<Vec<u8>, String> {
+        self.zip.finish().map(Cursor::into_inner).map_err(|e| e.to_string())
+    }
+}
+
+/// Current UTC time; the zip format cannot hold dates before 1980.
+fn now() -> DateTime {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    let (year, month, day) = audit::civil_date(now);
+    let secs = now % 86_400;
+    DateTime::from_date_and_time(year as u16, month as u8, day as u8, (secs / 3_600) as u8, (secs % 3_600 / 60) as u8, (secs % 60) as u8)
+        .unwrap_or_default()
+}
@@ -0,0 +1,85 @@
+//! Appends to, filters and exports the audit log.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use fs_ui_core::audit::{self, AuditAction, AuditEntry, AuditFilter, AuditLog, ExportFormat};
+use serde_json::json;
+
+fn entry(actor: &str, action: AuditAction, target: &str, timestamp: u64) -> AuditEntry {
+    AuditEntry {
+        timestamp,
+        ..AuditEntry::new(actor, action, target)
+    }
+}
+
+#[test]
+fn entries_are_appended_and_filtered() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let log = AuditLog::new(path.clone());
+    log.append(&entry("alice", AuditAction::Login, "alice", 100)).unwrap();
+    log.append(&entry("alice", AuditAction::Upload, "Acme", 200)).unwrap();
+    // A line cut short by a crash does not hide the ones after it.
+    OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"timestamp\":\n").unwrap();
+    log.append(&entry("Bob", AuditAction::Upload, "Globex", 300)).unwrap();
+
+    assert_eq!(log.read(&AuditFilter::default()).unwrap().len(), 3);
+    let uploads = AuditFilter { action: Some(AuditAction::Upload), ..AuditFilter::default() };
+    let targets: Vec<String> = log.read(&uploads).unwrap().into_iter().map(|e| e.target).collect();
+    assert_eq!(targets, ["Acme", "Globex"]);
+    let bob = AuditFilter { actor: Some("bob".to_string()), ..AuditFilter::default() };
+    assert_eq!(log.read(&bob).unwrap()[0].target, "Globex");
+    let window = AuditFilter { since: Some(150), until: Some(250), ..AuditFilter::default() };
+    assert_eq!(log.read(&window).unwrap()[0].action, AuditAction::Upload);
+}
+
+#[test]
+fn failures_keep_their_reason() {
+    let failed = AuditEntry::new("alice", AuditAction::Upload, "Acme").outcome(&Err::<(), _>("Not signed in".to_string()));
+
+    assert!(!failed.ok);
+    assert_eq!(failed.message, "Not signed in");
+}
+
+#[test]
+fn unwritable_log_is_reported_next_to_the_outcome() {
+    let dir = tempfile::tempdir().unwrap();
+    // A directory where the log file should be cannot be appended to.
+    let log = AuditLog::new(dir.path().to_path_buf());
+
+    let saved = log.record(AuditEntry::new("alice", AuditAction::SetRates, "currency rates"), Ok(()), "Rates saved");
+    assert!(saved.unwrap_err().starts_with("Rates saved, but the audit log could not be written"));
+    let failed = log.record(
+        AuditEntry::new("alice", AuditAction::Upload, "Acme"),
+        Err::<(), _>("Bad file".to_string()),
+        "Uploaded",
+    );
+    let message = failed.unwrap_err();
+    assert!(message.starts_with("Bad file"));
+    assert!(message.contains("the audit log could not be written either"));
+}
+
+#[test]
+fn export_to_csv_and_json() {
+    let mut changed = entry("alice", AuditAction::ChangeMapping, "Acme", 1_714_564_800);
+    changed.before = Some(json!({ "file_mappings": "File Key:SKU" }));
+    changed.file_sha256 = Some("ab12".to_string());
+
+    let csv = String::from_utf8(audit::export(&[changed.clone()], ExportFormat::Csv).unwrap()).unwrap();
+    assert_eq!(
+        csv,
+        "time,actor,action,target,before,after,file_sha256,ok,message\n\
+         2024-05-01T12:00:00Z,alice,change_mapping,Acme,\"{\"\"file_mappings\"\":\"\"File Key:SKU\"\"}\",,ab12,true,\n"
+    );
+    let json: Vec<AuditEntry> = serde_json::from_slice(&audit::export(&[changed.clone()], ExportFormat::Json).unwrap()).unwrap();
+    assert_eq!(json, [changed]);
+}
+
+#[test]
+fn file_hash_is_sha256() {
+    assert_eq!(
+        audit::sha256(&b"abc"[..]).unwrap(),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
@@ -0,0 +1,112 @@
+//! Searching, filtering, sorting and paging the ingested catalog.
+
+use std::sync::Arc;
+
+use fs_ui_core::catalog::{self, Catalog, CatalogCache, CatalogQuery, SortField, StockLevel};
+use fs_ui_core::dataset::{self, Dataset};
+use fs_ui_core::dialect::Dialect;
+use fs_ui_core::store::{VendorRecord, VendorStore};
+use tempfile::TempDir;
+
+const MAPPINGS: &str = "File Key:SKU, Brand:Make, Catalog:Line, Description:Name, Quantity:Qty";
+
+fn save(store: &VendorStore, vendor_name: &str, csv: &str) {
+    let dataset = Dataset::from_csv(csv.as_bytes(), &dataset::parse_mappings(MAPPINGS), None).unwrap();
+    store
+        .save(&VendorRecord {
+            vendor_name: vendor_name.to_string(),
+            file_mappings: MAPPINGS.to_string(),
+            dialect: Dialect::default(),
+            dataset,
+            history: Vec::new(),
+            versions: Vec::new(),
+        })
+        .unwrap();
+}
+
+fn store() -> (TempDir, VendorStore) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = VendorStore::new(dir.path().join("vendors"));
+    save(
+        &store,
+        "Acme",
+        "SKU,Make,Line,Name,Qty\nA1,Acme,Tools,Claw hammer,12\nA2,Acme,Tools,Rubber mallet,3\nA3,Bosch,Power,Cordless drill,0\n",
+    );
+    save(&store, "Globex", "SKU,Make,Line,Name,Qty\nG1,Stanley,Tools,Sledge Hammer,\nG2,Bosch,Power,Angle grinder,40\n");
+    (dir, store)
+}
+
+fn keys(catalog: &Catalog, query: CatalogQuery) -> Vec<String> {
+    catalog.query(&query).items.into_iter().map(|item| item.key).collect()
+}
+
+#[test]
+fn search_covers_description_and_brand() {
+    let (_dir, store) = store();
+    let catalog = Catalog::load(&store).unwrap();
+
+    assert_eq!(keys(&catalog, CatalogQuery { search: "HAMMER".to_string(), ..CatalogQuery::default() }), ["A1", "G1"]);
+    assert_eq!(keys(&catalog, CatalogQuery { search: "bosch drill".to_string(), ..CatalogQuery::default() }), ["A3"]);
+    // The catalog line is filtered on, not searched.
+    assert!(keys(&catalog, CatalogQuery { search: "power".to_string(), ..CatalogQuery::default() }).is_empty());
+}
+
+#[test]
+fn filters_combine() {
+    let (_dir, store) = store();
+    let catalog = Catalog::load(&store).unwrap();
+
+    let bosch = CatalogQuery { brand: Some("bosch".to_string()), ..CatalogQuery::default() };
+    assert_eq!(keys(&catalog, bosch.clone()), ["A3", "G2"]);
+    assert_eq!(keys(&catalog, CatalogQuery { vendor: Some("Globex".to_string()), ..bosch }), ["G2"]);
+    let tools = CatalogQuery { catalog: Some("Tools".to_string()), ..CatalogQuery::default() };
+    assert_eq!(keys(&catalog, CatalogQuery { stock: StockLevel::InStock, ..tools.clone() }), ["A1", "A2"]);
+    assert_eq!(keys(&catalog, CatalogQuery { stock: StockLevel::Low, ..tools.clone() }), ["A2"]);
+    assert_eq!(keys(&catalog, CatalogQuery { stock: StockLevel::OutOfStock, ..tools }), ["G1"]);
+}
+
+#[test]
+fn sorting_and_paging_happen_before_the_page_is_returned() {
+    let (_dir, store) = store();
+    let catalog = Catalog::load(&store).unwrap();
+
+    let by_quantity = CatalogQuery { sort: SortField::Quantity, descending: true, page_size: 2, ..CatalogQuery::default() };
+    let first = catalog.query(&by_quantity);
+    assert_eq!(first.total, 5);
+    assert_eq!(first.items.iter().map(|i| i.key.as_str()).collect::<Vec<_>>(), ["G2", "A1"]);
+    assert_eq!(keys(&catalog, CatalogQuery { page: 1, ..by_quantity.clone() }), ["A2", "A3"]);
+    assert_eq!(keys(&catalog, CatalogQuery { page: 2, ..by_quantity }), ["G1"]);
+
+    assert_eq!(first.vendors, ["Acme", "Globex"]);
+    assert_eq!(first.brands, ["Acme", "Bosch", "Stanley"]);
+    assert_eq!(first.catalogs, ["Power", "Tools"]);
+}
+
+#[test]
+fn detail_lists_fields_with_their_source_columns() {
+    let (_dir, store) = store();
+
+    let detail = catalog::detail(&store, "Acme", "A2").unwrap().unwrap();
+
+    assert_eq!(detail.item.quantity, Some(3.0));
+    assert_eq!(detail.fields[0], ("File Key".to_string(), "A2".to_string()));
+    assert!(detail.fields.contains(&("Description".to_string(), "Rubber mallet".to_string())));
+    assert_eq!(detail.mapping["Description"], "Name");
+    assert!(catalog::detail(&store, "Acme", "G1").unwrap().is_none());
+    assert!(catalog::detail(&store, "Initech", "A1").unwrap().is_none());
+}
+
+#[test]
+fn the_cache_is_reloaded_only_after_a_save() {
+    let (_dir, store) = store();
+    let cache = CatalogCache::default();
+
+    let first = cache.get(&store).unwrap();
+    assert!(Arc::ptr_eq(&first, &cache.get(&store).unwrap()));
+
+    // Saved through a clone, as the folder watcher does.
+    save(&store.clone(), "Initech", "SKU,Make,Line,Name,Qty\nI1,Initech,Office,Stapler,7\n");
+    let reloaded = cache.get(&store).unwrap();
+    assert!(!Arc::ptr_eq(&first, &reloaded));
+    assert_eq!(reloaded.query(&CatalogQuery::default()).total, first.query(&CatalogQuery::default()).total + 1);
+}
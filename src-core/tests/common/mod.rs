@@ -0,0 +1,33 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use fs_ui_core::upload::VendorInfo;
+use zip::ZipArchive;
+
+/// An upload of `csv` for "Acme" under `file_mappings`.
+pub fn payload(file_mappings: &str, csv: &str) -> VendorInfo {
+    VendorInfo {
+        vendor_name: "Acme".to_string(),
+        password: "secret".to_string(),
+        file_mappings: file_mappings.to_string(),
+        file_bytes: csv.as_bytes().to_vec(),
+        ..VendorInfo::default()
+    }
+}
+
+/// Reads the entries of a zip, such as a workbook or a diagnostics bundle.
+pub fn unzip(bytes: &[u8]) -> BTreeMap<String, String> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).unwrap();
+        let mut text = String::new();
+        file.read_to_string(&mut text).unwrap();
+        files.insert(file.name().to_string(), text);
+    }
+    files
+}
@@ -0,0 +1,72 @@
+//! Dashboard metrics from vendor history and the audit log.
+
+use fs_ui_core::audit::{AuditAction, AuditEntry};
+use fs_ui_core::dashboard::{Metrics, Periods};
+use fs_ui_core::dataset::Dataset;
+use fs_ui_core::dialect::Dialect;
+use fs_ui_core::store::{Snapshot, VendorRecord};
+
+const PERIODS: Periods = Periods { today: 1_000, week: 500 };
+
+fn record(vendor_name: &str, history: &[(u64, usize)]) -> VendorRecord {
+    VendorRecord {
+        vendor_name: vendor_name.to_string(),
+        file_mappings: "File Key:SKU".to_string(),
+        dialect: Dialect::default(),
+        dataset: Dataset::default(),
+        history: history
+            .iter()
+            .map(|&(timestamp, rows)| Snapshot { timestamp, rows, quantity: rows as f64 * 2.0, version: 1 })
+            .collect(),
+        versions: Vec::new(),
+    }
+}
+
+fn failure(action: AuditAction, timestamp: u64) -> AuditEntry {
+    AuditEntry {
+        timestamp,
+        ..AuditEntry::new("alice", action, "Acme").outcome(&Err::<(), _>("refused".to_string()))
+    }
+}
+
+#[test]
+fn uploads_and_rows_are_counted_per_period_and_vendor() {
+    let records = [
+        record("Acme", &[(100, 10), (600, 20), (1_200, 30)]),
+        record("Globex", &[(1_100, 100)]),
+        record("Initech", &[]),
+    ];
+
+    let metrics = Metrics::compute(&records, &[], PERIODS);
+
+    assert_eq!(metrics.vendors, 3);
+    assert_eq!(metrics.uploads_today, 2);
+    assert_eq!(metrics.uploads_this_week, 3);
+    assert_eq!(metrics.rows_ingested, 160);
+    let top: Vec<(&str, usize, usize)> = metrics
+        .top_vendors
+        .iter()
+        .map(|v| (v.vendor_name.as_str(), v.uploads, v.rows))
+        .collect();
+    assert_eq!(top, [("Globex", 1, 100), ("Acme", 3, 60)]);
+    assert_eq!(metrics.series.len(), 2);
+    assert_eq!(metrics.series[0].points[2].quantity, 60.0);
+}
+
+#[test]
+fn failed_uploads_this_week_are_counted() {
+    let audit = [
+        failure(AuditAction::Upload, 400),
+        failure(AuditAction::Upload, 700),
+        failure(AuditAction::CreateVendor, 1_500),
+        failure(AuditAction::Login, 1_500),
+        AuditEntry { timestamp: 800, ..AuditEntry::new("alice", AuditAction::Upload, "Acme") },
+    ];
+
+    let mut metrics = Metrics::compute(&[], &audit, PERIODS);
+    assert_eq!(metrics.failed_this_week, 2);
+
+    metrics.add_failure(900, PERIODS);
+    metrics.add_failure(10, PERIODS);
+    assert_eq!(metrics.failed_this_week, 3);
+}
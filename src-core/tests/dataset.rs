@@ -0,0 +1,63 @@
+//! What changed between two uploads of the same vendor, and the rows resent for it.
+
+use std::collections::BTreeSet;
+
+use fs_ui_core::dataset::{self, Dataset, FieldChange};
+
+const PREVIOUS: &str = "SKU,Brand,Qty\nA1,Acme,3\nA2,Acme,5\nA3,Acme,1\n";
+const CURRENT: &str = "SKU,Brand,Qty\nA1,Acme,3\nA2,Acme Co,7\nA4,Acme,2\n";
+
+fn read(csv: &str) -> Dataset {
+    let mapping = dataset::parse_mappings("File Key:SKU, Brand:Brand, Quantity:Qty");
+    Dataset::from_csv(csv.as_bytes(), &mapping, None).unwrap()
+}
+
+#[test]
+fn rows_are_added_removed_and_changed_field_by_field() {
+    let diff = dataset::diff(&read(PREVIOUS), &read(CURRENT));
+
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].key, "A4");
+    assert_eq!(diff.added[0].fields["Quantity"], "2");
+    assert_eq!(diff.removed_keys(), ["A3"]);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].key, "A2");
+    assert_eq!(
+        diff.changed[0].fields,
+        [
+            FieldChange { field: "Brand".to_string(), old: "Acme".to_string(), new: "Acme Co".to_string() },
+            FieldChange { field: "Quantity".to_string(), old: "5".to_string(), new: "7".to_string() },
+        ]
+    );
+    assert_eq!(diff.upserted_keys(), BTreeSet::from(["A2".to_string(), "A4".to_string()]));
+}
+
+#[test]
+fn a_field_dropped_from_the_mapping_is_a_change_to_empty() {
+    let mapping = dataset::parse_mappings("File Key:SKU, Quantity:Qty");
+    let current = Dataset::from_csv(PREVIOUS.as_bytes(), &mapping, None).unwrap();
+    let diff = dataset::diff(&read(PREVIOUS), &current);
+
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+    assert_eq!(diff.changed.len(), 3);
+    assert_eq!(diff.changed[0].fields, [FieldChange { field: "Brand".to_string(), old: "Acme".to_string(), new: String::new() }]);
+}
+
+#[test]
+fn unchanged_uploads_have_nothing_to_send() {
+    let diff = dataset::diff(&read(CURRENT), &read(CURRENT));
+    assert_eq!(diff, Default::default());
+    assert!(diff.upserted_keys().is_empty());
+}
+
+#[test]
+fn only_the_rows_to_send_are_kept() {
+    let mapping = dataset::parse_mappings("File Key:SKU, Brand:Brand, Quantity:Qty");
+    let diff = dataset::diff(&read(PREVIOUS), &read(CURRENT));
+
+    let filtered = dataset::filter_csv(CURRENT.as_bytes(), &mapping, &diff.upserted_keys()).unwrap();
+    assert_eq!(String::from_utf8(filtered).unwrap(), "SKU,Brand,Qty\nA2,Acme Co,7\nA4,Acme,2\n");
+
+    let unmapped = dataset::parse_mappings("Brand:Brand");
+    assert!(dataset::filter_csv(CURRENT.as_bytes(), &unmapped, &diff.upserted_keys()).is_err());
+}
@@ -0,0 +1,96 @@
+//! Sniffing and converting vendor file dialects, and inspecting files for the wizard.
+
+use fs_ui_core::dataset;
+use fs_ui_core::dialect::{self, Dialect};
+use fs_ui_core::source::FileSource;
+use fs_ui_core::store::{VendorRecord, VendorStore};
+use fs_ui_core::upload::{self, VendorInfo};
+
+fn normalized(dialect: &Dialect, bytes: &[u8]) -> Result<String, String> {
+    match dialect.normalize(FileSource::Bytes(bytes.to_vec()))? {
+        FileSource::Bytes(bytes) => Ok(String::from_utf8(bytes).unwrap()),
+        FileSource::Path(_) => unreachable!("bytes stay bytes"),
+    }
+}
+
+#[test]
+fn sniff_finds_delimiter_and_encoding() {
+    assert_eq!(dialect::sniff(b"SKU,Brand,Qty\nA1,Acme,5\n"), Dialect::default());
+
+    let semicolons = dialect::sniff(b"SKU;Brand;Qty\nA1;M\xfcller, GmbH;5\nA2;Acme;7\n");
+    assert_eq!(semicolons.delimiter, ';');
+    assert_eq!(semicolons.encoding, "windows-1252");
+
+    assert_eq!(dialect::sniff(b"SKU\tBrand\nA1\tAcme\n").delimiter, '\t');
+    assert_eq!(dialect::sniff(b"\xff\xfeS\0K\0U\0|\0Q\0t\0y\0\n\0").encoding, "utf-16le");
+    assert_eq!(dialect::sniff(b"SKU\nA1\n").delimiter, ',');
+}
+
+#[test]
+fn normalize_rewrites_as_utf8_with_commas() {
+    let dialect = Dialect { delimiter: ';', quote: '\'', encoding: "windows-1252".to_string(), ..Dialect::default() };
+
+    let csv = normalized(&dialect, b"SKU;Brand\nA1;'M\xfcller, GmbH'\n").unwrap();
+
+    assert_eq!(csv, "SKU,Brand\nA1,\"M\u{fc}ller, GmbH\"\n");
+}
+
+#[test]
+fn normalize_reports_bad_dialects() {
+    let utf8 = Dialect { delimiter: ';', ..Dialect::default() };
+    assert!(normalized(&utf8, b"SKU;Brand\nA1;M\xfcller\n").unwrap_err().contains("not valid UTF-8"));
+
+    let unknown = Dialect { encoding: "klingon".to_string(), ..Dialect::default() };
+    assert_eq!(normalized(&unknown, b"SKU\n").unwrap_err(), "Unknown encoding \"klingon\"");
+
+    let wide = Dialect { delimiter: '→', ..Dialect::default() };
+    assert!(normalized(&wide, b"SKU\n").unwrap_err().contains("single ASCII character"));
+}
+
+#[test]
+fn inspect_reads_the_file_in_its_dialect_and_detects_a_mapping() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = VendorStore::new(dir.path().join("vendors"));
+    let payload = VendorInfo {
+        vendor_name: "Acme".to_string(),
+        file_bytes: b"Part No;Qty\nA1;5\nA2;x\n".to_vec(),
+        dialect: Some(Dialect { delimiter: ';', ..Dialect::default() }),
+        ..VendorInfo::default()
+    };
+
+    let inspection = upload::inspect(payload, &store).unwrap();
+
+    assert_eq!(inspection.summary.headers, ["Part No", "Qty"]);
+    assert_eq!(inspection.summary.row_count, 2);
+    assert_eq!(inspection.summary.size, 22);
+    assert_eq!(dataset::format_mappings(&inspection.mapping), "File Key:Part No, Quantity:Qty");
+    assert_eq!(inspection.validation.errors, ["Line 3: Quantity \"x\" is not a number"]);
+    assert_eq!(inspection.dialect.decimal, None);
+
+    // The decimal separator most prices show is offered for the whole file.
+    let priced = VendorInfo {
+        vendor_name: "Acme".to_string(),
+        file_bytes: b"SKU;Qty;Cost\nA1;5;1.500\nA2;3;12.99\nA3;1;4.5\n".to_vec(),
+        dialect: Some(Dialect { delimiter: ';', ..Dialect::default() }),
+        ..VendorInfo::default()
+    };
+    let inspection = upload::inspect(priced, &store).unwrap();
+    assert_eq!(inspection.dialect.decimal, Some('.'));
+    assert!(inspection.validation.is_valid(), "{:?}", inspection.validation.errors);
+}
+
+#[test]
+fn only_an_unset_dialect_falls_back_to_the_saved_one() {
+    let mut record = VendorRecord::new("Acme");
+    record.dialect = Dialect { delimiter: ';', ..Dialect::default() };
+    let mut payload = VendorInfo {
+        vendor_name: "Acme".to_string(),
+        ..VendorInfo::default()
+    };
+
+    assert_eq!(upload::resolve_dialect(&payload, Some(&record)).delimiter, ';');
+    assert_eq!(upload::resolve_dialect(&payload, None), Dialect::default());
+    // A file in the default dialect for a vendor saved with another one.
+    payload.dialect = Some(Dialect::default());
+    assert_eq!(upload::resolve_dialect(&payload, Some(&record)), Dialect::default());
+}
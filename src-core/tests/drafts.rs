@@ -0,0 +1,84 @@
+//! Saving, listing and discarding wizard drafts.
+
+use std::fs;
+
+use fs_ui_core::dataset;
+use fs_ui_core::drafts::{Draft, DraftStore};
+
+fn draft(id: &str, vendor_name: &str) -> Draft {
+    Draft {
+        id: id.to_string(),
+        step: "mapping".to_string(),
+        vendor_name: vendor_name.to_string(),
+        mapping: dataset::parse_mappings("File Key:SKU, Description:=concat(\", \", [Brand], [Model])"),
+        ..Draft::default()
+    }
+}
+
+#[test]
+fn drafts_are_saved_replaced_and_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = DraftStore::new(dir.path().join("drafts"));
+    assert!(store.list().unwrap().is_empty());
+
+    let saved = store.save(draft("1", "Acme")).unwrap();
+    assert!(saved.updated > 0);
+    store.save(draft("2", "Globex")).unwrap();
+    store.save(draft("1", "Acme Corp")).unwrap();
+
+    let drafts = store.list().unwrap();
+    assert_eq!(drafts.len(), 2);
+    let acme = drafts.iter().find(|d| d.id == "1").unwrap();
+    assert_eq!(acme.vendor_name, "Acme Corp");
+    assert_eq!(acme.mapping["Description"], "=concat(\", \", [Brand], [Model])");
+
+    // Drafts from before the mapping was kept parsed still resume with it.
+    fs::write(dir.path().join("drafts/3.json"), r#"{"id":"3","updated":1,"file_mappings":"File Key:SKU, Quantity:Qty"}"#).unwrap();
+    let old = store.list().unwrap().into_iter().find(|d| d.id == "3").unwrap();
+    assert_eq!(old.mapping, dataset::parse_mappings("File Key:SKU, Quantity:Qty"));
+    fs::remove_file(dir.path().join("drafts/3.json")).unwrap();
+
+    store.remove("1").unwrap();
+    store.remove("1").unwrap();
+    assert_eq!(store.list().unwrap().iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), ["2"]);
+}
+
+#[test]
+fn ids_cannot_leave_the_drafts_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = DraftStore::new(dir.path().join("drafts"));
+
+    assert!(store.save(draft("../vendors/acme", "Acme")).is_err());
+    assert!(store.save(draft("", "Acme")).is_err());
+    assert!(store.remove("../audit").is_err());
+}
+
+#[test]
+fn interrupted_saves_leave_the_previous_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let drafts_dir = dir.path().join("drafts");
+    let store = DraftStore::new(drafts_dir.clone());
+    store.save(draft("1", "Acme")).unwrap();
+
+    // A save that cannot write its temporary file never touches the draft.
+    fs::create_dir(drafts_dir.join("1.json.partial")).unwrap();
+    assert!(store.save(draft("1", "Acme Corp")).is_err());
+    assert_eq!(store.list().unwrap()[0].vendor_name, "Acme");
+    fs::remove_dir(drafts_dir.join("1.json.partial")).unwrap();
+
+    // A finished save swaps a new file in rather than writing over the old one.
+    #[cfg(unix)]
+    let before = std::os::unix::fs::MetadataExt::ino(&fs::metadata(drafts_dir.join("1.json")).unwrap());
+    store.save(draft("1", "Acme Corp")).unwrap();
+    #[cfg(unix)]
+    assert_ne!(std::os::unix::fs::MetadataExt::ino(&fs::metadata(drafts_dir.join("1.json")).unwrap()), before);
+    assert!(!drafts_dir.join("1.json.partial").exists());
+
+    // What a crash part way through saving, or an older version writing in place, leaves behind.
+    fs::write(drafts_dir.join("1.json.partial"), "{\"id\":\"1\",\"vendor_na").unwrap();
+    fs::write(drafts_dir.join("2.json"), "{\"id\":\"2\",\"vendor_na").unwrap();
+
+    let drafts = store.list().unwrap();
+    assert_eq!(drafts.len(), 1);
+    assert_eq!(drafts[0].vendor_name, "Acme Corp");
+}
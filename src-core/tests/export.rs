@@ -0,0 +1,102 @@
+//! Exporting mapped rows in a chosen column order.
+
+mod common;
+
+use fs_ui_core::catalog::{Catalog, CatalogQuery};
+use fs_ui_core::dataset::{self, Dataset};
+use fs_ui_core::dialect::Dialect;
+use fs_ui_core::export::{ExportFormat, Table};
+use fs_ui_core::store::{VendorRecord, VendorStore};
+use fs_ui_core::upload::{self, VendorInfo};
+
+use common::{payload, unzip};
+
+const MAPPINGS: &str = "File Key:SKU, Brand:Make, Description:Name, Quantity:Qty";
+
+fn table() -> Table {
+    let csv = "SKU,Make,Name,Qty,Unused\nA2, Acme ,\"Mallet, rubber\",3,x\nA1,Acme,Hammer & <nails>,12.50,y\n";
+    let mapping = dataset::parse_mappings(MAPPINGS);
+    Table::from_dataset(&Dataset::from_csv(csv.as_bytes(), &mapping, None).unwrap(), &mapping)
+}
+
+fn columns(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn mapped_fields_follow_the_schema_and_can_be_reordered() {
+    let table = table();
+    assert_eq!(table.columns, ["File Key", "Brand", "Description", "Quantity"]);
+
+    let picked = table.select(&columns(&["Quantity", "File Key"])).unwrap();
+    let csv = String::from_utf8(picked.write(ExportFormat::Csv).unwrap()).unwrap();
+    assert_eq!(csv, "Quantity,File Key\n12.50,A1\n3,A2\n");
+
+    assert!(self::table().select(&columns(&["Catalog"])).is_err());
+    assert!(self::table().select(&columns(&["Brand", "Brand"])).is_err());
+    assert!(self::table().select(&[]).is_err());
+}
+
+#[test]
+fn json_lines_keep_the_column_order() {
+    let picked = table().select(&columns(&["Description", "File Key"])).unwrap();
+
+    let jsonl = String::from_utf8(picked.write(ExportFormat::Jsonl).unwrap()).unwrap();
+
+    assert_eq!(jsonl, "{\"Description\":\"Hammer & <nails>\",\"File Key\":\"A1\"}\n{\"Description\":\"Mallet, rubber\",\"File Key\":\"A2\"}\n");
+}
+
+#[test]
+fn workbooks_hold_one_sheet_with_numeric_quantities() {
+    let xlsx = table().write(ExportFormat::Xlsx).unwrap();
+
+    let files = unzip(&xlsx);
+    assert!(files.contains_key("[Content_Types].xml"));
+    assert!(files["xl/workbook.xml"].contains("<sheet name=\"Export\""));
+    let sheet = &files["xl/worksheets/sheet1.xml"];
+    assert!(sheet.contains("<c r=\"A1\" t=\"s\"><v>0</v></c>"));
+    assert!(sheet.contains("<c r=\"C2\" t=\"s\"><v>6</v></c>"));
+    assert!(sheet.contains("<c r=\"D2\"><v>12.5</v></c>"));
+    let strings = &files["xl/sharedStrings.xml"];
+    assert!(strings.contains("<si><t>File Key</t></si>"));
+    assert!(strings.contains("<si><t>Hammer &amp; &lt;nails&gt;</t></si>"));
+}
+
+#[test]
+fn files_are_exported_as_they_would_be_sent() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = VendorStore::new(dir.path().join("vendors"));
+    let payload = |csv: &str| VendorInfo {
+        dialect: Some(Dialect { delimiter: ';', ..Dialect::default() }),
+        ..payload(MAPPINGS, csv)
+    };
+
+    let table = upload::export_table(payload("SKU;Make;Name;Qty\nA1;Acme;Hammer;5\n"), &store).unwrap();
+    assert_eq!(table.rows, [["A1", "Acme", "Hammer", "5"]]);
+
+    let err = upload::export_table(payload("SKU;Make;Name;Qty\nA1;Acme;Hammer;lots\n"), &store).unwrap_err();
+    assert!(err.contains("is not a number"), "{}", err);
+}
+
+#[test]
+fn the_catalog_exports_every_matching_item() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = VendorStore::new(dir.path().join("vendors"));
+    let mapping = dataset::parse_mappings(MAPPINGS);
+    let csv = "SKU,Make,Name,Qty\nA1,Acme,Hammer,5\nA2,Acme,Mallet,3\nA3,Bosch,Drill,1\n";
+    store
+        .save(&VendorRecord {
+            vendor_name: "Acme".to_string(),
+            file_mappings: MAPPINGS.to_string(),
+            dialect: Dialect::default(),
+            dataset: Dataset::from_csv(csv.as_bytes(), &mapping, None).unwrap(),
+            history: Vec::new(),
+            versions: Vec::new(),
+        })
+        .unwrap();
+    let query = CatalogQuery { brand: Some("Acme".to_string()), page_size: 1, ..CatalogQuery::default() };
+
+    let table = Catalog::load(&store).unwrap().table(&query).select(&columns(&["Vendor", "File Key", "Quantity"])).unwrap();
+
+    assert_eq!(table.rows, [["Acme", "A1", "5"], ["Acme", "A2", "3"]]);
+}
@@ -0,0 +1,94 @@
+//! Target fields fed by several columns, constants and formulas.
+
+use fs_ui_core::dataset::{self, Dataset};
+use fs_ui_core::formula::Source;
+use fs_ui_core::validation;
+
+const CSV: &str = "SKU,Brand,Model,WH1,WH2,EAN,UPC\nA1,Acme,Hammer,3,4,,0123\nA2,Acme,,5,,4006,\n";
+
+fn eval(spec: &str, row: &[(&str, &str)]) -> Result<String, String> {
+    let value = |column: &str| row.iter().find(|(name, _)| *name == column).map(|(_, v)| v.to_string()).unwrap_or_default();
+    Source::parse(spec)?.eval(&value)
+}
+
+#[test]
+fn formulas_combine_columns_and_constants() {
+    let row = [("Brand", "Acme"), ("Model", "Hammer"), ("Qty", "7"), ("Reserved", "2.5"), ("Odd]", "x")];
+    assert_eq!(eval("Brand", &row).unwrap(), "Acme");
+    assert_eq!(eval(r#"=concat(" ", [Brand], [Missing], [Model])"#, &row).unwrap(), "Acme Hammer");
+    assert_eq!(eval("=sum([Qty], [Reserved], [Missing])", &row).unwrap(), "9.5");
+    assert_eq!(eval("=first([Missing], [Model], [Brand])", &row).unwrap(), "Hammer");
+    assert_eq!(eval(r#"="Say ""hi""""#, &row).unwrap(), r#"Say "hi""#);
+    assert_eq!(eval("=([Qty] - [Reserved]) * 2 / -1", &row).unwrap(), "-9");
+    assert_eq!(eval("=0.1 + 0.2", &row).unwrap(), "0.3");
+    assert_eq!(eval("=[Odd]]]", &row).unwrap(), "x");
+
+    assert!(eval("=[Brand] + 1", &row).unwrap_err().contains("\"Acme\" is not a number"));
+    assert!(eval("=[Qty] / [Missing]", &row).unwrap_err().contains("Division by zero"));
+    assert!(eval("=upper([Brand])", &row).unwrap_err().contains("Unknown function"));
+    assert!(eval("=concat([Brand])", &row).unwrap_err().contains("separator"));
+    assert!(eval("=[Qty] +", &row).is_err());
+    assert!(eval("=[Qty", &row).is_err());
+    assert!(eval("=", &row).is_err());
+}
+
+#[test]
+fn mapping_strings_keep_formulas_whole() {
+    let mappings = r#"Description:=concat(", ", [Brand], [Model]), File Key:SKU, Quantity:=sum([WH1], [WH2]), Catalog:="A, B""#;
+    let mapping = dataset::parse_mappings(mappings);
+    assert_eq!(mapping["Description"], r#"=concat(", ", [Brand], [Model])"#);
+    assert_eq!(mapping["File Key"], "SKU");
+    assert_eq!(mapping["Quantity"], "=sum([WH1], [WH2])");
+    assert_eq!(mapping["Catalog"], r#"="A, B""#);
+    assert_eq!(dataset::parse_mappings(&dataset::format_mappings(&mapping)), mapping);
+    // Plain column names are taken as they are, brackets and all.
+    assert_eq!(dataset::parse_mappings("Brand:Make (EU, Intl), Quantity:Qty")["Brand"], "Make (EU");
+
+    let headers: Vec<String> = ["SKU", "Brand", "Model", "WH1", "WH2"].map(String::from).to_vec();
+    assert_eq!(dataset::detect_mapping(&headers, Some(&mapping)), mapping);
+    let mut missing = mapping.clone();
+    missing.insert("Quantity".to_string(), "=[WH1] + [WH3]".to_string());
+    assert_ne!(dataset::detect_mapping(&headers, Some(&missing)), missing);
+}
+
+#[test]
+fn datasets_and_uploads_use_the_computed_values() {
+    let mapping = dataset::parse_mappings(
+        r#"File Key:SKU, Brand:="Acme Tools", Description:=concat(" ", [Brand], [Model]), Quantity:=sum([WH1], [WH2]), Ecommerce:=first([EAN], [UPC])"#,
+    );
+    assert!(dataset::has_formulas(&mapping));
+    assert!(validation::validate(CSV.as_bytes(), &mapping, None).is_valid());
+
+    let dataset = Dataset::from_csv(CSV.as_bytes(), &mapping, None).unwrap();
+    assert_eq!(dataset.rows["A1"]["Description"], "Acme Hammer");
+    assert_eq!(dataset.rows["A1"]["Quantity"], "7");
+    assert_eq!(dataset.rows["A1"]["Ecommerce"], "0123");
+    assert_eq!(dataset.rows["A2"]["Description"], "Acme");
+    assert_eq!(dataset.rows["A2"]["Brand"], "Acme Tools");
+
+    let (bytes, plain) = dataset::apply_mapping(CSV.as_bytes(), &mapping, None).unwrap();
+    assert_eq!(
+        String::from_utf8(bytes.clone()).unwrap(),
+        "File Key,Brand,Description,Ecommerce,Quantity\nA1,Acme Tools,Acme Hammer,0123,7\nA2,Acme Tools,Acme,4006,5\n"
+    );
+    assert!(!dataset::has_formulas(&plain));
+    assert_eq!(Dataset::from_csv(bytes.as_slice(), &plain, Some('.')).unwrap().rows, dataset.rows);
+}
+
+#[test]
+fn validation_reports_bad_formulas_and_missing_columns() {
+    let mapping = dataset::parse_mappings("File Key:SKU, Quantity:=[WH1] + [WH3], Description:=concat([Brand]");
+    let report = validation::validate(CSV.as_bytes(), &mapping, None);
+    assert!(report.errors.iter().any(|e| e.starts_with("Formula for \"Description\"")), "{:?}", report.errors);
+    assert!(report.errors.iter().any(|e| e == "Column \"WH3\" mapped to \"Quantity\" is not in the file"), "{:?}", report.errors);
+
+    let mapping = dataset::parse_mappings("File Key:SKU, Quantity:=[WH1] + [Brand]");
+    let report = validation::validate(CSV.as_bytes(), &mapping, None);
+    assert_eq!(report.errors[0], "Line 2: Quantity: \"Acme\" is not a number");
+
+    let negations = format!("={}1", "-".repeat(100_000));
+    assert_eq!(Source::parse(&negations).unwrap_err(), "The formula is nested too deeply");
+    let parentheses = format!("={}1{}", "(".repeat(100_000), ")".repeat(100_000));
+    assert_eq!(Source::parse(&parentheses).unwrap_err(), "The formula is nested too deeply");
+    assert!(Source::parse(&format!("={}1", "-".repeat(10))).is_ok());
+}
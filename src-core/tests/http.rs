@@ -0,0 +1,223 @@
+//! Drives uploads over the HTTP client against a local stub of the ingest API.
+
+mod common;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Multipart, State};
+use axum::http::{HeaderMap, StatusCode, Uri};
+use axum::routing::post;
+use axum::{Json, Router};
+use fs_ui_core::client::{IngestClient, RetryPolicy};
+use fs_ui_core::http::{HttpAuth, HttpClient, HttpConfig};
+use fs_ui_core::store::VendorStore;
+use fs_ui_core::upload::{self, VendorInfo};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+const MAPPINGS: &str = "File Key:SKU, Brand:Make, Quantity:Qty";
+const CSV: &str = "SKU,Make,Qty\nA1,Acme,5\n";
+
+#[derive(Debug, Clone, Default)]
+struct Seen {
+    path: String,
+    headers: HeaderMap,
+    json: Option<Value>,
+    metadata: Option<Value>,
+    file_name: Option<String>,
+    file: Option<Vec<u8>>,
+}
+
+impl Seen {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+#[derive(Default)]
+struct Stub {
+    seen: Vec<Seen>,
+    /// Answers for the next requests, in order; afterwards requests succeed.
+    script: VecDeque<(StatusCode, Value)>,
+}
+
+type Shared = Arc<Mutex<Stub>>;
+
+fn answer(stub: &Shared, seen: Seen, ok: Value) -> (StatusCode, Json<Value>) {
+    let mut stub = stub.lock().unwrap();
+    stub.seen.push(seen);
+    let (status, body) = stub.script.pop_front().unwrap_or((StatusCode::OK, ok));
+    (status, Json(body))
+}
+
+async fn setup(State(stub): State<Shared>, uri: Uri, headers: HeaderMap, Json(body): Json<Value>) -> (StatusCode, Json<Value>) {
+    let seen = Seen { path: uri.path().to_string(), headers, json: Some(body), ..Seen::default() };
+    answer(&stub, seen, json!({ "message": "Vendor created" }))
+}
+
+async fn upload(State(stub): State<Shared>, uri: Uri, headers: HeaderMap, mut form: Multipart) -> (StatusCode, Json<Value>) {
+    let mut seen = Seen { path: uri.path().to_string(), headers, ..Seen::default() };
+    while let Some(field) = form.next_field().await.unwrap() {
+        match field.name() {
+            Some("metadata") => seen.metadata = Some(serde_json::from_str(&field.text().await.unwrap()).unwrap()),
+            Some("file") => {
+                seen.file_name = field.file_name().map(str::to_string);
+                seen.file = Some(field.bytes().await.unwrap().to_vec());
+            }
+            _ => {}
+        }
+    }
+    let message = format!("Stored {} bytes", seen.file.as_ref().map_or(0, Vec::len));
+    answer(&stub, seen, json!({ "message": message }))
+}
+
+struct Fixture {
+    stub: Shared,
+    base_url: String,
+    store: VendorStore,
+    dir: TempDir,
+}
+
+impl Fixture {
+    async fn start() -> Self {
+        let stub = Shared::default();
+        let app = Router::new()
+            .route("/api/vendors", post(setup))
+            .route("/api/vendors/{vendor}/uploads", post(upload))
+            .with_state(stub.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/api/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let dir = tempfile::tempdir().unwrap();
+        Fixture { stub, base_url, store: VendorStore::new(dir.path().join("vendors")), dir }
+    }
+
+    fn script(&self, answers: impl IntoIterator<Item = (StatusCode, Value)>) {
+        self.stub.lock().unwrap().script.extend(answers);
+    }
+
+    fn seen(&self) -> Vec<Seen> {
+        self.stub.lock().unwrap().seen.clone()
+    }
+
+    fn client(&self, auth: Option<HttpAuth>, headers: BTreeMap<String, String>) -> IngestClient {
+        let config = HttpConfig {
+            base_url: self.base_url.clone(),
+            setup_path: "vendors".to_string(),
+            upload_path: "vendors/{vendor}/uploads".to_string(),
+            session_path: "session".to_string(),
+            auth,
+            headers,
+            ca_cert: None,
+            client_cert: None,
+            client_key: None,
+        };
+        let policy = RetryPolicy {
+            initial_backoff_ms: 10,
+            ..RetryPolicy::default()
+        };
+        HttpClient::new(config, policy).unwrap().into()
+    }
+
+    fn bearer(&self) -> IngestClient {
+        self.client(Some(HttpAuth::Bearer { token: "t0ken".to_string() }), BTreeMap::new())
+    }
+}
+
+fn payload(vendor_name: &str, password: &str) -> VendorInfo {
+    VendorInfo {
+        vendor_name: vendor_name.to_string(),
+        password: password.to_string(),
+        ..common::payload(MAPPINGS, CSV)
+    }
+}
+
+#[tokio::test]
+async fn new_vendor_is_set_up_then_uploaded() {
+    let f = Fixture::start().await;
+
+    let message = upload::submit(payload("Acme & Co", "secret"), &f.store, &f.bearer(), "").await.unwrap();
+
+    assert_eq!(message, "Stored 23 bytes");
+    let seen = f.seen();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0].path, "/api/vendors");
+    assert_eq!(seen[0].json.as_ref().unwrap()["password"], "secret");
+    assert_eq!(seen[0].json.as_ref().unwrap()["file_mappings"], MAPPINGS);
+    assert_eq!(seen[1].path, "/api/vendors/Acme%20%26%20Co/uploads");
+    assert_eq!(seen[1].metadata.as_ref().unwrap()["vendor_name"], "Acme & Co");
+    assert!(seen[1].metadata.as_ref().unwrap().get("password").is_none());
+    assert_eq!(seen[1].file.as_deref(), Some(CSV.as_bytes()));
+    for request in &seen {
+        assert_eq!(request.header("authorization"), Some("Bearer t0ken"));
+    }
+    let setup_key = seen[0].header("idempotency-key").unwrap();
+    assert!(setup_key.ends_with("-setup"), "{}", setup_key);
+    assert_eq!(seen[1].header("idempotency-key").unwrap(), setup_key.replace("-setup", "-upload"));
+    assert!(f.store.load("Acme & Co").unwrap().is_some());
+}
+
+#[tokio::test]
+async fn existing_vendor_upload_streams_the_file_only() {
+    let f = Fixture::start().await;
+    let path = f.dir.path().join("stock.csv");
+    fs::write(&path, CSV).unwrap();
+    let mut payload = payload("Acme", "");
+    payload.file_path = Some(path.display().to_string());
+    let client = f.client(
+        Some(HttpAuth::Header { name: "X-Api-Key".to_string(), value: "k3y".to_string() }),
+        BTreeMap::from([("X-Tenant".to_string(), "north".to_string())]),
+    );
+
+    upload::submit(payload, &f.store, &client, "").await.unwrap();
+
+    let seen = f.seen();
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen[0].file_name.as_deref(), Some("stock.csv"));
+    assert_eq!(seen[0].file.as_deref(), Some(CSV.as_bytes()));
+    assert_eq!(seen[0].header("x-api-key"), Some("k3y"));
+    assert_eq!(seen[0].header("x-tenant"), Some("north"));
+}
+
+#[tokio::test]
+async fn validation_errors_are_reported_without_retrying() {
+    let f = Fixture::start().await;
+    f.script([(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        json!({ "message": "Validation failed", "errors": ["Line 2: unknown brand"] }),
+    )]);
+
+    let err = upload::submit(payload("Acme", ""), &f.store, &f.bearer(), "").await.unwrap_err();
+
+    assert_eq!(err, "Validation failed\nLine 2: unknown brand");
+    assert_eq!(f.seen().len(), 1);
+    assert!(f.store.load("Acme").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn server_errors_are_retried_with_the_same_key() {
+    let f = Fixture::start().await;
+    f.script([(StatusCode::SERVICE_UNAVAILABLE, json!({}))]);
+
+    let message = upload::submit(payload("Acme", ""), &f.store, &f.bearer(), "").await.unwrap();
+
+    assert_eq!(message, "Stored 23 bytes");
+    let seen = f.seen();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0].header("idempotency-key"), seen[1].header("idempotency-key"));
+    assert_eq!(seen[0].file, seen[1].file);
+}
+
+#[tokio::test]
+async fn refused_credentials_stop_the_setup() {
+    let f = Fixture::start().await;
+    f.script([(StatusCode::UNAUTHORIZED, json!({}))]);
+
+    let err = upload::submit(payload("Acme", "wrong"), &f.store, &f.bearer(), "").await.unwrap_err();
+
+    assert!(err.contains("refused the credentials (401 Unauthorized)"), "{}", err);
+    assert_eq!(f.seen().len(), 1);
+}
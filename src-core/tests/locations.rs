@@ -0,0 +1,70 @@
+//! Stock spread over warehouses, as a column per warehouse or a row per location.
+
+use fs_ui_core::dataset::{self, Dataset};
+use fs_ui_core::export::Table;
+use fs_ui_core::validation;
+
+#[test]
+fn warehouse_columns_are_kept_apart_and_totalled() {
+    let csv = "SKU,Brand,North,South\nA1,Acme,3,4\nA2,Acme,,2.5\n";
+    let mapping = dataset::parse_mappings("File Key:SKU, Brand:Brand, Quantity@North:North, Quantity@South:South");
+
+    let report = validation::validate(csv.as_bytes(), &mapping, None);
+    assert!(report.is_valid(), "{:?}", report.errors);
+    assert_eq!(report.locations, ["North", "South"]);
+    assert!(!report.warnings.iter().any(|w| w.contains("\"Quantity\"")));
+
+    let dataset = Dataset::from_csv(csv.as_bytes(), &mapping, None).unwrap();
+    assert_eq!(dataset.rows["A1"]["Quantity@North"], "3");
+    assert_eq!(dataset.rows["A1"]["Quantity"], "7");
+    assert_eq!(dataset.rows["A2"]["Quantity"], "2.5");
+
+    let table = Table::from_dataset(&dataset, &mapping);
+    assert_eq!(table.columns, ["File Key", "Brand", "Quantity", "Quantity@North", "Quantity@South"]);
+    assert_eq!(table.rows[1], ["A2", "Acme", "2.5", "", "2.5"]);
+
+    let bad = validation::validate("SKU,North\nA1,lots\n".as_bytes(), &dataset::parse_mappings("File Key:SKU, Quantity@North:North"), None);
+    assert_eq!(bad.errors, ["Line 2: Quantity@North \"lots\" is not a number"]);
+}
+
+#[test]
+fn rows_per_location_are_pivoted_into_one_item() {
+    let csv = "SKU,Brand,Site,Qty\nA1,Acme,North,3\nA1,Acme,South,4\nA2,Acme,North,1\n";
+    let mapping = dataset::parse_mappings("File Key:SKU, Brand:Brand, Location:Site, Quantity:Qty");
+
+    let report = validation::validate(csv.as_bytes(), &mapping, None);
+    assert!(report.is_valid(), "{:?}", report.errors);
+    assert_eq!(report.locations, ["North", "South"]);
+
+    let dataset = Dataset::from_csv(csv.as_bytes(), &mapping, None).unwrap();
+    assert_eq!(dataset.rows.len(), 2);
+    assert_eq!(dataset.rows["A1"]["Quantity@South"], "4");
+    assert_eq!(dataset.rows["A1"]["Quantity"], "7");
+    assert!(!dataset.rows["A1"].contains_key("Location"));
+    assert!(!dataset.rows["A2"].contains_key("Quantity@South"));
+
+    let (bytes, plain) = dataset::apply_mapping(csv.as_bytes(), &mapping, None).unwrap();
+    assert_eq!(
+        String::from_utf8(bytes.clone()).unwrap(),
+        "File Key,Brand,Quantity,Quantity@North,Quantity@South\nA1,Acme,7,3,4\nA2,Acme,1,1,\n"
+    );
+    assert!(validation::validate(bytes.as_slice(), &plain, Some('.')).is_valid());
+    assert_eq!(Dataset::from_csv(bytes.as_slice(), &plain, Some('.')).unwrap().rows, dataset.rows);
+}
+
+#[test]
+fn location_mistakes_are_reported() {
+    let csv = "SKU,Site,Qty,North\nA1,North,3,1\nA1,North,4,1\nA2,,1,1\n";
+    let report = validation::validate(csv.as_bytes(), &dataset::parse_mappings("File Key:SKU, Location:Site, Quantity:Qty"), None);
+    assert_eq!(
+        report.errors,
+        ["Line 3: duplicate File Key \"A1\" at \"North\"", "Line 4: File Key \"A2\" has no Location"]
+    );
+
+    let both = validation::validate(csv.as_bytes(), &dataset::parse_mappings("File Key:SKU, Location:Site, Quantity@North:North"), None);
+    assert!(both.errors[0].contains("not both"), "{:?}", both.errors);
+    let no_quantity = validation::validate(csv.as_bytes(), &dataset::parse_mappings("File Key:SKU, Location:Site"), None);
+    assert_eq!(no_quantity.errors, ["\"Location\" needs \"Quantity\" mapped"]);
+    let unnamed = validation::validate(csv.as_bytes(), &dataset::parse_mappings("File Key:SKU, Quantity@:North"), None);
+    assert_eq!(unnamed.errors, ["A warehouse quantity has no warehouse name"]);
+}
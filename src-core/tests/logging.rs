@@ -0,0 +1,120 @@
+//! Log files, their redaction and rotation, and the diagnostics bundle.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use fs_ui_core::diagnostics;
+use fs_ui_core::logging::{self, LogConfig, LOG_DIR};
+use fs_ui_core::upload::VendorInfo;
+use serde_json::{json, Value};
+
+use common::{payload, unzip};
+
+fn log_lines(dir: &Path, name: &str) -> Vec<Value> {
+    fs::read_to_string(dir.join(name))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn events_are_written_without_secrets() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = LogConfig { rotation: "never".to_string(), ..LogConfig::default() };
+    let logger = logging::subscriber(dir.path(), &config).unwrap();
+    let payload = VendorInfo {
+        password: "hunter2".to_string(),
+        ..payload("File Key:SKU", "SKU\nA1\n")
+    };
+
+    tracing::subscriber::with_default(logger, || {
+        tracing::debug!("below the level");
+        tracing::info!(vendor = "Acme", ?payload, "sending upload");
+        tracing::warn!(password = "hunter2", rows = 3, value = "Price", "setup");
+    });
+
+    let lines = log_lines(dir.path(), "fs_ui.log");
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["level"], "INFO");
+    assert_eq!(lines[0]["message"], "sending upload");
+    assert_eq!(lines[0]["vendor"], "Acme");
+    assert!(lines[0]["payload"].as_str().unwrap().contains("[7 bytes]"));
+    assert_eq!(lines[1]["password"], "[redacted]");
+    assert_eq!(lines[1]["rows"], 3);
+    assert_eq!(lines[1]["value"], "Price");
+    assert!(!fs::read_to_string(dir.path().join("fs_ui.log")).unwrap().contains("hunter2"));
+}
+
+#[test]
+fn old_files_are_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    for day in ["2024-05-01", "2024-05-02", "2024-05-03", "2024-05-04"] {
+        fs::write(dir.path().join(format!("fs_ui.{}.log", day)), "{}\n").unwrap();
+    }
+    fs::write(dir.path().join("notes.txt"), "kept").unwrap();
+    let config = LogConfig { max_files: 3, ..LogConfig::default() };
+
+    let logger = logging::subscriber(dir.path(), &config).unwrap();
+    tracing::subscriber::with_default(logger, || tracing::info!("started"));
+
+    let names: Vec<String> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    let logs: Vec<&String> = names.iter().filter(|name| name.starts_with("fs_ui.")).collect();
+    assert_eq!(logs.len(), 3);
+    assert!(logs.iter().any(|name| !name.starts_with("fs_ui.2024-")));
+    assert!(names.contains(&"notes.txt".to_string()));
+}
+
+#[test]
+fn unknown_level_is_an_error() {
+    let config = LogConfig { level: "loud".to_string(), ..LogConfig::default() };
+    assert!(logging::subscriber(Path::new("logs"), &config).is_err());
+    let config = LogConfig { rotation: "weekly".to_string(), ..LogConfig::default() };
+    assert!(logging::subscriber(Path::new("logs"), &config).is_err());
+}
+
+#[test]
+fn config_secrets_are_redacted() {
+    let mut config = json!({
+        "http": {
+            "base_url": "https://ingest.example.com/",
+            "auth": { "kind": "header", "name": "X-Api-Key", "value": "k3y" },
+            "headers": { "X-Tenant": "north" },
+        },
+        "users": [{ "username": "alice", "password": "s3cret" }],
+    });
+
+    logging::redact(&mut config);
+
+    assert_eq!(config["http"]["base_url"], "https://ingest.example.com/");
+    assert_eq!(config["http"]["auth"]["name"], "X-Api-Key");
+    assert_eq!(config["http"]["auth"]["value"], "[redacted]");
+    assert_eq!(config["http"]["headers"]["X-Tenant"], "[redacted]");
+    assert_eq!(config["users"][0]["password"], "[redacted]");
+}
+
+#[test]
+fn diagnostics_bundle_logs_config_and_version() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("client.json"),
+        json!({ "http": { "base_url": "https://ingest.example.com/", "auth": { "kind": "bearer", "token": "t0ken" } } }).to_string(),
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join(LOG_DIR)).unwrap();
+    fs::write(dir.path().join(LOG_DIR).join("fs_ui.log"), "{\"message\":\"started\"}\n").unwrap();
+
+    let zip = diagnostics::collect(dir.path(), &json!({ "version": "0.1.0" })).unwrap();
+
+    let files = unzip(&zip);
+    assert_eq!(files.keys().collect::<Vec<_>>(), ["config/client.json", "logs/fs_ui.log", "version.json"]);
+    assert!(files["version.json"].contains("0.1.0"));
+    assert!(files["config/client.json"].contains("[redacted]"));
+    assert!(!files["config/client.json"].contains("t0ken"));
+    assert_eq!(files["logs/fs_ui.log"], "{\"message\":\"started\"}\n");
+}
@@ -0,0 +1,125 @@
+//! Prices in the vendors' own formats and currencies, and converting them.
+
+use fs_ui_core::dataset::{self, Dataset};
+use fs_ui_core::export::Table;
+use fs_ui_core::pricing::{self, Price, RateTable};
+use fs_ui_core::store::VendorStore;
+use fs_ui_core::validation;
+
+fn price(amount: f64, currency: Option<&str>) -> Price {
+    Price { amount, currency: currency.map(String::from) }
+}
+
+#[test]
+fn prices_are_read_in_any_locale() {
+    assert_eq!(pricing::parse_price("$1,234.50", None).unwrap(), price(1234.5, Some("USD")));
+    assert_eq!(pricing::parse_price("1.234,50 €", None).unwrap(), price(1234.5, Some("EUR")));
+    assert_eq!(pricing::parse_price("EUR 1 234,50", None).unwrap(), price(1234.5, Some("EUR")));
+    assert_eq!(pricing::parse_price("1'234.50", None).unwrap(), price(1234.5, None));
+    assert_eq!(pricing::parse_price("12,5", None).unwrap(), price(12.5, None));
+    assert_eq!(pricing::parse_price("1,234", None).unwrap(), price(1234.0, None));
+    assert_eq!(pricing::parse_price("0,125", None).unwrap(), price(0.125, None));
+    assert_eq!(pricing::parse_price("1.234.567", None).unwrap(), price(1234567.0, None));
+    assert_eq!(pricing::parse_price("-3.5 gbp", None).unwrap(), price(-3.5, Some("GBP")));
+    assert_eq!(pricing::parse_price("Fr. 12.50", None).unwrap(), price(12.5, Some("CHF")));
+    assert_eq!(pricing::parse_price("1'234.50 Fr", None).unwrap(), price(1234.5, Some("CHF")));
+
+    assert!(pricing::parse_price("call us", None).is_err());
+    assert!(pricing::parse_price("12 apples", None).is_err());
+    assert!(pricing::parse_price("", None).is_err());
+    assert_eq!(pricing::currency_code("C$").unwrap(), "CAD");
+}
+
+#[test]
+fn the_vendors_decimal_separator_decides_what_the_guess_cannot() {
+    assert_eq!(pricing::parse_price("1.500", Some('.')).unwrap(), price(1.5, None));
+    assert_eq!(pricing::parse_price("12.345", Some('.')).unwrap(), price(12.345, None));
+    assert_eq!(pricing::parse_price("1.500", Some(',')).unwrap(), price(1500.0, None));
+    assert_eq!(pricing::parse_price("1 234,5 €", Some(',')).unwrap(), price(1234.5, Some("EUR")));
+    assert!(pricing::parse_price("1,234.50", Some(',')).is_err());
+    assert!(pricing::parse_price("1.2.3", Some('.')).is_err());
+
+    assert_eq!(pricing::detect_decimal(["1.500", "12.99", "-4.5"]), Some('.'));
+    assert_eq!(pricing::detect_decimal(["1.500", "9,99", "1.234,50"]), Some(','));
+    assert_eq!(pricing::detect_decimal(["1.500", "2.000", "7"]), None);
+
+    let mapping = dataset::parse_mappings("File Key:SKU, Cost:Cost");
+    let dataset = Dataset::from_csv("SKU,Cost\nA1,1.500\nA2,12.345\n".as_bytes(), &mapping, Some('.')).unwrap();
+    assert_eq!(dataset.rows["A1"]["Cost"], "1.5");
+    assert_eq!(dataset.rows["A2"]["Cost"], "12.345");
+}
+
+#[test]
+fn rows_take_their_currency_from_the_mapping_or_the_prices() {
+    let csv = "SKU,Cost,MSRP,Cur\nA1,\"9,99\",\"19,99 €\",\nA2,$5,$8,usd\n";
+    let mapping = dataset::parse_mappings("File Key:SKU, Cost:Cost, MSRP:MSRP, Currency:Cur");
+    let report = validation::validate(csv.as_bytes(), &mapping, Some(','));
+    assert!(report.is_valid(), "{:?}", report.errors);
+    assert_eq!(report.currencies, ["EUR", "USD"]);
+    assert!(!report.warnings.iter().any(|w| w.contains("\"MAP\"")));
+
+    let dataset = Dataset::from_csv(csv.as_bytes(), &mapping, Some(',')).unwrap();
+    assert_eq!(dataset.rows["A1"]["Cost"], "9.99");
+    assert_eq!(dataset.rows["A1"]["Currency"], "EUR");
+    assert_eq!(dataset.rows["A2"]["MSRP"], "8");
+    assert_eq!(dataset.rows["A2"]["Currency"], "USD");
+
+    let table = Table::from_dataset(&dataset, &mapping);
+    assert_eq!(table.columns, ["File Key", "Cost", "MSRP", "Currency"]);
+
+    // A fixed currency for the whole file, and formulas always using '.'.
+    let fixed = dataset::parse_mappings(r#"File Key:SKU, Cost:=[Cost] * 1.5, Currency:="CHF""#);
+    let dataset = Dataset::from_csv("SKU,Cost\nA1,9.057\n".as_bytes(), &fixed, None).unwrap();
+    assert_eq!(dataset.rows["A1"]["Cost"], "13.5855");
+    assert_eq!(dataset.rows["A1"]["Currency"], "CHF");
+}
+
+#[test]
+fn negative_mismatched_and_zero_prices_are_reported() {
+    let csv = "SKU,Cost,MAP,Cur\nA1,-5,1,\nA2,€3,,USD\nA3,free,,\nA4,0,0,\nA5,0,2,\n";
+    let report = validation::validate(csv.as_bytes(), &dataset::parse_mappings("File Key:SKU, Cost:Cost, MAP:MAP, Currency:Cur"), None);
+    assert_eq!(
+        report.errors,
+        [
+            "Line 2: Cost -5 is below zero",
+            "Line 3: Cost \"€3\" is in EUR, not USD",
+            "Line 4: Cost \"free\" is not a price",
+        ]
+    );
+    assert!(report.warnings.contains(&"2 rows have a Cost of zero".to_string()), "{:?}", report.warnings);
+    assert!(report.warnings.contains(&"1 rows have a MAP of zero".to_string()), "{:?}", report.warnings);
+}
+
+#[test]
+fn the_rate_table_is_kept_with_the_vendors_and_converts_prices() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = VendorStore::new(dir.path().join("vendors"));
+    assert_eq!(store.rates().unwrap(), RateTable::default());
+
+    let mut rates = RateTable { convert_to: "eur".to_string(), ..RateTable::default() };
+    assert!(store.save_rates(&mut rates).unwrap_err().contains("no rate for EUR"));
+    rates.rates.insert("eur".to_string(), 1.25);
+    rates.rates.insert("GBP".to_string(), 1.5);
+    store.save_rates(&mut rates).unwrap();
+    let saved = store.rates().unwrap();
+    assert_eq!(saved.convert_to, "EUR");
+    assert_eq!(saved.rates["EUR"], 1.25);
+    assert!(saved.updated > 0);
+    rates.rates.insert("JPY".to_string(), 0.0);
+    assert!(store.save_rates(&mut rates).unwrap_err().contains("above zero"));
+
+    let mapping = dataset::parse_mappings("File Key:SKU, Cost:Cost, Currency:Cur");
+    let csv = "SKU,Cost,Cur\nA1,10,USD\nA2,10,GBP\nA3,10,EUR\nA4,,\n";
+    let mut dataset = Dataset::from_csv(csv.as_bytes(), &mapping, None).unwrap();
+    saved.convert(&mut dataset).unwrap();
+    assert_eq!(dataset.rows["A1"]["Cost"], "8");
+    assert_eq!(dataset.rows["A2"]["Cost"], "12");
+    assert_eq!(dataset.rows["A3"]["Cost"], "10");
+    assert!(dataset.rows.values().filter(|row| row.get("Cost").is_some_and(|cost| !cost.is_empty())).all(|row| row["Currency"] == "EUR"));
+    assert!(!saved.converts("CAD"));
+
+    let mut unknown = Dataset::from_csv("SKU,Cost,Cur\nA1,10,CAD\n".as_bytes(), &mapping, None).unwrap();
+    assert!(saved.convert(&mut unknown).unwrap_err().contains("no rate for CAD"));
+    let mut unpriced = Dataset::from_csv("SKU,Cost\nA1,10\n".as_bytes(), &dataset::parse_mappings("File Key:SKU, Cost:Cost"), None).unwrap();
+    assert!(saved.convert(&mut unpriced).unwrap_err().contains("no currency"));
+}
@@ -0,0 +1,189 @@
+//! Exporting mapping profiles from one installation and importing them on another.
+
+use std::collections::BTreeMap;
+
+use fs_ui_core::audit::{AuditAction, AuditFilter, AuditLog};
+use fs_ui_core::dataset::{self, Dataset};
+use fs_ui_core::dialect::Dialect;
+use fs_ui_core::profile::{self, ImportStatus, ProfileDocument, ProfileFormat, Resolution, PROFILE_VERSION};
+use fs_ui_core::store::{VendorRecord, VendorStore};
+use tempfile::TempDir;
+
+fn store_with(vendors: &[(&str, &str)]) -> (TempDir, VendorStore) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = VendorStore::new(dir.path().join("vendors"));
+    for (vendor_name, mappings) in vendors {
+        store
+            .save(&VendorRecord {
+                vendor_name: vendor_name.to_string(),
+                file_mappings: mappings.to_string(),
+                dialect: Dialect::default(),
+                dataset: Dataset::default(),
+                history: Vec::new(),
+                versions: Vec::new(),
+            })
+            .unwrap();
+    }
+    (dir, store)
+}
+
+fn resolve(pairs: &[(&str, Resolution)]) -> BTreeMap<String, Resolution> {
+    pairs.iter().map(|(vendor, resolution)| (vendor.to_string(), *resolution)).collect()
+}
+
+#[test]
+fn documents_round_trip_through_json_and_toml() {
+    let (_dir, store) = store_with(&[("Acme", "File Key:SKU, Quantity:Qty")]);
+    let mut record = store.load("Acme").unwrap().unwrap();
+    record.dialect = Dialect { delimiter: ';', quote: '\'', encoding: "windows-1252".to_string(), decimal: Some(',') };
+    store.save(&record).unwrap();
+
+    let document = ProfileDocument::export(&store, &[]).unwrap();
+    assert_eq!(document.version, PROFILE_VERSION);
+    assert_eq!(document.schema[0], "File Key");
+    assert_eq!(document.profiles[0].mapping["Quantity"], "Qty");
+
+    for format in [ProfileFormat::Json, ProfileFormat::Toml] {
+        let bytes = document.write(format).unwrap();
+        assert_eq!(ProfileDocument::parse(&bytes, format).unwrap(), document);
+    }
+    assert!(ProfileDocument::export(&store, &["Initech".to_string()]).is_err());
+}
+
+#[test]
+fn documents_from_a_later_version_are_refused() {
+    let toml = format!("version = {}\nschema = []\n", PROFILE_VERSION + 1);
+    let err = ProfileDocument::parse(toml.as_bytes(), ProfileFormat::Toml).unwrap_err();
+    assert!(err.contains("newer"), "{}", err);
+    assert!(ProfileDocument::parse(br#"{"profiles": []}"#, ProfileFormat::Json).is_err());
+}
+
+#[test]
+fn preview_flags_conflicts_and_unknown_fields() {
+    let (_dir, store) = store_with(&[("Acme", "File Key:SKU, Quantity:Qty"), ("Globex", "File Key:Id")]);
+    let document = ProfileDocument::parse(
+        br#"
+            version = 1
+            schema = ["File Key", "Quantity", "Price"]
+
+            [[profiles]]
+            vendor_name = "Acme"
+            mapping = { "File Key" = "SKU", "Quantity" = "Stock", "Price" = "Cost" }
+
+            [[profiles]]
+            vendor_name = "Globex"
+            mapping = { "File Key" = "Id" }
+
+            [[profiles]]
+            vendor_name = "Initech"
+            mapping = { "File Key" = "Part" }
+
+            [[profiles]]
+            vendor_name = "Umbrella"
+            mapping = { "Quantity" = "Qty" }
+        "#,
+        ProfileFormat::Toml,
+    )
+    .unwrap();
+
+    let preview = profile::preview(&document, &store).unwrap();
+
+    assert!(preview.warnings.iter().any(|w| w.contains("\"Price\"")));
+    assert!(preview.warnings.iter().any(|w| w.contains("no \"Brand\"")));
+    let statuses: Vec<ImportStatus> = preview.profiles.iter().map(|p| p.status).collect();
+    assert_eq!(statuses, [ImportStatus::Conflict, ImportStatus::Unchanged, ImportStatus::New, ImportStatus::Invalid]);
+    let acme = &preview.profiles[0];
+    assert!(!acme.incoming.mapping.contains_key("Price"));
+    assert!(acme.warnings[0].contains("Unknown target field \"Price\""));
+    assert_eq!(acme.current.as_ref().unwrap().mapping["Quantity"], "Qty");
+    assert!(preview.profiles[3].errors[0].contains("File Key"));
+}
+
+#[test]
+fn import_applies_each_resolution_and_audits_the_changes() {
+    let (dir, store) = store_with(&[("Acme", "File Key:SKU, Brand:Make, Quantity:Qty"), ("Globex", "File Key:Id, Brand:Make")]);
+    let document = ProfileDocument::parse(
+        br#"{
+            "version": 1,
+            "schema": ["File Key", "Catalog", "Brand", "Description", "Quantity", "Ecommerce"],
+            "profiles": [
+                { "vendor_name": "Acme", "mapping": { "File Key": "Part", "Quantity": "Stock" } },
+                { "vendor_name": "Globex", "mapping": { "File Key": "Part", "Quantity": "Stock" }, "dialect": { "delimiter": ";" } },
+                { "vendor_name": "Initech", "mapping": { "File Key": "Part" } },
+                { "vendor_name": "Hooli", "mapping": { "File Key": "Part" } }
+            ]
+        }"#,
+        ProfileFormat::Json,
+    )
+    .unwrap();
+    let audit = AuditLog::new(dir.path().join("audit.jsonl"));
+    let resolutions = resolve(&[("Acme", Resolution::Replace), ("Globex", Resolution::Merge), ("Initech", Resolution::Replace)]);
+    // Only importing Initech, new here, takes the right to create vendors.
+    assert!(profile::creates_vendors(&document, &resolutions, &store).unwrap());
+    assert!(!profile::creates_vendors(&document, &resolve(&[("Acme", Resolution::Replace)]), &store).unwrap());
+
+    let summary = profile::import_audited(
+        &document,
+        &resolutions,
+        &store,
+        &audit,
+        "alice",
+    )
+    .unwrap();
+
+    assert_eq!(summary.imported.len(), 3);
+    assert_eq!(summary.skipped, ["Hooli"]);
+    assert_eq!(store.load("Acme").unwrap().unwrap().file_mappings, "File Key:Part, Quantity:Stock");
+    let globex = store.load("Globex").unwrap().unwrap();
+    assert_eq!(dataset::parse_mappings(&globex.file_mappings)["Brand"], "Make");
+    assert_eq!(dataset::parse_mappings(&globex.file_mappings)["File Key"], "Part");
+    assert_eq!(globex.dialect.delimiter, ';');
+    let initech = store.load("Initech").unwrap().unwrap();
+    assert!(initech.dataset.rows.is_empty());
+    assert!(store.load("Hooli").unwrap().is_none());
+
+    let entries = audit.read(&AuditFilter::default()).unwrap();
+    assert_eq!(entries.len(), 3);
+    let actions: Vec<AuditAction> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(actions, [AuditAction::ChangeMapping, AuditAction::ChangeMapping, AuditAction::CreateVendor]);
+    assert!(entries.iter().all(|e| e.actor == "alice"));
+    assert_eq!(entries[0].before.as_ref().unwrap()["file_mappings"], "Brand:Make, File Key:SKU, Quantity:Qty");
+    assert!(entries[2].before.is_none());
+}
+
+#[test]
+fn profile_changes_are_versioned_and_can_be_rolled_back() {
+    let (dir, store) = store_with(&[("Acme", "File Key:SKU, Quantity:Qty")]);
+    let mut record = store.load("Acme").unwrap().unwrap();
+    // The profile saved before versions were kept becomes version 1, as old as its file.
+    let modified = std::fs::metadata(dir.path().join("vendors/Acme.json")).unwrap().modified().unwrap();
+    let modified = modified.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+    assert_eq!((record.version(), record.versions[0].timestamp), (1, modified));
+    assert_eq!(record.set_profile("File Key:SKU, Quantity:Qty", &Dialect::default(), "alice", ""), 1);
+    assert_eq!(record.set_profile("File Key:Part, Quantity:Qty, Brand:Make", &Dialect::default(), "alice", "New price list"), 2);
+    let semicolons = Dialect { delimiter: ';', ..Dialect::default() };
+    assert_eq!(record.set_profile("Brand:Make, File Key:Part, Quantity:Qty", &semicolons, "bob", ""), 3);
+    store.save(&record).unwrap();
+
+    let versions = profile::versions(&store, "Acme").unwrap();
+    assert_eq!(versions.iter().map(|v| v.version.version).collect::<Vec<_>>(), [3, 2, 1]);
+    assert_eq!(versions[1].version.author, "alice");
+    assert_eq!(versions[1].version.note, "New price list");
+
+    let changes = profile::diff(&store, "Acme", 1, 3).unwrap();
+    let fields: Vec<(&str, &str, &str)> = changes.iter().map(|c| (c.field.as_str(), c.old.as_str(), c.new.as_str())).collect();
+    assert_eq!(fields, [("File Key", "SKU", "Part"), ("Brand", "", "Make"), ("Delimiter", ",", ";")]);
+
+    let audit = AuditLog::new(dir.path().join("audit.jsonl"));
+    assert_eq!(profile::rollback_audited(&store, &audit, "carol", "Acme", 1, "").unwrap(), 4);
+    let record = store.load("Acme").unwrap().unwrap();
+    assert_eq!(record.file_mappings, "File Key:SKU, Quantity:Qty");
+    assert_eq!(record.dialect, Dialect::default());
+    assert_eq!(record.versions.last().unwrap().note, "Rolled back to version 1");
+    assert!(profile::rollback(&store, "Acme", 4, "carol", "").unwrap_err().contains("in use"));
+    assert!(profile::rollback(&store, "Acme", 9, "carol", "").is_err());
+
+    let entries = audit.read(&AuditFilter::default()).unwrap();
+    assert_eq!(entries[0].before.as_ref().unwrap()["profile_version"], 3);
+    assert_eq!(entries[0].after.as_ref().unwrap()["rolled_back_to"], 1);
+}
@@ -0,0 +1,265 @@
+//! Sends one request over each transport to a local listener.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use fs_ui_core::client::{ClientConfig, RetryPolicy, SocketClient};
+use fs_ui_core::protocol::Reply;
+use fs_ui_core::source::FileSource;
+use fs_ui_core::transport::Transport;
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+use serde_json::{json, Map, Value};
+use tempfile::TempDir;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// Reads one request and answers with the vendor name and file it carried.
+async fn answer(mut stream: impl AsyncRead + AsyncWrite + Unpin) {
+    let mut request = Vec::new();
+    stream.read_to_end(&mut request).await.unwrap();
+    let request: Value = serde_json::from_slice(&request).unwrap();
+    let file_bytes: Vec<u8> = serde_json::from_value(request["file_bytes"].clone()).unwrap();
+    let reply = Reply::ok(format!("{}: {}", request["vendor_name"].as_str().unwrap(), String::from_utf8(file_bytes).unwrap()));
+    stream.write_all(&serde_json::to_vec(&reply).unwrap()).await.unwrap();
+    stream.shutdown().await.unwrap();
+}
+
+async fn send(client: &SocketClient) -> Result<String, String> {
+    let mut fields = Map::new();
+    fields.insert("vendor_name".to_string(), json!("Acme"));
+    client.send(fields, &FileSource::Bytes(b"SKU\nA1\n".to_vec())).await
+}
+
+fn no_retries() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 1,
+        ..RetryPolicy::default()
+    }
+}
+
+struct Pki {
+    dir: TempDir,
+    ca: CertifiedIssuer<'static, KeyPair>,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap();
+        let pki = Pki { dir: tempfile::tempdir().unwrap(), ca };
+        fs::write(pki.path("ca.pem"), pki.ca.pem()).unwrap();
+        pki
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    /// Issues a certificate for `localhost`, written as `<name>.pem` and `<name>.key`.
+    fn issue(&self, name: &str) -> (PathBuf, PathBuf) {
+        let key = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&key, &self.ca)
+            .unwrap();
+        let (cert_path, key_path) = (self.path(&format!("{}.pem", name)), self.path(&format!("{}.key", name)));
+        fs::write(&cert_path, cert.pem()).unwrap();
+        fs::write(&key_path, key.serialize_pem()).unwrap();
+        (cert_path, key_path)
+    }
+
+    /// A TLS listener presenting a `localhost` certificate, requiring client
+    /// certificates from this CA when `client_auth` is set.
+    fn acceptor(&self, client_auth: bool) -> TlsAcceptor {
+        let (cert, key) = self.issue("server");
+        let provider = Arc::new(ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap();
+        let builder = if client_auth {
+            let mut roots = RootCertStore::empty();
+            roots.add(self.ca.der().clone()).unwrap();
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build().unwrap();
+            builder.with_client_cert_verifier(verifier)
+        } else {
+            builder.with_no_client_auth()
+        };
+        let config = builder.with_single_cert(read_certs(&cert), PrivateKeyDer::from_pem_file(key).unwrap()).unwrap();
+        TlsAcceptor::from(Arc::new(config))
+    }
+
+    fn transport(&self, address: String, client_cert: Option<(PathBuf, PathBuf)>) -> Transport {
+        let (client_cert, client_key) = client_cert.unzip();
+        Transport::Tls {
+            address,
+            server_name: Some("localhost".to_string()),
+            ca_cert: Some(self.path("ca.pem")),
+            client_cert,
+            client_key,
+        }
+    }
+}
+
+fn read_certs(path: &Path) -> Vec<CertificateDer<'static>> {
+    CertificateDer::pem_file_iter(path).unwrap().collect::<Result<_, _>>().unwrap()
+}
+
+/// Accepts TLS connections until the test ends, answering those that complete the handshake.
+async fn serve_tls(acceptor: TlsAcceptor) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            if let Ok(stream) = acceptor.accept(stream).await {
+                answer(stream).await;
+            }
+        }
+    });
+    address
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ingest.sock");
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+    let server = tokio::spawn(async move { answer(listener.accept().await.unwrap().0).await });
+
+    let message = send(&SocketClient::new(path.as_path())).await.unwrap();
+
+    assert_eq!(message, "Acme: SKU\nA1\n");
+    server.await.unwrap();
+}
+
+/// Reads one request and answers like a service that predates reply objects.
+async fn answer_legacy(mut stream: impl AsyncRead + AsyncWrite + Unpin, reply: &[u8]) {
+    let mut request = Vec::new();
+    stream.read_to_end(&mut request).await.unwrap();
+    stream.write_all(reply).await.unwrap();
+    stream.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn legacy_services_are_understood() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        answer_legacy(listener.accept().await.unwrap().0, b"stored\n").await;
+        answer_legacy(listener.accept().await.unwrap().0, b"stored\n").await;
+        answer_legacy(listener.accept().await.unwrap().0, b"").await;
+        answer_legacy(listener.accept().await.unwrap().0, b"").await;
+    });
+
+    let client = SocketClient::new(Transport::Tcp { address }).with_policy(no_retries());
+    assert!(send(&client).await.unwrap_err().contains("Could not read the ingest service's reply"));
+    assert_eq!(send(&client.clone().with_legacy_replies(true)).await.unwrap(), "stored");
+    assert!(send(&client).await.unwrap_err().contains("without a reply"));
+    assert_eq!(send(&client.with_legacy_replies(true)).await.unwrap(), "Sent to the ingest service");
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn truncated_replies_are_not_taken_as_accepted() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move {
+        answer_legacy(listener.accept().await.unwrap().0, br#"{"status":"ok","mess"#).await;
+    });
+
+    let client = SocketClient::new(Transport::Tcp { address }).with_policy(no_retries());
+    assert!(send(&client).await.unwrap_err().contains("Could not read the ingest service's reply"));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn plain_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server = tokio::spawn(async move { answer(listener.accept().await.unwrap().0).await });
+
+    let message = send(&SocketClient::new(Transport::Tcp { address })).await.unwrap();
+
+    assert_eq!(message, "Acme: SKU\nA1\n");
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn tls_with_private_ca() {
+    let pki = Pki::new();
+    let address = serve_tls(pki.acceptor(false)).await;
+
+    let message = send(&SocketClient::new(pki.transport(address, None))).await.unwrap();
+
+    assert_eq!(message, "Acme: SKU\nA1\n");
+}
+
+#[tokio::test]
+async fn tls_rejects_server_from_another_ca() {
+    let pki = Pki::new();
+    let address = serve_tls(pki.acceptor(false)).await;
+    let other = Pki::new();
+    let client = SocketClient::new(other.transport(address, None)).with_policy(no_retries());
+
+    let err = send(&client).await.unwrap_err();
+
+    assert!(err.contains("certificate"), "{}", err);
+}
+
+#[tokio::test]
+async fn tls_with_client_certificate() {
+    let pki = Pki::new();
+    let address = serve_tls(pki.acceptor(true)).await;
+    let client_cert = pki.issue("client");
+
+    let without = SocketClient::new(pki.transport(address.clone(), None)).with_policy(no_retries());
+    assert!(send(&without).await.is_err());
+
+    let with = SocketClient::new(pki.transport(address, Some(client_cert)));
+    assert_eq!(send(&with).await.unwrap(), "Acme: SKU\nA1\n");
+}
+
+#[test]
+fn transport_is_selected_by_config() {
+    let config: ClientConfig = serde_json::from_value(json!({
+        "transport": { "kind": "tls", "address": "ingest.example.com:7000", "ca_cert": "/etc/fs_ui/ca.pem" },
+        "max_attempts": 5,
+    }))
+    .unwrap();
+
+    assert_eq!(
+        config.transport,
+        Some(Transport::Tls {
+            address: "ingest.example.com:7000".to_string(),
+            server_name: None,
+            ca_cert: Some(PathBuf::from("/etc/fs_ui/ca.pem")),
+            client_cert: None,
+            client_key: None,
+        })
+    );
+    assert_eq!(config.policy.max_attempts, 5);
+    assert_eq!(config.policy.connect_timeout_ms, RetryPolicy::default().connect_timeout_ms);
+}
+
+#[test]
+fn locations_parse_to_transports() {
+    assert_eq!(
+        Transport::parse("tcp://10.0.0.5:7000").unwrap(),
+        Transport::Tcp { address: "10.0.0.5:7000".to_string() }
+    );
+    assert!(matches!(Transport::parse("tls://ingest:7000").unwrap(), Transport::Tls { .. }));
+    #[cfg(unix)]
+    assert_eq!(
+        Transport::parse("/run/ingest.sock").unwrap(),
+        Transport::Unix { path: PathBuf::from("/run/ingest.sock") }
+    );
+}
@@ -0,0 +1,371 @@
+//! A stand-in for the ingest service: binds a Unix socket, speaks the same
+//! protocol as `fs_ui_core::client` and answers from a script.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use fs_ui_core::dataset;
+use fs_ui_core::protocol::{Reply, Session, ACTION, IDEMPOTENCY_KEY, SESSION_TOKEN};
+use fs_ui_core::session::Role;
+use fs_ui_core::validation;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// How the mock answers one request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Behavior {
+    /// Accept the upload without looking at it.
+    Accept {
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// Validate the file against its mapping like the real service would.
+    #[default]
+    Validate,
+    /// Answer with a validation error.
+    Reject {
+        message: String,
+        #[serde(default)]
+        errors: Vec<String>,
+    },
+    /// Wait before behaving like `then`.
+    Delay { ms: u64, then: Box<Behavior> },
+    /// Close the connection without reading the request or replying.
+    Disconnect,
+    /// Behave like `then` but close the connection instead of replying, as if
+    /// the service crashed right after applying the request.
+    DropReply { then: Box<Behavior> },
+}
+
+/// An upload the mock accepted.
+#[derive(Debug, Clone, Serialize)]
+pub struct Received {
+    pub vendor_name: String,
+    pub password: String,
+    pub file_mappings: String,
+    pub delta_only: bool,
+    pub removed_keys: Vec<String>,
+    pub idempotency_key: Option<String>,
+    /// Operator whose session the upload carried.
+    pub operator: Option<String>,
+    pub file_bytes: Vec<u8>,
+    /// The whole request, for fields the mock does not know about.
+    pub request: Value,
+}
+
+impl Received {
+    fn from_request(request: Value) -> Result<Self, String> {
+        let field = |name: &str| request.get(name).cloned().unwrap_or(Value::Null);
+        let string = |name: &str| field(name).as_str().unwrap_or_default().to_string();
+        Ok(Received {
+            vendor_name: string("vendor_name"),
+            password: string("password"),
+            file_mappings: string("file_mappings"),
+            delta_only: field("delta_only").as_bool().unwrap_or_default(),
+            removed_keys: serde_json::from_value(field("removed_keys")).unwrap_or_default(),
+            idempotency_key: field(IDEMPOTENCY_KEY).as_str().map(str::to_string),
+            operator: None,
+            file_bytes: serde_json::from_value(field("file_bytes")).map_err(|e| format!("file_bytes: {}", e))?,
+            request,
+        })
+    }
+}
+
+#[derive(Default)]
+struct State {
+    script: VecDeque<Behavior>,
+    default: Behavior,
+    received: Vec<Received>,
+    /// Replies already given, by idempotency key, so repeats are not applied twice.
+    replies: HashMap<String, Reply>,
+    store_dir: Option<PathBuf>,
+    /// Operator passwords and roles; when there are any, uploads need a session.
+    users: BTreeMap<String, (String, Role)>,
+    /// Usernames by session token.
+    sessions: HashMap<String, String>,
+}
+
+impl State {
+    fn next_behavior(&mut self) -> Behavior {
+        self.script.pop_front().unwrap_or_else(|| self.default.clone())
+    }
+
+    fn login(&mut self, request: &Value) -> Reply {
+        let field = |name: &str| request.get(name).and_then(Value::as_str).unwrap_or_default();
+        let (username, password) = (field("username"), field("password"));
+        let Some((_, role)) = self.users.get(username).filter(|(expected, _)| expected == password) else {
+            return Reply::Error {
+                message: "Unknown username or wrong password".to_string(),
+                errors: Vec::new(),
+            };
+        };
+        let session = Session {
+            token: Uuid::new_v4().to_string(),
+            username: username.to_string(),
+            role: *role,
+        };
+        self.sessions.insert(session.token.clone(), session.username.clone());
+        Reply::Ok {
+            message: format!("Signed in as {}", username),
+            session: Some(session),
+        }
+    }
+
+    /// The operator behind the request's session, if the service requires one.
+    fn authorize(&self, request: &Value) -> Result<Option<String>, Reply> {
+        if self.users.is_empty() {
+            return Ok(None);
+        }
+        let token = request.get(SESSION_TOKEN).and_then(Value::as_str).unwrap_or_default();
+        match self.sessions.get(token) {
+            Some(username) => Ok(Some(username.clone())),
+            None => Err(Reply::Error {
+                message: "Not signed in".to_string(),
+                errors: Vec::new(),
+            }),
+        }
+    }
+
+    /// Writes an accepted upload as `<store_dir>/<vendor>/<n>.json` plus its file as `<n>.csv`.
+    fn persist(&self, received: &Received) -> Result<(), String> {
+        let Some(store_dir) = &self.store_dir else {
+            return Ok(());
+        };
+        let dir = store_dir.join(&received.vendor_name);
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let n = self.received.len();
+        let mut meta = received.request.clone();
+        if let Value::Object(fields) = &mut meta {
+            fields.remove("file_bytes");
+            fields.remove("password");
+            fields.remove(SESSION_TOKEN);
+        }
+        fs::write(dir.join(format!("{}.json", n)), meta.to_string()).map_err(|e| e.to_string())?;
+        fs::write(dir.join(format!("{}.csv", n)), &received.file_bytes).map_err(|e| e.to_string())
+    }
+}
+
+pub struct MockServer {
+    socket_path: PathBuf,
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Binds `socket_path`, replacing a stale socket file, and serves on a background thread.
+    pub fn start(socket_path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let socket_path = socket_path.into();
+        if socket_path.exists() {
+            fs::remove_file(&socket_path)?;
+        }
+        let listener = UnixListener::bind(&socket_path)?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let state = state.clone();
+            let stop = stop.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let state = state.clone();
+                    thread::spawn(move || handle_connection(stream, &state));
+                }
+            })
+        };
+
+        Ok(MockServer {
+            socket_path,
+            state,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Behaviors for the next requests, in order; afterwards the default applies.
+    pub fn script(&self, behaviors: impl IntoIterator<Item = Behavior>) {
+        self.state.lock().unwrap().script.extend(behaviors);
+    }
+
+    pub fn set_default(&self, behavior: Behavior) {
+        self.state.lock().unwrap().default = behavior;
+    }
+
+    pub fn set_store_dir(&self, dir: impl Into<PathBuf>) {
+        self.state.lock().unwrap().store_dir = Some(dir.into());
+    }
+
+    /// Adds an operator who can sign in. Once there is one, uploads need a session.
+    pub fn add_user(&self, username: impl Into<String>, password: impl Into<String>, role: Role) {
+        self.state.lock().unwrap().users.insert(username.into(), (password.into(), role));
+    }
+
+    /// Operators with an open session.
+    pub fn signed_in(&self) -> Vec<String> {
+        let mut users: Vec<String> = self.state.lock().unwrap().sessions.values().cloned().collect();
+        users.sort();
+        users
+    }
+
+    pub fn received(&self) -> Vec<Received> {
+        self.state.lock().unwrap().received.clone()
+    }
+
+    pub fn vendors(&self) -> Vec<String> {
+        let mut vendors: Vec<String> = self.received().into_iter().map(|r| r.vendor_name).collect();
+        vendors.sort();
+        vendors.dedup();
+        vendors
+    }
+
+    /// Blocks until the server is stopped from another thread or the process exits.
+    pub fn wait(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag. If the socket file is
+        // already gone the thread cannot be woken and is left to the process.
+        if UnixStream::connect(&self.socket_path).is_ok() {
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+        let _ = fs::remove_file(&self.socket_path);
+    }
+}
+
+fn handle_connection(mut stream: UnixStream, state: &Mutex<State>) {
+    let mut behavior = state.lock().unwrap().next_behavior();
+    while let Behavior::Delay { ms, then } = behavior {
+        thread::sleep(Duration::from_millis(ms));
+        behavior = *then;
+    }
+    if behavior == Behavior::Disconnect {
+        return;
+    }
+    let (behavior, reply_lost) = match behavior {
+        Behavior::DropReply { then } => (*then, true),
+        behavior => (behavior, false),
+    };
+
+    let mut request = Vec::new();
+    if stream.read_to_end(&mut request).is_err() || request.is_empty() {
+        return;
+    }
+    let reply = match serde_json::from_slice::<Value>(&request) {
+        Ok(request) => handle_request(behavior, request, state),
+        Err(e) => malformed(e.to_string()),
+    };
+    if reply_lost {
+        return;
+    }
+    let reply = serde_json::to_vec(&reply).unwrap_or_default();
+    let _ = stream.write_all(&reply);
+}
+
+fn handle_request(behavior: Behavior, request: Value, state: &Mutex<State>) -> Reply {
+    match request.get(ACTION).and_then(Value::as_str) {
+        Some("login") => state.lock().unwrap().login(&request),
+        Some("logout") => {
+            let token = request.get(SESSION_TOKEN).and_then(Value::as_str).unwrap_or_default();
+            state.lock().unwrap().sessions.remove(token);
+            Reply::ok("Signed out")
+        }
+        Some(action) => malformed(format!("unknown action \"{}\"", action)),
+        None => {
+            let operator = match state.lock().unwrap().authorize(&request) {
+                Ok(operator) => operator,
+                Err(reply) => return reply,
+            };
+            match Received::from_request(request) {
+                Ok(received) => respond_once(behavior, Received { operator, ..received }, state),
+                Err(e) => malformed(e),
+            }
+        }
+    }
+}
+
+fn malformed(e: String) -> Reply {
+    Reply::Error {
+        message: format!("Malformed request: {}", e),
+        errors: Vec::new(),
+    }
+}
+
+/// Answers a request seen before with the reply it got then.
+fn respond_once(behavior: Behavior, received: Received, state: &Mutex<State>) -> Reply {
+    let Some(key) = received.idempotency_key.clone() else {
+        return respond(behavior, received, state);
+    };
+    let repeated = state.lock().unwrap().replies.get(&key).cloned();
+    repeated.unwrap_or_else(|| {
+        let reply = respond(behavior, received, state);
+        state.lock().unwrap().replies.insert(key, reply.clone());
+        reply
+    })
+}
+
+fn respond(behavior: Behavior, received: Received, state: &Mutex<State>) -> Reply {
+    match behavior {
+        Behavior::Reject { message, errors } => Reply::Error { message, errors },
+        Behavior::Validate => {
+            let mapping = dataset::parse_mappings(&received.file_mappings);
+            // Files with prices arrive as plain numbers.
+            let report = validation::validate(received.file_bytes.as_slice(), &mapping, Some('.'));
+            if report.is_valid() {
+                accept(None, received, state)
+            } else {
+                Reply::Error {
+                    message: "Validation failed".to_string(),
+                    errors: report.errors,
+                }
+            }
+        }
+        Behavior::Accept { message } => accept(message, received, state),
+        Behavior::Delay { .. } | Behavior::Disconnect | Behavior::DropReply { .. } => {
+            unreachable!("handled before reading the request")
+        }
+    }
+}
+
+fn accept(message: Option<String>, received: Received, state: &Mutex<State>) -> Reply {
+    let mut state = state.lock().unwrap();
+    if let Err(e) = state.persist(&received) {
+        return Reply::Error {
+            message: format!("Could not store the upload: {}", e),
+            errors: Vec::new(),
+        };
+    }
+    let message = message.unwrap_or_else(|| {
+        format!(
+            "Received {} bytes for {}",
+            received.file_bytes.len(),
+            received.vendor_name
+        )
+    });
+    state.received.push(received);
+    Reply::ok(message)
+}
@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use fs_ui_core::session::Role;
+use fs_ui_mock::{Behavior, MockServer};
+
+/// Operator added when no `--user` is given, so the app can always sign in.
+const DEFAULT_USER: &str = "admin";
+
+#[derive(Parser)]
+#[command(name = "fs_ui-mock", version, about = "Stand-in for the ingest service")]
+struct Args {
+    /// Unix socket to listen on.
+    #[arg(long, env = "SOCKET_LOCATION")]
+    socket: PathBuf,
+
+    /// Keep accepted uploads on disk under this directory instead of only in memory.
+    #[arg(long)]
+    store_dir: Option<PathBuf>,
+
+    /// JSON array of behaviors for the first requests, e.g.
+    /// `[{"action": "reject", "message": "Bad file"}, {"action": "disconnect"}]`.
+    #[arg(long)]
+    script: Option<PathBuf>,
+
+    /// Behavior once the script is used up: accept, validate, reject or disconnect.
+    #[arg(long, default_value = "validate")]
+    default: String,
+
+    /// Operator who can sign in, as `username:password[:role]`; repeat for more.
+    /// The role is viewer, uploader or admin (the default). Without any, `admin:admin` is added.
+    #[arg(long = "user", value_name = "USERNAME:PASSWORD[:ROLE]")]
+    users: Vec<String>,
+
+    /// Wait this long before answering every request.
+    #[arg(long, default_value_t = 0)]
+    delay_ms: u64,
+}
+
+fn default_behavior(args: &Args) -> Result<Behavior, String> {
+    let behavior = match args.default.as_str() {
+        "accept" => Behavior::Accept { message: None },
+        "validate" => Behavior::Validate,
+        "reject" => Behavior::Reject {
+            message: "Rejected by fs_ui-mock".to_string(),
+            errors: Vec::new(),
+        },
+        "disconnect" => Behavior::Disconnect,
+        other => return Err(format!("Unknown behavior \"{}\"", other)),
+    };
+    Ok(match args.delay_ms {
+        0 => behavior,
+        ms => Behavior::Delay { ms, then: Box::new(behavior) },
+    })
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let server = MockServer::start(&args.socket).map_err(|e| format!("{}: {}", args.socket.display(), e))?;
+    server.set_default(default_behavior(&args)?);
+    for user in &args.users {
+        let (username, rest) = user
+            .split_once(':')
+            .ok_or_else(|| format!("--user {}: expected USERNAME:PASSWORD[:ROLE]", user))?;
+        let (password, role) = match rest.rsplit_once(':') {
+            Some((password, role)) => (password, role.parse().map_err(|e| format!("--user {}: {}", user, e))?),
+            None => (rest, Role::Admin),
+        };
+        server.add_user(username, password, role);
+    }
+    if args.users.is_empty() {
+        server.add_user(DEFAULT_USER, DEFAULT_USER, Role::Admin);
+        println!("Sign in as {0} with password {0}", DEFAULT_USER);
+    }
+    if let Some(dir) = &args.store_dir {
+        server.set_store_dir(dir);
+    }
+    if let Some(script) = &args.script {
+        let json = fs::read_to_string(script).map_err(|e| format!("{}: {}", script.display(), e))?;
+        let behaviors: Vec<Behavior> = serde_json::from_str(&json).map_err(|e| format!("{}: {}", script.display(), e))?;
+        server.script(behaviors);
+    }
+    println!("Listening on {}", server.socket_path().display());
+    server.wait();
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Uploads recorded in the audit log against the mock service.
+
+mod common;
+
+use fs_ui_core::audit::{AuditAction, AuditFilter};
+use fs_ui_core::upload::{self, VendorInfo};
+use fs_ui_mock::Behavior;
+
+use common::fixture;
+
+fn payload(file_mappings: &str) -> VendorInfo {
+    common::payload(file_mappings, "SKU,Qty,Stock\nA1,5,6\n")
+}
+
+#[tokio::test]
+async fn vendor_lifecycle_is_recorded() {
+    let f = fixture();
+    let submit = |payload| upload::submit_audited(payload, &f.store, &f.client, &f.log, "alice", "alice");
+
+    submit(payload("File Key:SKU, Quantity:Qty")).await.unwrap();
+    submit(payload("")).await.unwrap();
+    submit(payload("File Key:SKU, Quantity:Stock")).await.unwrap();
+
+    let entries = f.log.read(&AuditFilter::default()).unwrap();
+    let actions: Vec<AuditAction> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(actions, [AuditAction::CreateVendor, AuditAction::Upload, AuditAction::ChangeMapping]);
+    assert!(entries.iter().all(|e| e.actor == "alice" && e.target == "Acme" && e.ok));
+    assert!(entries[0].before.is_none());
+    assert_eq!(entries[2].before.as_ref().unwrap()["file_mappings"], "File Key:SKU, Quantity:Qty");
+    assert_eq!(entries[2].after.as_ref().unwrap()["file_mappings"], "File Key:SKU, Quantity:Stock");
+    let hash = entries[0].file_sha256.as_deref().unwrap();
+    assert_eq!(hash.len(), 64);
+    assert!(entries.iter().all(|e| e.file_sha256.as_deref() == Some(hash)));
+    let text = serde_json::to_string(&entries).unwrap();
+    assert!(!text.contains("secret"));
+}
+
+#[tokio::test]
+async fn rejected_upload_is_recorded_as_failed() {
+    let f = fixture();
+    f.server.script([Behavior::Reject { message: "Bad file".to_string(), errors: Vec::new() }]);
+
+    let err = upload::submit_audited(payload("File Key:SKU, Quantity:Qty"), &f.store, &f.client, &f.log, "alice", "alice").await.unwrap_err();
+
+    let entries = f.log.read(&AuditFilter::default()).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(!entries[0].ok);
+    assert_eq!(entries[0].message, err);
+}
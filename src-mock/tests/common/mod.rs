@@ -0,0 +1,49 @@
+//! The mock service and a vendor store to upload to it from, shared by the tests.
+
+#![allow(dead_code)]
+
+use fs_ui_core::audit::AuditLog;
+use fs_ui_core::client::{IngestClient, RetryPolicy, SocketClient};
+use fs_ui_core::store::VendorStore;
+use fs_ui_core::upload::VendorInfo;
+use fs_ui_mock::MockServer;
+use tempfile::TempDir;
+
+// Fields drop in order: the server must stop before its directory goes away.
+pub struct Fixture {
+    pub server: MockServer,
+    pub store: VendorStore,
+    pub client: IngestClient,
+    pub log: AuditLog,
+    pub dir: TempDir,
+}
+
+pub fn fixture() -> Fixture {
+    fixture_with(RetryPolicy {
+        initial_backoff_ms: 10,
+        ..RetryPolicy::default()
+    })
+}
+
+pub fn fixture_with(policy: RetryPolicy) -> Fixture {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("ingest.sock");
+    Fixture {
+        server: MockServer::start(&socket).unwrap(),
+        store: VendorStore::new(dir.path().join("vendors")),
+        client: SocketClient::new(socket.as_path()).with_policy(policy).into(),
+        log: AuditLog::new(dir.path().join("audit.jsonl")),
+        dir,
+    }
+}
+
+/// An upload of `csv` for "Acme" under `file_mappings`.
+pub fn payload(file_mappings: &str, csv: &str) -> VendorInfo {
+    VendorInfo {
+        vendor_name: "Acme".to_string(),
+        password: "secret".to_string(),
+        file_mappings: file_mappings.to_string(),
+        file_bytes: csv.as_bytes().to_vec(),
+        ..VendorInfo::default()
+    }
+}
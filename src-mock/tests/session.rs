@@ -0,0 +1,103 @@
+//! Operator login against the mock service and the session kept by the app.
+
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use fs_ui_core::session::{Permission, Role, Sessions};
+use fs_ui_core::upload::{self, VendorInfo};
+
+use common::Fixture;
+
+fn fixture() -> Fixture {
+    let f = common::fixture();
+    f.server.add_user("alice", "s3cret", Role::Uploader);
+    f.server.add_user("root", "t00r", Role::Admin);
+    f.server.add_user("vic", "v1ew", Role::Viewer);
+    f
+}
+
+fn payload() -> VendorInfo {
+    common::payload("File Key:SKU, Quantity:Qty", "SKU,Qty\nA1,5\n")
+}
+
+#[tokio::test]
+async fn wrong_password_is_refused() {
+    let f = fixture();
+
+    let err = f.client.login("alice", "guess").await.unwrap_err();
+
+    assert_eq!(err, "Unknown username or wrong password");
+    assert!(f.server.signed_in().is_empty());
+}
+
+#[tokio::test]
+async fn upload_needs_a_session() {
+    let f = fixture();
+
+    let err = upload::submit(payload(), &f.store, &f.client, "").await.unwrap_err();
+
+    assert_eq!(err, "Not signed in");
+    assert!(f.server.received().is_empty());
+}
+
+#[tokio::test]
+async fn session_is_attached_to_uploads_until_logout() {
+    let f = fixture();
+    let session = f.client.login("alice", "s3cret").await.unwrap();
+    assert_eq!(session.username, "alice");
+    let client = f.client.clone().with_session_token(session.token);
+
+    upload::submit(payload(), &f.store, &client, "").await.unwrap();
+    assert_eq!(f.server.received()[0].operator.as_deref(), Some("alice"));
+
+    client.logout().await.unwrap();
+    assert!(f.server.signed_in().is_empty());
+    assert_eq!(upload::submit(payload(), &f.store, &client, "").await.unwrap_err(), "Not signed in");
+}
+
+#[tokio::test]
+async fn idle_session_expires() {
+    let f = fixture();
+    let sessions = Sessions::new(Duration::from_millis(100));
+    sessions.start(f.client.login("alice", "s3cret").await.unwrap());
+
+    assert!(sessions.touch().is_ok());
+    thread::sleep(Duration::from_millis(150));
+
+    assert!(sessions.touch().unwrap_err().contains("inactivity"));
+    assert!(sessions.current().is_none());
+}
+
+#[tokio::test]
+async fn role_comes_from_the_service() {
+    let f = fixture();
+    let sessions = Sessions::new(Duration::from_secs(60));
+
+    let operator = sessions.start(f.client.login("vic", "v1ew").await.unwrap());
+
+    assert_eq!(operator.role, Role::Viewer);
+    assert!(operator.permissions.is_empty());
+    assert_eq!(sessions.authorize(Permission::Upload).unwrap_err(), "The viewer role does not allow uploading files");
+}
+
+#[tokio::test]
+async fn uploaders_keep_to_existing_vendors_and_mappings() {
+    let f = fixture();
+    let sessions = Sessions::new(Duration::from_secs(60));
+    assert_eq!(upload::required_permission(&payload(), None), Permission::CreateVendor);
+
+    let admin = f.client.clone().with_session_token(f.client.login("root", "t00r").await.unwrap().token);
+    upload::submit(payload(), &f.store, &admin, "").await.unwrap();
+    let saved = f.store.load("Acme").unwrap();
+    let mut remapped = payload();
+    remapped.file_mappings = "File Key:Qty, Quantity:SKU".to_string();
+    assert_eq!(upload::required_permission(&payload(), saved.as_ref()), Permission::Upload);
+    assert_eq!(upload::required_permission(&remapped, saved.as_ref()), Permission::EditMapping);
+
+    sessions.start(f.client.login("alice", "s3cret").await.unwrap());
+    assert!(sessions.authorize(Permission::Upload).is_ok());
+    assert!(sessions.authorize(Permission::EditMapping).unwrap_err().contains("changing a vendor's mapping"));
+    assert!(sessions.authorize(Permission::CreateVendor).is_err());
+}
@@ -0,0 +1,275 @@
+//! Drives the upload path behind the `setup_new_user` command against the mock service.
+
+mod common;
+
+use std::fs;
+
+use fs_ui_core::client::{RetryPolicy, SocketClient};
+use fs_ui_core::dialect::Dialect;
+use fs_ui_core::upload::{self, VendorInfo};
+use fs_ui_mock::Behavior;
+
+use common::{fixture, fixture_with};
+
+const MAPPINGS: &str = "File Key:SKU, Brand:Make, Quantity:Qty";
+
+fn payload(csv: &str) -> VendorInfo {
+    common::payload(MAPPINGS, csv)
+}
+
+#[tokio::test]
+async fn accepted_upload_is_received_and_remembered() {
+    let f = fixture();
+    let message = upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap();
+
+    assert_eq!(message, "Received 23 bytes for Acme");
+    let received = f.server.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].vendor_name, "Acme");
+    assert_eq!(received[0].file_mappings, MAPPINGS);
+    assert_eq!(received[0].file_bytes, b"SKU,Make,Qty\nA1,Acme,5\n");
+    assert!(f.store.load("Acme").unwrap().is_some());
+}
+
+#[tokio::test]
+async fn file_picked_by_path_is_streamed() {
+    let f = fixture();
+    let path = f.dir.path().join("stock.csv");
+    fs::write(&path, "SKU,Make,Qty\nA1,Acme,5\nA2,Acme,7\n").unwrap();
+    let mut payload = payload("");
+    payload.file_path = Some(path.display().to_string());
+
+    upload::submit(payload, &f.store, &f.client, "").await.unwrap();
+
+    assert_eq!(f.server.received()[0].file_bytes, fs::read(&path).unwrap());
+}
+
+#[tokio::test]
+async fn rejection_is_reported_and_not_remembered() {
+    let f = fixture();
+    f.server.script([Behavior::Reject {
+        message: "Validation failed".to_string(),
+        errors: vec!["Line 2: unknown brand".to_string()],
+    }]);
+
+    let err = upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap_err();
+
+    assert_eq!(err, "Validation failed\nLine 2: unknown brand");
+    assert!(f.server.received().is_empty());
+    assert!(f.store.load("Acme").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn disconnect_is_an_error() {
+    let f = fixture();
+    f.server.set_default(Behavior::Disconnect);
+
+    assert!(upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.is_err());
+    assert!(f.store.load("Acme").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn delayed_reply_is_awaited() {
+    let f = fixture();
+    f.server.script([Behavior::Delay {
+        ms: 200,
+        then: Box::new(Behavior::Accept { message: Some("late".to_string()) }),
+    }]);
+
+    let message = upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap();
+
+    assert_eq!(message, "late");
+}
+
+#[tokio::test]
+async fn invalid_file_never_reaches_the_service() {
+    let f = fixture();
+
+    let err = upload::submit(payload("SKU,Make,Qty\nA1,Acme,lots\n"), &f.store, &f.client, "").await.unwrap_err();
+
+    assert!(err.contains("Quantity \"lots\" is not a number"), "{}", err);
+    assert!(f.server.received().is_empty());
+}
+
+#[tokio::test]
+async fn delta_upload_sends_only_changed_rows() {
+    let f = fixture();
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\nA2,Acme,7\nA3,Acme,1\n"), &f.store, &f.client, "").await.unwrap();
+
+    let mut delta = payload("SKU,Make,Qty\nA1,Acme,5\nA2,Acme,9\nA4,Acme,2\n");
+    delta.delta_only = true;
+    upload::submit(delta, &f.store, &f.client, "").await.unwrap();
+
+    let received = f.server.received();
+    assert_eq!(received[1].file_bytes, b"SKU,Make,Qty\nA2,Acme,9\nA4,Acme,2\n");
+    assert_eq!(received[1].removed_keys, vec!["A3".to_string()]);
+    assert!(received[1].delta_only);
+}
+
+#[tokio::test]
+async fn store_dir_keeps_uploads_without_the_password() {
+    let f = fixture();
+    let store_dir = f.dir.path().join("received");
+    f.server.set_store_dir(&store_dir);
+
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap();
+
+    let meta = fs::read_to_string(store_dir.join("Acme").join("0.json")).unwrap();
+    assert!(!meta.contains("secret"));
+    assert_eq!(fs::read(store_dir.join("Acme").join("0.csv")).unwrap(), b"SKU,Make,Qty\nA1,Acme,5\n");
+    assert_eq!(f.server.vendors(), vec!["Acme".to_string()]);
+}
+
+#[tokio::test]
+async fn disconnect_is_retried() {
+    let f = fixture();
+    f.server.script([Behavior::Disconnect]);
+
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap();
+
+    assert_eq!(f.server.received().len(), 1);
+    assert!(f.store.load("Acme").unwrap().is_some());
+}
+
+#[tokio::test]
+async fn retried_upload_is_applied_once() {
+    let f = fixture();
+    f.server.script([Behavior::DropReply { then: Box::new(Behavior::Validate) }]);
+
+    let message = upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap();
+
+    assert_eq!(message, "Received 23 bytes for Acme");
+    assert_eq!(f.server.received().len(), 1);
+}
+
+#[tokio::test]
+async fn hung_service_times_out() {
+    let f = fixture_with(RetryPolicy {
+        read_timeout_ms: 100,
+        max_attempts: 1,
+        ..RetryPolicy::default()
+    });
+    f.server.script([Behavior::Delay {
+        ms: 2_000,
+        then: Box::new(Behavior::Validate),
+    }]);
+
+    let err = upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap_err();
+
+    assert!(err.contains("Timed out"), "{}", err);
+    assert!(f.store.load("Acme").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn ping_retries_until_attempts_run_out() {
+    let f = fixture();
+    let client = SocketClient::new(f.dir.path().join("missing.sock")).with_policy(RetryPolicy {
+        initial_backoff_ms: 10,
+        ..RetryPolicy::default()
+    });
+
+    let err = client.ping().await.unwrap_err();
+
+    assert!(err.ends_with("(after 3 attempts)"), "{}", err);
+}
+
+#[tokio::test]
+async fn other_dialects_are_sent_as_utf8_with_commas_and_remembered() {
+    let f = fixture();
+    // "Müller" in Windows-1252, separated by semicolons.
+    let mut first = payload("");
+    first.file_bytes = b"SKU;Make;Qty\nA1;M\xfcller;5\n".to_vec();
+    first.dialect = Some(Dialect { delimiter: ';', encoding: "windows-1252".to_string(), ..Dialect::default() });
+    upload::submit(first, &f.store, &f.client, "").await.unwrap();
+
+    // Later uploads, e.g. from a watch folder, fall back to the saved dialect.
+    let mut next = payload("");
+    next.file_bytes = b"SKU;Make;Qty\nA1;M\xfcller;6\n".to_vec();
+    upload::submit(next, &f.store, &f.client, "").await.unwrap();
+
+    let received = f.server.received();
+    assert_eq!(received[0].file_bytes, "SKU,Make,Qty\nA1,Müller,5\n".as_bytes());
+    assert_eq!(received[1].file_bytes, "SKU,Make,Qty\nA1,Müller,6\n".as_bytes());
+    assert_eq!(f.store.load("Acme").unwrap().unwrap().dialect.delimiter, ';');
+}
+
+#[tokio::test]
+async fn accepted_uploads_are_kept_in_the_vendor_history() {
+    let f = fixture();
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\nA2,Acme,7\n"), &f.store, &f.client, "").await.unwrap();
+    f.server.script([Behavior::Reject { message: "No".to_string(), errors: Vec::new() }]);
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,1\n"), &f.store, &f.client, "").await.unwrap_err();
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,2\nA2,Acme,\n"), &f.store, &f.client, "").await.unwrap();
+
+    let history = f.store.load("Acme").unwrap().unwrap().history;
+    assert_eq!(history.iter().map(|s| (s.rows, s.quantity)).collect::<Vec<_>>(), [(2, 12.0), (2, 2.0)]);
+}
+
+#[tokio::test]
+async fn uploads_record_the_profile_version_they_were_mapped_with() {
+    let f = fixture();
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,5\n"), &f.store, &f.client, "").await.unwrap();
+    upload::submit(payload("SKU,Make,Qty\nA1,Acme,6\n"), &f.store, &f.client, "").await.unwrap();
+    let remapped = VendorInfo {
+        file_mappings: "File Key:SKU, Quantity:Qty".to_string(),
+        change_note: "Brand comes from the catalog now".to_string(),
+        ..payload("SKU,Make,Qty\nA1,Acme,7\n")
+    };
+    upload::submit(remapped, &f.store, &f.client, "alice").await.unwrap();
+
+    let record = f.store.load("Acme").unwrap().unwrap();
+    assert_eq!(record.history.iter().map(|s| s.version).collect::<Vec<_>>(), [1, 1, 2]);
+    assert_eq!(record.versions.len(), 2);
+    assert_eq!(record.versions[1].author, "alice");
+    assert_eq!(record.versions[1].note, "Brand comes from the catalog now");
+    // The note stays with the profile; the service never sees it.
+    assert!(f.server.received().iter().all(|r| r.request.get("change_note").is_none()));
+}
+
+#[tokio::test]
+async fn computed_fields_are_sent_as_plain_columns() {
+    let f = fixture();
+    let mut upload = payload("SKU,Make,WH1,WH2\nA1,Acme,3,4\n");
+    upload.file_mappings = "File Key:SKU, Brand:Make, Quantity:=sum([WH1], [WH2])".to_string();
+    upload::submit(upload, &f.store, &f.client, "").await.unwrap();
+
+    let received = f.server.received();
+    assert_eq!(received[0].file_mappings, "Brand:Brand, File Key:File Key, Quantity:Quantity");
+    assert_eq!(received[0].file_bytes, b"File Key,Brand,Quantity\nA1,Acme,7\n");
+    let record = f.store.load("Acme").unwrap().unwrap();
+    assert_eq!(record.file_mappings, "File Key:SKU, Brand:Make, Quantity:=sum([WH1], [WH2])");
+    assert_eq!(record.dataset.rows["A1"]["Quantity"], "7");
+}
+
+#[tokio::test]
+async fn rows_per_location_are_sent_as_one_row_per_item() {
+    let f = fixture();
+    let mut upload = payload("SKU,Make,Site,Qty\nA1,Acme,North,3\nA1,Acme,South,4\n");
+    upload.file_mappings = "File Key:SKU, Brand:Make, Location:Site, Quantity:Qty".to_string();
+    upload::submit(upload, &f.store, &f.client, "").await.unwrap();
+
+    let received = f.server.received();
+    assert_eq!(received[0].file_bytes, b"File Key,Brand,Quantity,Quantity@North,Quantity@South\nA1,Acme,7,3,4\n");
+    let record = f.store.load("Acme").unwrap().unwrap();
+    assert_eq!(record.dataset.rows["A1"]["Quantity@North"], "3");
+    assert_eq!(record.history[0].quantity, 7.0);
+}
+
+#[tokio::test]
+async fn prices_are_converted_and_sent_as_plain_numbers() {
+    let f = fixture();
+    let mut rates = f.store.rates().unwrap();
+    rates.rates.insert("EUR".to_string(), 1.1);
+    rates.convert_to = "USD".to_string();
+    f.store.save_rates(&mut rates).unwrap();
+
+    let mut upload = payload("SKU,Make,Qty,Price\nA1,Acme,5,\"1.234,50 €\"\n");
+    upload.file_mappings = "File Key:SKU, Brand:Make, Quantity:Qty, Cost:Price".to_string();
+    upload::submit(upload, &f.store, &f.client, "").await.unwrap();
+
+    let received = f.server.received();
+    assert_eq!(received[0].file_bytes, b"File Key,Brand,Cost,Currency,Quantity\nA1,Acme,1357.95,USD,5\n");
+    let record = f.store.load("Acme").unwrap().unwrap();
+    assert_eq!(record.dataset.rows["A1"]["Cost"], "1357.95");
+    assert_eq!(record.file_mappings, "File Key:SKU, Brand:Make, Quantity:Qty, Cost:Price");
+}
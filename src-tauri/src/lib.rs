@@ -1,13 +1,41 @@
-use std::{env, io::Write, os::unix::net::UnixStream};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::{collections::BTreeMap, path::PathBuf, sync::{Arc, Mutex}};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_notification::NotificationExt;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct VendorInfo {
-    vendor_name: String,
-    password: String,
+use fs_ui_core::audit::{self, AuditAction, AuditEntry, AuditFilter, AuditLog, ExportFormat};
+use fs_ui_core::catalog::{self, CatalogCache, CatalogPage, CatalogQuery, ItemDetail};
+use fs_ui_core::client::{self, ClientConfig};
+use fs_ui_core::dashboard::{Metrics, Periods};
+use fs_ui_core::dataset::{self, Dataset, DatasetDiff, FieldChange};
+use fs_ui_core::diagnostics;
+use fs_ui_core::dialect::{self, Dialect};
+use fs_ui_core::drafts::{Draft, DraftStore};
+use fs_ui_core::export;
+use fs_ui_core::logging;
+use fs_ui_core::pricing::RateTable;
+use fs_ui_core::profile::{self, ImportPreview, ImportSummary, Profile, ProfileDocument, ProfileFormat, Resolution, VersionUse};
+use fs_ui_core::session::{Operator, Permission, Sessions};
+use fs_ui_core::source::{self, FileSource, FileSummary};
+use fs_ui_core::store::VendorStore;
+use fs_ui_core::upload::{self, Inspection, VendorInfo};
+use fs_ui_core::validation::{self, ValidationReport};
+
+mod watcher;
+
+use watcher::{FolderWatcher, IngestResult, IngestStatus, WatchFolder};
+
+struct WatchState {
+    config_path: PathBuf,
+    watcher: Mutex<FolderWatcher>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileReport {
+    headers: Vec<String>,
     file_mappings: String,
-    file_bytes: Vec<u8>,
+    validation: ValidationReport,
 }
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -17,20 +45,608 @@ fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-fn setup_new_user(payload: VendorInfo) -> Result<String, String> {
-    println!("{:#?}", payload);
-    let socket_path = env::var("SOCKET_LOCATION").map_err(|e| e.to_string())?;
-    let mut stream = UnixStream::connect(&socket_path).map_err(|e| e.to_string())?;
-    let msg = serde_json::to_string(&payload).map_err(|e| e.to_string())?;
-    stream.write_all(msg.as_bytes()).map_err(|e| e.to_string())?;
-    Ok(String::from("Gotten into the rust side!!"))
+fn list_vendors(store: State<VendorStore>, sessions: State<Arc<Sessions>>) -> Result<Vec<String>, String> {
+    sessions.touch()?;
+    store.list()
+}
+
+/// One page of the items the vendors' last uploads hold; any signed-in operator may browse them.
+#[tauri::command]
+async fn query_catalog(
+    query: CatalogQuery,
+    store: State<'_, VendorStore>,
+    catalog: State<'_, Arc<CatalogCache>>,
+    sessions: State<'_, Arc<Sessions>>,
+) -> Result<CatalogPage, String> {
+    sessions.touch()?;
+    let (store, catalog) = (store.inner().clone(), catalog.inner().clone());
+    blocking(move || Ok(catalog.get(&store)?.query(&query))).await
+}
+
+#[tauri::command]
+async fn catalog_item(vendor_name: String, key: String, store: State<'_, VendorStore>, sessions: State<'_, Arc<Sessions>>) -> Result<ItemDetail, String> {
+    sessions.touch()?;
+    let store = store.inner().clone();
+    blocking(move || {
+        catalog::detail(&store, &vendor_name, &key)?.ok_or_else(|| format!("{} has no item \"{}\"", vendor_name, key))
+    })
+    .await
+}
+
+/// Runs file work off the async runtime's workers.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(work).await.map_err(|e| e.to_string())?
+}
+
+/// Detects a mapping for one file of a batch and validates the file against
+/// it, reading the file in the vendor's saved dialect as its upload will.
+#[tauri::command]
+async fn inspect_file(
+    vendor_name: String,
+    file_bytes: Vec<u8>,
+    store: State<'_, VendorStore>,
+    sessions: State<'_, Arc<Sessions>>,
+) -> Result<FileReport, String> {
+    sessions.touch()?;
+    let store = store.inner().clone();
+    blocking(move || {
+        let saved = store.load(&vendor_name)?;
+        let dialect = saved.as_ref().map(|record| record.dialect.clone()).unwrap_or_default();
+        let source = dialect.normalize(FileSource::Bytes(file_bytes))?;
+        let headers = dataset::read_headers(source.reader()?)?;
+        let mapping = dataset::detect_mapping(&headers, saved.as_ref().map(|record| dataset::parse_mappings(&record.file_mappings)).as_ref());
+        Ok(FileReport {
+            headers,
+            file_mappings: dataset::format_mappings(&mapping),
+            validation: validation::validate(source.reader()?, &mapping, dialect.decimal),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+async fn open_file_dialog(app: AppHandle, sessions: State<'_, Arc<Sessions>>) -> Result<Option<FileSummary>, String> {
+    sessions.touch()?;
+    let picked = app
+        .dialog()
+        .file()
+        .add_filter("CSV", &["csv"])
+        .blocking_pick_file();
+    match picked {
+        Some(file_path) => {
+            let path = file_path.into_path().map_err(|e| e.to_string())?;
+            source::summarize(&path).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+#[tauri::command]
+async fn pick_folder(app: AppHandle) -> Result<Option<String>, String> {
+    match app.dialog().file().blocking_pick_folder() {
+        Some(folder) => folder
+            .into_path()
+            .map(|path| Some(path.display().to_string()))
+            .map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Summarizes a file dropped onto the window from the OS, which only hands the webview its path.
+#[tauri::command]
+async fn inspect_path(path: String, sessions: State<'_, Arc<Sessions>>) -> Result<FileSummary, String> {
+    sessions.touch()?;
+    blocking(move || source::summarize(&PathBuf::from(path))).await
+}
+
+#[tauri::command]
+async fn sniff_dialect(mut payload: VendorInfo, store: State<'_, VendorStore>, sessions: State<'_, Arc<Sessions>>) -> Result<Dialect, String> {
+    sessions.touch()?;
+    let store = store.inner().clone();
+    blocking(move || {
+        let mut dialect = dialect::sniff_source(&payload.take_source())?;
+        // The vendor's confirmed decimal separator is kept; the file cannot tell it apart.
+        dialect.decimal = store.load(&payload.vendor_name)?.and_then(|record| record.dialect.decimal);
+        Ok(dialect)
+    })
+    .await
+}
+
+#[tauri::command]
+async fn inspect_upload(payload: VendorInfo, store: State<'_, VendorStore>, sessions: State<'_, Arc<Sessions>>) -> Result<Inspection, String> {
+    sessions.touch()?;
+    let store = store.inner().clone();
+    blocking(move || upload::inspect(payload, &store)).await
+}
+
+#[tauri::command]
+async fn preview_upload_diff(mut payload: VendorInfo, store: State<'_, VendorStore>, sessions: State<'_, Arc<Sessions>>) -> Result<DatasetDiff, String> {
+    sessions.touch()?;
+    let store = store.inner().clone();
+    blocking(move || {
+        let previous = store.load(&payload.vendor_name)?;
+        let mapping = dataset::parse_mappings(&upload::resolve_mappings(&payload, previous.as_ref()));
+        let dialect = upload::resolve_dialect(&payload, previous.as_ref());
+        let source = dialect.normalize(payload.take_source())?;
+        let mut current = Dataset::from_csv(source.reader()?, &mapping, dialect.decimal)?;
+        store.rates()?.convert(&mut current)?;
+        let previous = previous.map(|record| record.dataset).unwrap_or_default();
+        Ok(dataset::diff(&previous, &current))
+    })
+    .await
+}
+
+#[tauri::command]
+async fn login(
+    username: String,
+    password: String,
+    client_config: State<'_, ClientConfig>,
+    sessions: State<'_, Arc<Sessions>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+) -> Result<Operator, String> {
+    let session = match client_config.client() {
+        Ok(client) => client.login(&username, &password).await,
+        Err(e) => Err(e),
+    };
+    let entry = AuditEntry::new(&username, AuditAction::Login, &username).outcome(&session);
+    let result = session.map(|session| sessions.start(session));
+    match (audit_log.append(&entry), result) {
+        (Err(e), Ok(operator)) => {
+            // The operator is signed in either way; a missing audit line must not undo that.
+            tracing::error!(operator = %username, error = %e, "signed in, but the audit log could not be written");
+            Ok(operator)
+        }
+        (Err(e), Err(failed)) => Err(format!("{} (the audit log could not be written either: {})", failed, e)),
+        (Ok(()), result) => result,
+    }
+}
+
+/// Ends the session here even when the service cannot be told.
+#[tauri::command]
+async fn logout(client_config: State<'_, ClientConfig>, sessions: State<'_, Arc<Sessions>>, audit_log: State<'_, Arc<AuditLog>>) -> Result<(), String> {
+    let Some(session) = sessions.end() else {
+        return Ok(());
+    };
+    let username = session.username.clone();
+    let result = match client_config.client() {
+        Ok(client) => client.with_session_token(session.token).logout().await,
+        Err(e) => Err(e),
+    };
+    audit_log.record(AuditEntry::new(&username, AuditAction::Logout, &username), result, "Signed out")
+}
+
+#[tauri::command]
+fn current_operator(sessions: State<Arc<Sessions>>) -> Option<Operator> {
+    sessions.operator()
+}
+
+/// Keeps the session alive while the operator works in forms that call no other command.
+#[tauri::command]
+fn touch_session(sessions: State<Arc<Sessions>>) -> Result<(), String> {
+    sessions.touch().map(|_| ())
+}
+
+#[tauri::command]
+async fn setup_new_user(
+    app: AppHandle,
+    payload: VendorInfo,
+    store: State<'_, VendorStore>,
+    client_config: State<'_, ClientConfig>,
+    sessions: State<'_, Arc<Sessions>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+) -> Result<String, String> {
+    let previous = store.load(&payload.vendor_name)?;
+    let session = sessions.authorize(upload::required_permission(&payload, previous.as_ref()))?;
+    tracing::info!(operator = %session.username, ?payload, "setup_new_user");
+    let client = client_config.client()?.with_session_token(session.token);
+    let vendor_name = payload.vendor_name.clone();
+    let result = upload::submit_audited(payload, &store, &client, &audit_log, &session.username, &session.username).await;
+    // Lets the dashboard catch up, whether or not the upload went through.
+    let _ = app.emit("upload://finished", vendor_name);
+    result
+}
+
+#[tauri::command]
+fn list_drafts(drafts: State<DraftStore>, sessions: State<Arc<Sessions>>) -> Result<Vec<Draft>, String> {
+    sessions.touch()?;
+    drafts.list()
+}
+
+#[tauri::command]
+fn save_draft(draft: Draft, drafts: State<DraftStore>, sessions: State<Arc<Sessions>>) -> Result<Draft, String> {
+    sessions.touch()?;
+    drafts.save(draft)
+}
+
+#[tauri::command]
+fn delete_draft(id: String, drafts: State<DraftStore>, sessions: State<Arc<Sessions>>) -> Result<(), String> {
+    sessions.touch()?;
+    drafts.remove(&id)
+}
+
+#[tauri::command]
+fn get_watch_folders(state: State<WatchState>, sessions: State<Arc<Sessions>>) -> Result<Vec<WatchFolder>, String> {
+    sessions.touch()?;
+    watcher::load_folders(&state.config_path)
+}
+
+/// Restarts watching with the new folders and keeps them only if that worked.
+#[tauri::command]
+fn set_watch_folders(mut folders: Vec<WatchFolder>, state: State<WatchState>, sessions: State<Arc<Sessions>>, audit_log: State<Arc<AuditLog>>) -> Result<(), String> {
+    let session = sessions.authorize(Permission::ManageWatchFolders)?;
+    let mut entry = AuditEntry::new(&session.username, AuditAction::SetWatchFolders, "watch folders");
+    let saved = watcher::load_folders(&state.config_path).unwrap_or_default();
+    entry.before = serde_json::to_value(&saved).ok();
+    watcher::assign_folders(&mut folders, &saved, &session.username);
+    entry.after = serde_json::to_value(&folders).ok();
+    let result = state
+        .watcher
+        .lock()
+        .unwrap()
+        .start(folders.clone())
+        .and_then(|_| watcher::save_folders(&state.config_path, &folders));
+    audit_log.record(entry, result, "Watch folders saved")
+}
+
+#[tauri::command]
+fn list_audit(filter: AuditFilter, sessions: State<Arc<Sessions>>, audit_log: State<Arc<AuditLog>>) -> Result<Vec<AuditEntry>, String> {
+    sessions.authorize(Permission::ViewAudit)?;
+    audit_log.read(&filter)
+}
+
+/// Writes the matching entries where the operator picks; `None` if they cancel.
+#[tauri::command]
+async fn export_audit(
+    app: AppHandle,
+    filter: AuditFilter,
+    format: ExportFormat,
+    sessions: State<'_, Arc<Sessions>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+) -> Result<Option<String>, String> {
+    sessions.authorize(Permission::ViewAudit)?;
+    let bytes = audit::export(&audit_log.read(&filter)?, format)?;
+    save_as(&app, "audit", format.extension(), &bytes)
+}
+
+/// Asks where to write `bytes`, suggesting `name.extension`; `None` if the operator cancels.
+fn save_as(app: &AppHandle, name: &str, extension: &str, bytes: &[u8]) -> Result<Option<String>, String> {
+    let picked = app
+        .dialog()
+        .file()
+        .add_filter(extension.to_uppercase(), &[extension])
+        .set_file_name(format!("{}.{}", name, extension))
+        .blocking_save_file();
+    let Some(file_path) = picked else {
+        return Ok(None);
+    };
+    let path = file_path.into_path().map_err(|e| e.to_string())?;
+    std::fs::write(&path, bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Some(path.display().to_string()))
+}
+
+/// Exports the rows a file would send, in `columns` order, where the operator picks.
+#[tauri::command]
+async fn export_upload(
+    app: AppHandle,
+    payload: VendorInfo,
+    columns: Vec<String>,
+    format: export::ExportFormat,
+    store: State<'_, VendorStore>,
+    sessions: State<'_, Arc<Sessions>>,
+) -> Result<Option<String>, String> {
+    sessions.touch()?;
+    let name = payload.vendor_name.clone();
+    let store = store.inner().clone();
+    let bytes = blocking(move || upload::export_table(payload, &store)?.select(&columns)?.write(format)).await?;
+    save_as(&app, &name, format.extension(), &bytes)
+}
+
+/// Exports every item matching `query`, not just the page shown.
+#[tauri::command]
+async fn export_catalog(
+    app: AppHandle,
+    query: CatalogQuery,
+    columns: Vec<String>,
+    format: export::ExportFormat,
+    store: State<'_, VendorStore>,
+    catalog: State<'_, Arc<CatalogCache>>,
+    sessions: State<'_, Arc<Sessions>>,
+) -> Result<Option<String>, String> {
+    sessions.touch()?;
+    let (store, catalog) = (store.inner().clone(), catalog.inner().clone());
+    let bytes = blocking(move || catalog.get(&store)?.table(&query).select(&columns)?.write(format)).await?;
+    save_as(&app, "catalog", format.extension(), &bytes)
+}
+
+/// Every saved vendor's profile, to pick which to export.
+#[tauri::command]
+fn list_profiles(store: State<VendorStore>, sessions: State<Arc<Sessions>>) -> Result<Vec<Profile>, String> {
+    sessions.touch()?;
+    let mut profiles = Vec::new();
+    for name in store.list()? {
+        if let Some(record) = store.load(&name)? {
+            profiles.push(Profile::of(&record));
+        }
+    }
+    Ok(profiles)
+}
+
+/// Writes the profiles of `vendors`, or of all of them, where the operator picks.
+#[tauri::command]
+async fn export_profiles(
+    app: AppHandle,
+    vendors: Vec<String>,
+    format: ProfileFormat,
+    store: State<'_, VendorStore>,
+    sessions: State<'_, Arc<Sessions>>,
+) -> Result<Option<String>, String> {
+    sessions.touch()?;
+    let bytes = ProfileDocument::export(&store, &vendors)?.write(format)?;
+    save_as(&app, "mapping-profiles", format.extension(), &bytes)
+}
+
+/// Reads a profile document the operator picks and checks it against the
+/// saved vendors; `None` if they cancel. The document comes back with the
+/// preview, to send to `import_profiles` once conflicts are resolved.
+#[tauri::command]
+async fn open_profiles(
+    app: AppHandle,
+    store: State<'_, VendorStore>,
+    sessions: State<'_, Arc<Sessions>>,
+) -> Result<Option<(ProfileDocument, ImportPreview)>, String> {
+    sessions.authorize(Permission::EditMapping)?;
+    let picked = app
+        .dialog()
+        .file()
+        .add_filter("Mapping profiles", &["json", "toml"])
+        .blocking_pick_file();
+    let Some(file_path) = picked else {
+        return Ok(None);
+    };
+    let path = file_path.into_path().map_err(|e| e.to_string())?;
+    let bytes = std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let document = ProfileDocument::parse(&bytes, ProfileFormat::of_path(&path))?;
+    let preview = profile::preview(&document, &store)?;
+    Ok(Some((document, preview)))
+}
+
+#[tauri::command]
+fn import_profiles(
+    document: ProfileDocument,
+    resolutions: BTreeMap<String, Resolution>,
+    store: State<VendorStore>,
+    sessions: State<Arc<Sessions>>,
+    audit_log: State<Arc<AuditLog>>,
+) -> Result<ImportSummary, String> {
+    let session = sessions.authorize(Permission::EditMapping)?;
+    if profile::creates_vendors(&document, &resolutions, &store)? {
+        sessions.authorize(Permission::CreateVendor)?;
+    }
+    profile::import_audited(&document, &resolutions, &store, &audit_log, &session.username)
+}
+
+#[tauri::command]
+fn profile_versions(vendor_name: String, store: State<VendorStore>, sessions: State<Arc<Sessions>>) -> Result<Vec<VersionUse>, String> {
+    sessions.touch()?;
+    profile::versions(&store, &vendor_name)
+}
+
+#[tauri::command]
+fn profile_diff(vendor_name: String, from: u32, to: u32, store: State<VendorStore>, sessions: State<Arc<Sessions>>) -> Result<Vec<FieldChange>, String> {
+    sessions.touch()?;
+    profile::diff(&store, &vendor_name, from, to)
+}
+
+/// Makes an earlier profile version the vendor's profile again, as a new version.
+#[tauri::command]
+fn rollback_profile(
+    vendor_name: String,
+    version: u32,
+    note: String,
+    store: State<VendorStore>,
+    sessions: State<Arc<Sessions>>,
+    audit_log: State<Arc<AuditLog>>,
+) -> Result<u32, String> {
+    let session = sessions.authorize(Permission::EditMapping)?;
+    profile::rollback_audited(&store, &audit_log, &session.username, &vendor_name, version, &note)
+}
+
+#[tauri::command]
+fn get_rates(store: State<VendorStore>, sessions: State<Arc<Sessions>>) -> Result<RateTable, String> {
+    sessions.touch()?;
+    store.rates()
+}
+
+/// Saves the rate table and returns it as stored, codes upper-cased and stamped.
+#[tauri::command]
+fn save_rates(mut rates: RateTable, store: State<VendorStore>, sessions: State<Arc<Sessions>>, audit_log: State<Arc<AuditLog>>) -> Result<RateTable, String> {
+    let session = sessions.authorize(Permission::EditMapping)?;
+    let mut entry = AuditEntry::new(&session.username, AuditAction::SetRates, "currency rates");
+    entry.before = store.rates().ok().and_then(|rates| serde_json::to_value(rates).ok());
+    entry.after = serde_json::to_value(&rates).ok();
+    let result = store.save_rates(&mut rates);
+    audit_log.record(entry, result, "Rates saved").map(|_| rates)
+}
+
+/// Writes a message from the webview to the app's log.
+#[tauri::command]
+fn log_frontend(level: String, message: String) {
+    match level.as_str() {
+        "error" => tracing::error!(target: "frontend", "{}", message),
+        "warn" => tracing::warn!(target: "frontend", "{}", message),
+        "debug" => tracing::debug!(target: "frontend", "{}", message),
+        _ => tracing::info!(target: "frontend", "{}", message),
+    }
+}
+
+/// Raises a native OS notification, for jobs that finish while the window is in the background.
+#[tauri::command]
+fn notify_desktop(app: AppHandle, title: String, body: String) -> Result<(), String> {
+    app.notification().builder().title(title).body(body).show().map_err(|e| e.to_string())
+}
+
+/// Zips the logs, redacted config and version info where the user picks; `None` if they cancel.
+#[tauri::command]
+async fn collect_diagnostics(app: AppHandle, sessions: State<'_, Arc<Sessions>>) -> Result<Option<String>, String> {
+    sessions.touch()?;
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let package = app.package_info();
+    let version = serde_json::json!({
+        "name": package.name,
+        "version": package.version.to_string(),
+        "tauri": tauri::VERSION,
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+    });
+    let bundle = diagnostics::collect(&data_dir, &version)?;
+    let picked = app
+        .dialog()
+        .file()
+        .add_filter("Zip", &["zip"])
+        .set_file_name("fs_ui-diagnostics.zip")
+        .blocking_save_file();
+    let Some(file_path) = picked else {
+        return Ok(None);
+    };
+    let path = file_path.into_path().map_err(|e| e.to_string())?;
+    std::fs::write(&path, bundle).map_err(|e| format!("{}: {}", path.display(), e))?;
+    tracing::info!(path = %path.display(), "saved diagnostics");
+    Ok(Some(path.display().to_string()))
+}
+
+/// Watched files that were quarantined, or not sent before an upload was attempted, never
+/// reach the audit log, so they are counted here.
+#[tauri::command]
+fn dashboard_metrics(
+    periods: Periods,
+    store: State<VendorStore>,
+    state: State<WatchState>,
+    sessions: State<Arc<Sessions>>,
+    audit_log: State<Arc<AuditLog>>,
+) -> Result<Metrics, String> {
+    sessions.touch()?;
+    let mut records = Vec::new();
+    for name in store.list()? {
+        records.extend(store.load(&name)?);
+    }
+    let mut metrics = Metrics::compute(&records, &audit_log.read(&AuditFilter::default())?, periods);
+    for result in state.watcher.lock().unwrap().results() {
+        if matches!(result.status, IngestStatus::Quarantined | IngestStatus::Failed) && !result.audited {
+            metrics.add_failure(result.timestamp, periods);
+        }
+    }
+    Ok(metrics)
+}
+
+#[tauri::command]
+fn list_ingest_results(state: State<WatchState>, sessions: State<Arc<Sessions>>) -> Result<Vec<IngestResult>, String> {
+    sessions.touch()?;
+    Ok(state.watcher.lock().unwrap().results())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet, setup_new_user])
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            let store = VendorStore::new(data_dir.join("vendors"));
+            let client_config_path = data_dir.join(client::CLIENT_CONFIG);
+            let (client_config, config_error) = match ClientConfig::load(&client_config_path) {
+                Ok(config) => (config, None),
+                Err(e) => (ClientConfig::default(), Some(e)),
+            };
+            if let Err(e) = logging::init(&data_dir.join(logging::LOG_DIR), &client_config.log) {
+                // Nowhere else to report it.
+                eprintln!("Failed to start logging: {}", e);
+            }
+            tracing::info!(version = %app.package_info().version, "starting");
+            if let Some(e) = config_error {
+                tracing::error!(path = %client_config_path.display(), error = %e, "failed to read the client config");
+            }
+
+            let sessions = Arc::new(Sessions::new(client_config.idle_timeout()));
+            let audit_log = Arc::new(AuditLog::new(data_dir.join(audit::AUDIT_LOG)));
+
+            let handle = app.handle().clone();
+            let mut folder_watcher = FolderWatcher::new(store.clone(), client_config.clone(), audit_log.clone(), move |result| {
+                let _ = handle.emit("watch://result", result);
+                // The watch page already lists the result when the window is in front.
+                let focused = handle.webview_windows().values().any(|window| window.is_focused().unwrap_or(false));
+                if !focused {
+                    let _ = handle
+                        .notification()
+                        .builder()
+                        .title(format!("{} {}", result.file, result.status))
+                        .body(format!("{}: {}", result.vendor_name, result.message))
+                        .show();
+                }
+            });
+            let config_path = data_dir.join("watch.json");
+            match watcher::load_folders(&config_path) {
+                Ok(folders) => {
+                    if let Err(e) = folder_watcher.start(folders) {
+                        tracing::error!(error = %e, "failed to watch folders");
+                    }
+                }
+                Err(e) => tracing::error!(path = %config_path.display(), error = %e, "failed to read watch folders"),
+            }
+
+            app.manage(store);
+            app.manage(Arc::new(CatalogCache::default()));
+            app.manage(DraftStore::new(data_dir.join("drafts")));
+            app.manage(client_config);
+            app.manage(sessions);
+            app.manage(audit_log);
+            app.manage(WatchState {
+                config_path,
+                watcher: Mutex::new(folder_watcher),
+            });
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            login,
+            logout,
+            current_operator,
+            touch_session,
+            setup_new_user,
+            list_vendors,
+            query_catalog,
+            catalog_item,
+            inspect_file,
+            open_file_dialog,
+            inspect_path,
+            sniff_dialect,
+            inspect_upload,
+            preview_upload_diff,
+            list_drafts,
+            save_draft,
+            delete_draft,
+            pick_folder,
+            get_watch_folders,
+            set_watch_folders,
+            list_audit,
+            export_audit,
+            export_upload,
+            export_catalog,
+            list_profiles,
+            export_profiles,
+            open_profiles,
+            import_profiles,
+            profile_versions,
+            profile_diff,
+            rollback_profile,
+            get_rates,
+            save_rates,
+            log_frontend,
+            collect_diagnostics,
+            notify_desktop,
+            list_ingest_results,
+            dashboard_metrics
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
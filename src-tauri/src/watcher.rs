@@ -0,0 +1,473 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use glob::Pattern;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::{self, Runtime};
+
+use fs_ui_core::audit::AuditLog;
+use fs_ui_core::client::ClientConfig;
+use fs_ui_core::dataset;
+use fs_ui_core::session::{self, Permission};
+use fs_ui_core::source::FileSource;
+use fs_ui_core::store::VendorStore;
+use fs_ui_core::upload::{self, VendorInfo};
+use fs_ui_core::validation;
+
+const QUARANTINE_DIR: &str = "quarantine";
+const PROCESSED_DIR: &str = "processed";
+
+/// How long a dropped file must stop growing before it is picked up.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Results kept in memory for the UI; older ones are dropped.
+const MAX_RESULTS: usize = 200;
+
+/// Shortest wait before a file that could not be sent is tried again; the
+/// client has retried the request itself by then.
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchFolder {
+    pub path: String,
+    pub vendor_name: String,
+    /// Glob matched against file names, e.g. `stock_*.csv`.
+    pub pattern: String,
+    /// Operator who set the folder up, whom the audit log names for its uploads.
+    #[serde(default)]
+    pub configured_by: String,
+}
+
+impl WatchFolder {
+    fn same_folder(&self, other: &WatchFolder) -> bool {
+        self.path == other.path && self.vendor_name == other.vendor_name && self.pattern == other.pattern
+    }
+}
+
+/// Names `operator` as the one who set up each of `folders` that is new or
+/// changed since `saved`; the others keep who set them up.
+pub fn assign_folders(folders: &mut [WatchFolder], saved: &[WatchFolder], operator: &str) {
+    for folder in folders {
+        folder.configured_by = match saved.iter().find(|saved| saved.same_folder(folder)) {
+            Some(saved) => saved.configured_by.clone(),
+            None => operator.to_string(),
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum IngestStatus {
+    Submitted,
+    Quarantined,
+    /// The file was fine but could not be sent; it stays in the folder and is
+    /// tried again later.
+    Failed,
+}
+
+impl fmt::Display for IngestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IngestStatus::Submitted => "uploaded",
+            IngestStatus::Quarantined => "quarantined",
+            IngestStatus::Failed => "not sent",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestResult {
+    pub file: String,
+    pub vendor_name: String,
+    pub status: IngestStatus,
+    pub message: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The failed upload is in the audit log already.
+    #[serde(default)]
+    pub audited: bool,
+}
+
+pub fn load_folders(config_path: &Path) -> Result<Vec<WatchFolder>, String> {
+    if !config_path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = fs::read_to_string(config_path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+pub fn save_folders(config_path: &Path, folders: &[WatchFolder]) -> Result<(), String> {
+    if let Some(dir) = config_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(folders).map_err(|e| e.to_string())?;
+    fs::write(config_path, json).map_err(|e| e.to_string())
+}
+
+type ResultCallback = Arc<dyn Fn(&IngestResult) + Send + Sync>;
+
+/// Watches the configured vendor drop folders and ingests new files with the
+/// vendor's saved mapping.
+pub struct FolderWatcher {
+    store: VendorStore,
+    client_config: ClientConfig,
+    audit_log: Arc<AuditLog>,
+    on_result: ResultCallback,
+    results: Arc<Mutex<Vec<IngestResult>>>,
+    taken: Arc<Mutex<Taken>>,
+    watcher: Option<RecommendedWatcher>,
+}
+
+impl FolderWatcher {
+    pub fn new(
+        store: VendorStore,
+        client_config: ClientConfig,
+        audit_log: Arc<AuditLog>,
+        on_result: impl Fn(&IngestResult) + Send + Sync + 'static,
+    ) -> Self {
+        FolderWatcher {
+            store,
+            client_config,
+            audit_log,
+            on_result: Arc::new(on_result),
+            results: Arc::new(Mutex::new(Vec::new())),
+            taken: Arc::new(Mutex::new(Taken::default())),
+            watcher: None,
+        }
+    }
+
+    pub fn results(&self) -> Vec<IngestResult> {
+        self.results.lock().unwrap().clone()
+    }
+
+    /// Ingests files already waiting in `folders` and watches them for more.
+    /// The previous folders stay watched until the new ones are.
+    pub fn start(&mut self, folders: Vec<WatchFolder>) -> Result<(), String> {
+        let mut patterns = Vec::new();
+        for folder in &folders {
+            let pattern = Pattern::new(&folder.pattern).map_err(|e| format!("{}: {}", folder.pattern, e))?;
+            patterns.push(pattern);
+        }
+
+        let (tx, rx) = channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+        for folder in &folders {
+            watcher
+                .watch(Path::new(&folder.path), RecursiveMode::NonRecursive)
+                .map_err(|e| format!("{}: {}", folder.path, e))?;
+        }
+
+        let ingest = Ingest {
+            folders: folders.into_iter().zip(patterns).collect(),
+            store: self.store.clone(),
+            client_config: self.client_config.clone(),
+            audit_log: self.audit_log.clone(),
+            runtime: runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| e.to_string())?,
+            on_result: self.on_result.clone(),
+            results: self.results.clone(),
+            taken: self.taken.clone(),
+        };
+        // Dropping the previous watcher ends its ingest thread once the file
+        // it is on is done; that file stays taken, so this one skips it.
+        self.watcher = Some(watcher);
+        thread::spawn(move || ingest.run(rx));
+        Ok(())
+    }
+}
+
+/// A file as it was when it was ingested; the same name with other contents
+/// or another modification time is a new file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Seen {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Seen {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = path.metadata().ok()?;
+        Some(Seen {
+            path: path.to_path_buf(),
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Files the ingest threads have picked up, kept across restarts.
+#[derive(Default)]
+struct Taken {
+    seen: HashSet<Seen>,
+    /// Files that could not be sent: the failures so far and when to try again.
+    retries: HashMap<Seen, (u32, Instant)>,
+}
+
+impl Taken {
+    fn waiting(&self, file: &Seen) -> bool {
+        self.seen.contains(file) || self.retries.get(file).is_some_and(|(_, due)| *due > Instant::now())
+    }
+}
+
+enum Ingested {
+    Submitted(String),
+    Invalid(String),
+    /// Not sent; `audited` once the upload itself failed and the audit log has it.
+    Failed { message: String, audited: bool },
+}
+
+struct Ingest {
+    folders: Vec<(WatchFolder, Pattern)>,
+    store: VendorStore,
+    client_config: ClientConfig,
+    audit_log: Arc<AuditLog>,
+    /// Drives uploads on the ingest thread, one file at a time.
+    runtime: Runtime,
+    on_result: ResultCallback,
+    results: Arc<Mutex<Vec<IngestResult>>>,
+    taken: Arc<Mutex<Taken>>,
+}
+
+impl Ingest {
+    /// Runs until the watcher is dropped, which closes the channel. Files
+    /// that could not be sent are tried again as their retries fall due.
+    fn run(self, rx: Receiver<notify::Result<Event>>) {
+        for (folder, _) in &self.folders {
+            if let Ok(entries) = fs::read_dir(&folder.path) {
+                for entry in entries.flatten() {
+                    self.handle(&entry.path());
+                }
+            }
+        }
+        loop {
+            match rx.recv_timeout(self.next_retry()) {
+                Ok(Ok(event)) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    for path in event.paths {
+                        self.handle(&path);
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => {
+                    for path in self.due() {
+                        self.handle(&path);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    fn folder(&self, path: &Path) -> Option<&WatchFolder> {
+        self.folders
+            .iter()
+            .find(|(folder, pattern)| {
+                path.parent() == Some(Path::new(&folder.path))
+                    && path
+                        .file_name()
+                        .is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+            })
+            .map(|(folder, _)| folder)
+    }
+
+    /// How long until a file in these folders is due for another try.
+    fn next_retry(&self) -> Duration {
+        let now = Instant::now();
+        let taken = self.taken.lock().unwrap();
+        let waits = taken.retries.iter().filter(|(file, _)| !taken.seen.contains(file) && self.folder(&file.path).is_some());
+        waits.map(|(_, (_, due))| due.saturating_duration_since(now)).fold(MIN_RETRY_DELAY, Duration::min)
+    }
+
+    /// Files in these folders due for another try. Those changed or gone since
+    /// are dropped; a change is picked up as a new file.
+    fn due(&self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        let mut taken = self.taken.lock().unwrap();
+        taken.retries.retain(|file, _| Seen::of(&file.path).as_ref() == Some(file));
+        taken
+            .retries
+            .iter()
+            .filter(|(file, (_, due))| *due <= now && !taken.seen.contains(file) && self.folder(&file.path).is_some())
+            .map(|(file, _)| file.path.clone())
+            .collect()
+    }
+
+    fn handle(&self, path: &Path) {
+        if !path.is_file() || Seen::of(path).is_some_and(|file| self.taken.lock().unwrap().waiting(&file)) {
+            return;
+        }
+        let Some(folder) = self.folder(path) else {
+            return;
+        };
+        wait_until_settled(path);
+        let Some(file) = Seen::of(path) else { return };
+        {
+            let mut taken = self.taken.lock().unwrap();
+            if taken.waiting(&file) || !taken.seen.insert(file.clone()) {
+                return;
+            }
+        }
+
+        let ingested = self.ingest(path, folder);
+        let mut taken = self.taken.lock().unwrap();
+        let result = match ingested {
+            Ingested::Submitted(message) => {
+                taken.retries.remove(&file);
+                self.finish(path, folder, Some(PROCESSED_DIR), IngestStatus::Submitted, message)
+            }
+            Ingested::Invalid(message) => {
+                taken.retries.remove(&file);
+                self.finish(path, folder, Some(QUARANTINE_DIR), IngestStatus::Quarantined, message)
+            }
+            Ingested::Failed { message, audited } => {
+                // Left in the folder and tried again later, or as soon as it changes.
+                taken.seen.remove(&file);
+                let failures = taken.retries.get(&file).map_or(0, |(failures, _)| *failures) + 1;
+                let delay = self.client_config.policy.backoff(failures).max(MIN_RETRY_DELAY);
+                taken.retries.insert(file, (failures, Instant::now() + delay));
+                let message = format!("{} (trying again in {}s)", message, delay.as_secs());
+                IngestResult { audited, ..self.finish(path, folder, None, IngestStatus::Failed, message) }
+            }
+        };
+        drop(taken);
+        (self.on_result)(&result);
+        let mut results = self.results.lock().unwrap();
+        results.push(result);
+        if results.len() > MAX_RESULTS {
+            results.remove(0);
+        }
+    }
+
+    fn ingest(&self, path: &Path, folder: &WatchFolder) -> Ingested {
+        let record = match self.store.load(&folder.vendor_name) {
+            Ok(Some(record)) => record,
+            Ok(None) => return Ingested::Invalid(format!("Vendor \"{}\" has no saved mapping", folder.vendor_name)),
+            Err(e) => return Ingested::Failed { message: e, audited: false },
+        };
+        let mapping = dataset::parse_mappings(&record.file_mappings);
+        // Read as the vendor's files are written; one that does not decode is quarantined.
+        let source = match record.dialect.normalize(FileSource::Path(path.to_path_buf())) {
+            Ok(source) => source,
+            Err(e) => return Ingested::Invalid(e),
+        };
+        let report = match source.reader() {
+            Ok(reader) => validation::validate(reader, &mapping, record.dialect.decimal),
+            Err(e) => return Ingested::Failed { message: e, audited: false },
+        };
+        if !report.is_valid() {
+            return Ingested::Invalid(report.errors.join("\n"));
+        }
+        let payload = VendorInfo {
+            vendor_name: folder.vendor_name.clone(),
+            file_mappings: record.file_mappings,
+            file_path: Some(path.display().to_string()),
+            ..VendorInfo::default()
+        };
+        // Files arrive unattended, so they upload as the watch account rather
+        // than whoever happens to be signed in.
+        let client = match self.client_config.client() {
+            Ok(client) => client,
+            Err(e) => return Ingested::Failed { message: e, audited: false },
+        };
+        let client = match &self.client_config.watch_account {
+            Some(account) => {
+                let session = match self.runtime.block_on(client.login(&account.username, &account.password)) {
+                    Ok(session) => session,
+                    Err(e) => {
+                        let message = format!("The watch account could not sign in: {}", e);
+                        return Ingested::Failed { message, audited: false };
+                    }
+                };
+                if let Err(e) = session::check(&session, Permission::Upload) {
+                    return Ingested::Failed { message: e, audited: false };
+                }
+                client.with_session_token(session.token)
+            }
+            None => client,
+        };
+        let actor = if folder.configured_by.is_empty() { "watch folder" } else { folder.configured_by.as_str() };
+        let author = self.client_config.watch_account.as_ref().map_or(actor, |account| account.username.as_str());
+        let result = self.runtime.block_on(upload::submit_audited(payload, &self.store, &client, &self.audit_log, actor, author));
+        if self.client_config.watch_account.is_some() {
+            if let Err(e) = self.runtime.block_on(client.logout()) {
+                tracing::warn!(error = %e, "watch account sign-out failed");
+            }
+        }
+        match result {
+            Ok(message) => Ingested::Submitted(message),
+            Err(e) => Ingested::Failed { message: e, audited: true },
+        }
+    }
+
+    /// Moves the file out of the watched folder so it is not ingested twice.
+    fn finish(&self, path: &Path, folder: &WatchFolder, dir: Option<&str>, status: IngestStatus, message: String) -> IngestResult {
+        let file = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let message = match dir {
+            Some(dir) => {
+                let target_dir = Path::new(&folder.path).join(dir);
+                let name = free_name(&target_dir, &file);
+                let moved = fs::create_dir_all(&target_dir).and_then(|_| fs::rename(path, target_dir.join(&name)));
+                if status == IngestStatus::Quarantined {
+                    let _ = fs::write(target_dir.join(format!("{}.errors.txt", name)), &message);
+                }
+                match moved {
+                    Ok(()) => message,
+                    Err(e) => format!("{} (could not move file to {}: {})", message, dir, e),
+                }
+            }
+            None => message,
+        };
+        IngestResult {
+            file,
+            vendor_name: folder.vendor_name.clone(),
+            status,
+            message,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            audited: false,
+        }
+    }
+}
+
+/// `file`, or `name-1.ext`, `name-2.ext`... when an earlier file of that name
+/// is in `dir` already.
+fn free_name(dir: &Path, file: &str) -> String {
+    let (stem, extension) = match file.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (file, String::new()),
+    };
+    let mut name = file.to_string();
+    let mut counter = 0;
+    while dir.join(&name).exists() {
+        counter += 1;
+        name = format!("{}-{}{}", stem, counter, extension);
+    }
+    name
+}
+
+/// Waits for a file that is still being copied into the folder to stop growing.
+fn wait_until_settled(path: &Path) {
+    let size = |path: &Path| path.metadata().map(|m| m.len()).ok();
+    let mut last = size(path);
+    loop {
+        thread::sleep(SETTLE_TIME);
+        let current = size(path);
+        if current == last {
+            return;
+        }
+        last = current;
+    }
+}
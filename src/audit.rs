@@ -0,0 +1,176 @@
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+use web_sys::MouseEvent;
+
+use crate::watch::format_timestamp;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+const ACTIONS: [&str; 7] = ["login", "logout", "create_vendor", "change_mapping", "upload", "set_watch_folders", "set_rates"];
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    pub actor: String,
+    pub action: String,
+    pub target: String,
+    #[serde(default)]
+    pub before: Option<Value>,
+    #[serde(default)]
+    pub after: Option<Value>,
+    #[serde(default)]
+    pub file_sha256: Option<String>,
+    pub ok: bool,
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+struct AuditFilter {
+    actor: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    since: Option<u64>,
+    until: Option<u64>,
+}
+
+/// Seconds since the epoch for a `yyyy-mm-dd` date input, in local time.
+fn parse_date(date: &str, time: &str) -> Option<u64> {
+    if date.is_empty() {
+        return None;
+    }
+    let ms = js_sys::Date::parse(&format!("{}T{}", date, time));
+    (!ms.is_nan()).then(|| (ms / 1000.0) as u64)
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.trim().is_empty()).then(|| value.trim().to_string())
+}
+
+#[component]
+pub fn AuditTrail() -> impl IntoView {
+    let actor = RwSignal::new(String::new());
+    let action = RwSignal::new(String::new());
+    let target = RwSignal::new(String::new());
+    let since = RwSignal::new(String::new());
+    let until = RwSignal::new(String::new());
+    let entries = RwSignal::new(Vec::<AuditEntry>::new());
+    let status = RwSignal::new(None::<String>);
+
+    let filter = move || AuditFilter {
+        actor: non_empty(actor.get_untracked()),
+        action: non_empty(action.get_untracked()),
+        target: non_empty(target.get_untracked()),
+        since: parse_date(&since.get_untracked(), "00:00:00"),
+        until: parse_date(&until.get_untracked(), "23:59:59"),
+    };
+
+    let search = move || {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "filter": filter() })).unwrap();
+            match try_invoke("list_audit", args).await {
+                Ok(result) => {
+                    entries.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+                    status.set(None);
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+    search();
+
+    let export = move |format: &'static str| {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "filter": filter(),
+                "format": format,
+            }))
+            .unwrap();
+            match try_invoke("export_audit", args).await {
+                Ok(result) => {
+                    if let Ok(Some(path)) = serde_wasm_bindgen::from_value::<Option<String>>(result) {
+                        status.set(Some(format!("Exported to {}", path)));
+                    }
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+
+    let text_filter = move |placeholder: &'static str, value: RwSignal<String>| view! {
+        <input
+            type="text"
+            class="text-input"
+            placeholder=placeholder
+            prop:value=move || value.get()
+            on:input=move |ev| value.set(event_target_value(&ev))
+        />
+    };
+
+    view! {
+        <div class="upload-container audit-container">
+            <h1>"Audit Log"</h1>
+            <div class="audit-filters">
+                {text_filter("Actor", actor)}
+                <select class="vendor-select" on:change=move |ev| action.set(event_target_value(&ev))>
+                    <option value="">"All actions"</option>
+                    {ACTIONS.map(|a| view! { <option value=a>{a}</option> }).collect_view()}
+                </select>
+                {text_filter("Vendor", target)}
+                <input type="date" prop:value=move || since.get() on:input=move |ev| since.set(event_target_value(&ev)) />
+                <input type="date" prop:value=move || until.get() on:input=move |ev| until.set(event_target_value(&ev)) />
+            </div>
+            <div class="audit-actions">
+                <button class="preview-button" on:click=move |_: MouseEvent| search()>"Search"</button>
+                <button class="preview-button" on:click=move |_: MouseEvent| export("csv")>"Export CSV"</button>
+                <button class="preview-button" on:click=move |_: MouseEvent| export("json")>"Export JSON"</button>
+            </div>
+            {move || status.get().map(|status| view! { <p class="batch-summary">{status}</p> })}
+            <table class="diff-table audit-entries">
+                <tr>
+                    <th>"Time"</th>
+                    <th>"Actor"</th>
+                    <th>"Action"</th>
+                    <th>"Target"</th>
+                    <th>"Before"</th>
+                    <th>"After"</th>
+                    <th>"File SHA-256"</th>
+                    <th>"Result"</th>
+                </tr>
+                {move || entries
+                    .get()
+                    .into_iter()
+                    .rev()
+                    .map(|entry| {
+                        let json = |value: Option<Value>| value.map(|v| v.to_string()).unwrap_or_default();
+                        let hash = entry.file_sha256.unwrap_or_default();
+                        let (class, result) = if entry.ok {
+                            ("uploaded", "ok".to_string())
+                        } else {
+                            ("failed", entry.message.clone())
+                        };
+                        view! {
+                            <tr>
+                                <td>{format_timestamp(entry.timestamp)}</td>
+                                <td>{entry.actor}</td>
+                                <td>{entry.action}</td>
+                                <td>{entry.target}</td>
+                                <td class="audit-value">{json(entry.before)}</td>
+                                <td class="audit-value">{json(entry.after)}</td>
+                                <td title=hash.clone()>{hash.chars().take(12).collect::<String>()}</td>
+                                <td class=format!("job-status {}", class)>{result}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </table>
+        </div>
+    }
+}
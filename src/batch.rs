@@ -0,0 +1,267 @@
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use leptos::prelude::Callback;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::{FileList, HtmlInputElement, MouseEvent};
+use gloo_file::futures::read_as_bytes;
+use gloo_file::Blob as GlooBlob;
+
+use crate::components::*;
+use crate::log;
+use crate::login::{use_session, Permission};
+use crate::notify::{use_notifications, Notice};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub row_count: usize,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub locations: Vec<String>,
+    #[serde(default)]
+    pub currencies: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FileReport {
+    pub headers: Vec<String>,
+    pub file_mappings: String,
+    pub validation: ValidationReport,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Uploaded(String),
+    Failed(String),
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchFile {
+    pub id: usize,
+    pub name: String,
+    pub bytes: Vec<u8>,
+    pub report: Option<FileReport>,
+    pub status: JobStatus,
+    /// Bumped on every inspection, so a late result for an earlier one is dropped.
+    pub inspection: usize,
+}
+
+impl BatchFile {
+    fn is_valid(&self) -> bool {
+        self.report.as_ref().is_some_and(|report| report.validation.errors.is_empty())
+    }
+}
+
+fn update_file(files: RwSignal<Vec<BatchFile>>, id: usize, f: impl FnOnce(&mut BatchFile)) {
+    files.update(|files| {
+        if let Some(file) = files.iter_mut().find(|file| file.id == id) {
+            f(file);
+        }
+    });
+}
+
+fn inspect(files: RwSignal<Vec<BatchFile>>, id: usize, vendor_name: String, bytes: Vec<u8>) {
+    let mut inspection = 0;
+    // A file skipped for an earlier report gets another chance with this one.
+    update_file(files, id, |file| {
+        file.inspection += 1;
+        inspection = file.inspection;
+        file.report = None;
+        if file.status == JobStatus::Skipped {
+            file.status = JobStatus::Pending;
+        }
+    });
+    spawn_local(async move {
+        let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+            "vendorName": vendor_name,
+            "fileBytes": bytes,
+        }))
+        .unwrap();
+        let report = match try_invoke("inspect_file", args).await {
+            Ok(result) => serde_wasm_bindgen::from_value(result).unwrap_or_default(),
+            Err(err) => FileReport {
+                validation: ValidationReport {
+                    errors: vec![err.as_string().unwrap_or_default()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        };
+        update_file(files, id, |file| {
+            if file.inspection == inspection {
+                file.report = Some(report);
+            }
+        });
+    });
+}
+
+#[component]
+pub fn BatchUpload() -> impl IntoView {
+    let (is_new_vendor, _) = signal(false);
+    let vendors = RwSignal::new(Vec::<String>::new());
+    let vendor_name = RwSignal::new(String::new());
+    let files = RwSignal::new(Vec::<BatchFile>::new());
+    let next_id = StoredValue::new(0usize);
+    let running = RwSignal::new(false);
+    let session = use_session();
+    let summary = RwSignal::new(None::<String>);
+    let notifications = use_notifications();
+
+    spawn_local(async move {
+        if let Ok(result) = try_invoke("list_vendors", JsValue::NULL).await {
+            vendors.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+        }
+    });
+
+    // A different vendor means a different saved mapping, so detect again.
+    Effect::new(move |_| {
+        let vendor = vendor_name.get();
+        for file in files.get_untracked() {
+            inspect(files, file.id, vendor.clone(), file.bytes);
+        }
+    });
+
+    let on_files_change = Callback::new(move |ev: JsValue| {
+        let ev: web_sys::Event = ev.into();
+        let input: HtmlInputElement = event_target(&ev);
+        let list: FileList = input.files().unwrap();
+        summary.set(None);
+        for index in 0..list.length() {
+            let Some(f) = list.get(index) else { continue };
+            if !f.name().to_lowercase().ends_with(".csv") {
+                continue;
+            }
+            let id = next_id.get_value();
+            next_id.set_value(id + 1);
+            let name = f.name();
+            let gloo_blob = GlooBlob::from(f);
+            spawn_local(async move {
+                match read_as_bytes(&gloo_blob).await {
+                    Ok(bytes) => {
+                        files.update(|files| {
+                            files.push(BatchFile {
+                                id,
+                                name,
+                                bytes: bytes.clone(),
+                                report: None,
+                                status: JobStatus::Pending,
+                                inspection: 0,
+                            })
+                        });
+                        inspect(files, id, vendor_name.get_untracked(), bytes);
+                    }
+                    Err(err) => log::error(format!("Reading {} failed: {}", name, err)),
+                }
+            });
+        }
+        input.set_value("");
+    });
+
+    let on_remove = Callback::new(move |id: usize| {
+        files.update(|files| files.retain(|file| file.id != id));
+    });
+
+    let on_upload_all = Callback::new(move |_: MouseEvent| {
+        if running.get_untracked() {
+            return;
+        }
+        running.set(true);
+        summary.set(None);
+        spawn_local(async move {
+            let vendor = vendor_name.get_untracked();
+            for file in files.get_untracked() {
+                if !matches!(file.status, JobStatus::Pending | JobStatus::Failed(_)) {
+                    continue;
+                }
+                if !file.is_valid() {
+                    update_file(files, file.id, |file| file.status = JobStatus::Skipped);
+                    continue;
+                }
+                update_file(files, file.id, |file| file.status = JobStatus::Running);
+                let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                    "payload": {
+                        "vendor_name": vendor,
+                        "password": "",
+                        "file_mappings": file.report.map(|report| report.file_mappings).unwrap_or_default(),
+                        "file_bytes": file.bytes,
+                    }
+                }))
+                .unwrap();
+                let status = match try_invoke("setup_new_user", args).await {
+                    Ok(result) => JobStatus::Uploaded(result.as_string().unwrap_or_default()),
+                    Err(err) => JobStatus::Failed(err.as_string().unwrap_or_default()),
+                };
+                update_file(files, file.id, |file| file.status = status);
+            }
+
+            let files = files.get_untracked();
+            let count = |f: fn(&JobStatus) -> bool| files.iter().filter(|file| f(&file.status)).count();
+            let text = format!(
+                "{} uploaded, {} failed, {} skipped",
+                count(|status| matches!(status, JobStatus::Uploaded(_))),
+                count(|status| matches!(status, JobStatus::Failed(_))),
+                count(|status| matches!(status, JobStatus::Skipped)),
+            );
+            let failures: Vec<String> = files
+                .iter()
+                .filter_map(|file| match &file.status {
+                    JobStatus::Failed(message) => Some(format!("{}: {}", file.name, message)),
+                    _ => None,
+                })
+                .collect();
+            let notice = if failures.is_empty() {
+                Notice::success(format!("Batch for {}: {}", vendor, text))
+            } else {
+                Notice::warning(format!("Batch for {}: {}", vendor, text)).details(failures.join("\n"))
+            };
+            notifications.notify_in_background(notice);
+            summary.set(Some(text));
+            running.set(false);
+        });
+    });
+
+    view! {
+        <div class="upload-container batch-container">
+            <h1>"Batch Upload"</h1>
+            <VendorSelect
+                is_new_vendor=is_new_vendor
+                vendors=vendors
+                vendor_name=vendor_name
+                on_change=Callback::new(move |name| vendor_name.set(name))
+            />
+            <div class="batch-pickers">
+                <FileUploadLabel
+                    id="batch-files"
+                    text="Choose Files"
+                    multiple=true
+                    on_file_change=on_files_change
+                />
+                <FileUploadLabel
+                    id="batch-folder"
+                    text="Choose Folder"
+                    directory=true
+                    on_file_change=on_files_change
+                />
+            </div>
+            <BatchFileList files=files on_remove=on_remove />
+            <button
+                class="submit-button"
+                disabled=move || running.get() || files.get().is_empty() || vendor_name.get().is_empty() || !session.can(Permission::Upload)
+                on:click=move |ev: MouseEvent| on_upload_all.run(ev)
+            >
+                {move || if running.get() { "Uploading..." } else { "Upload All" }}
+            </button>
+            {move || summary.get().map(|summary| view! { <p class="batch-summary">{summary}</p> })}
+        </div>
+    }
+}
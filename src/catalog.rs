@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::MouseEvent;
+
+use crate::components::ExportPanel;
+use crate::wizard::{location_of, CURRENCY, LOCATION, TARGET_FIELDS};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+const PAGE_SIZE: usize = 50;
+
+const STOCK_LEVELS: [(&str, &str); 4] = [("any", "Any stock"), ("in_stock", "In stock"), ("low", "Low stock"), ("out_of_stock", "Out of stock")];
+
+/// Sortable columns, as the backend names them.
+const COLUMNS: [(&str, &str); 6] = [
+    ("vendor", "Vendor"),
+    ("key", "File Key"),
+    ("catalog", "Catalog"),
+    ("brand", "Brand"),
+    ("description", "Description"),
+    ("quantity", "Quantity"),
+];
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct CatalogQuery {
+    search: String,
+    vendor: Option<String>,
+    brand: Option<String>,
+    catalog: Option<String>,
+    stock: String,
+    sort: String,
+    descending: bool,
+    page: usize,
+    page_size: usize,
+}
+
+impl Default for CatalogQuery {
+    fn default() -> Self {
+        CatalogQuery {
+            search: String::new(),
+            vendor: None,
+            brand: None,
+            catalog: None,
+            stock: "any".to_string(),
+            sort: "vendor".to_string(),
+            descending: false,
+            page: 0,
+            page_size: PAGE_SIZE,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CatalogItem {
+    pub vendor_name: String,
+    pub key: String,
+    pub catalog: String,
+    pub brand: String,
+    pub description: String,
+    pub quantity: Option<f64>,
+    pub ecommerce: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+struct CatalogPage {
+    items: Vec<CatalogItem>,
+    total: usize,
+    page: usize,
+    page_size: usize,
+    vendors: Vec<String>,
+    brands: Vec<String>,
+    catalogs: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct ItemDetail {
+    item: CatalogItem,
+    fields: Vec<(String, String)>,
+    mapping: BTreeMap<String, String>,
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.trim().is_empty()).then(|| value.trim().to_string())
+}
+
+/// Any change but turning the page starts again from the first one.
+fn refine(query: RwSignal<CatalogQuery>, change: impl FnOnce(&mut CatalogQuery)) {
+    query.update(|query| {
+        change(query);
+        query.page = 0;
+    })
+}
+
+fn format_quantity(quantity: Option<f64>) -> String {
+    quantity.map(|q| q.to_string()).unwrap_or_default()
+}
+
+/// Browses what the vendors' last uploads hold. Searching, sorting and paging
+/// happen in the backend, so only the page shown crosses over.
+#[component]
+pub fn CatalogBrowser() -> impl IntoView {
+    let query = RwSignal::new(CatalogQuery::default());
+    let page = RwSignal::new(CatalogPage::default());
+    let detail = RwSignal::new(None::<ItemDetail>);
+    let status = RwSignal::new(None::<String>);
+
+    // A reply to anything but the latest query is dropped.
+    Effect::new(move |_| {
+        let current = query.get();
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "query": current })).unwrap();
+            let result = try_invoke("query_catalog", args).await;
+            if query.get_untracked() != current {
+                return;
+            }
+            match result {
+                Ok(result) => {
+                    page.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+                    status.set(None);
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    });
+
+    let show_detail = move |item: CatalogItem| {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "vendorName": item.vendor_name,
+                "key": item.key,
+            }))
+            .unwrap();
+            match try_invoke("catalog_item", args).await {
+                Ok(result) => detail.set(serde_wasm_bindgen::from_value(result).ok()),
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+
+    // Options come with each page, so each keeps track of whether it is the one picked.
+    let facet_filter = move |label: &'static str, options: fn(&CatalogPage) -> Vec<String>, picked: fn(&mut CatalogQuery) -> &mut Option<String>| view! {
+        <select class="vendor-select" on:change=move |ev| {
+            let value = non_empty(event_target_value(&ev));
+            refine(query, |query| *picked(query) = value);
+        }>
+            <option value="">{label}</option>
+            {move || page
+                .with(options)
+                .into_iter()
+                .map(|option| {
+                    let selected = picked(&mut query.get_untracked()).as_ref() == Some(&option);
+                    view! { <option value=option.clone() selected=selected>{option.clone()}</option> }
+                })
+                .collect_view()}
+        </select>
+    };
+
+    // Exports every match, not just the page shown.
+    let on_export = Callback::new(move |(columns, format): (Vec<String>, String)| {
+        let current = query.get_untracked();
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "query": current,
+                "columns": columns,
+                "format": format,
+            }))
+            .unwrap();
+            match try_invoke("export_catalog", args).await {
+                Ok(result) => status.set(result.as_string().map(|path| format!("Exported to {}", path))),
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    });
+    let export_columns = std::iter::once("Vendor").chain(TARGET_FIELDS).chain([CURRENCY]).map(String::from).collect::<Vec<String>>();
+
+    let page_count = move || page.with(|page| page.total.div_ceil(page.page_size.max(1)).max(1));
+
+    view! {
+        <div class="upload-container catalog-container">
+            <h1>"Catalog"</h1>
+            <div class="catalog-filters">
+                <input
+                    type="search"
+                    class="text-input catalog-search"
+                    placeholder="Search descriptions and brands"
+                    prop:value=move || query.with(|query| query.search.clone())
+                    on:input=move |ev| {
+                        let search = event_target_value(&ev);
+                        refine(query, |query| query.search = search);
+                    }
+                />
+                {facet_filter("All vendors", |page| page.vendors.clone(), |query| &mut query.vendor)}
+                {facet_filter("All brands", |page| page.brands.clone(), |query| &mut query.brand)}
+                {facet_filter("All catalogs", |page| page.catalogs.clone(), |query| &mut query.catalog)}
+                <select class="vendor-select" on:change=move |ev| {
+                    let stock = event_target_value(&ev);
+                    refine(query, |query| query.stock = stock);
+                }>
+                    {STOCK_LEVELS.map(|(value, label)| view! { <option value=value>{label}</option> }).collect_view()}
+                </select>
+            </div>
+            <ExportPanel available=Signal::stored(export_columns) on_export=on_export />
+            {move || status.get().map(|status| view! { <p class="batch-summary">{status}</p> })}
+            <table class="diff-table catalog-items">
+                <tr>
+                    {COLUMNS.map(|(field, label)| {
+                        let arrow = move || query.with(|query| match (query.sort == field, query.descending) {
+                            (true, false) => " ▲",
+                            (true, true) => " ▼",
+                            _ => "",
+                        });
+                        view! {
+                            <th class="sortable" on:click=move |_: MouseEvent| refine(query, |query| {
+                                query.descending = query.sort == field && !query.descending;
+                                query.sort = field.to_string();
+                            })>
+                                {label}{arrow}
+                            </th>
+                        }
+                    }).collect_view()}
+                </tr>
+                {move || page
+                    .get()
+                    .items
+                    .into_iter()
+                    .map(|item| {
+                        let quantity = format_quantity(item.quantity);
+                        let row = item.clone();
+                        view! {
+                            <tr class="catalog-item" on:click=move |_: MouseEvent| show_detail(row.clone())>
+                                <td>{item.vendor_name}</td>
+                                <td>{item.key}</td>
+                                <td>{item.catalog}</td>
+                                <td>{item.brand}</td>
+                                <td>{item.description}</td>
+                                <td class="catalog-quantity">{quantity}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </table>
+            <div class="catalog-pages">
+                <button
+                    class="preview-button"
+                    disabled=move || query.with(|query| query.page == 0)
+                    on:click=move |_: MouseEvent| query.update(|query| query.page = query.page.saturating_sub(1))
+                >
+                    "Previous"
+                </button>
+                <span class="diff-summary">
+                    {move || format!("Page {} of {} · {} items", page.with(|page| page.page) + 1, page_count(), page.with(|page| page.total))}
+                </span>
+                <button
+                    class="preview-button"
+                    disabled=move || query.with(|query| query.page + 1 >= page_count())
+                    on:click=move |_: MouseEvent| query.update(|query| query.page += 1)
+                >
+                    "Next"
+                </button>
+            </div>
+            {move || detail.get().map(|shown| view! {
+                <ItemDetailView detail=shown on_close=Callback::new(move |()| detail.set(None)) />
+            })}
+        </div>
+    }
+}
+
+/// Where a field's value came from in the vendor's file.
+fn origin(field: &str, mapping: &BTreeMap<String, String>) -> String {
+    let by_location = mapping.get(LOCATION);
+    let warehouses = mapping.keys().any(|target| location_of(target).is_some());
+    match location_of(field) {
+        _ if field == "Quantity" && (by_location.is_some() || warehouses) => "Total of every location".to_string(),
+        Some(location) if !mapping.contains_key(field) => match (mapping.get("Quantity"), by_location) {
+            (Some(quantity), Some(column)) => format!("{} where {} is \"{}\"", quantity, column, location),
+            _ => String::new(),
+        },
+        _ => mapping.get(field).cloned().unwrap_or_default(),
+    }
+}
+
+#[component]
+fn ItemDetailView(detail: ItemDetail, on_close: Callback<()>) -> impl IntoView {
+    let ItemDetail { item, fields, mapping } = detail;
+    view! {
+        <div class="catalog-detail">
+            <div class="catalog-detail-header">
+                <h2>{format!("{} · {}", item.key, item.vendor_name)}</h2>
+                <button class="preview-button" on:click=move |_: MouseEvent| on_close.run(())>"Close"</button>
+            </div>
+            <table class="diff-table">
+                <tr>
+                    <th>"Field"</th>
+                    <th>"Value"</th>
+                    <th>"Column in the vendor's file"</th>
+                </tr>
+                {fields
+                    .into_iter()
+                    .map(|(field, value)| {
+                        let column = origin(&field, &mapping);
+                        view! {
+                            <tr>
+                                <td>{field}</td>
+                                <td>{value}</td>
+                                <td class="batch-detail">{column}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </table>
+        </div>
+    }
+}
@@ -1,65 +1,326 @@
+use std::collections::BTreeMap;
+
+use leptos::task::spawn_local;
 use leptos::prelude::*;
 use leptos::prelude::Callback;
+use serde::Deserialize;
 use wasm_bindgen::prelude::*;
-use web_sys::{Event, MouseEvent};
+use web_sys::{DragEvent, Event, MouseEvent};
+use gloo_file::futures::read_as_bytes;
+use gloo_file::Blob as GlooBlob;
+
+use crate::batch::{BatchFile, JobStatus, ValidationReport};
+use crate::login::{use_session, Permission};
+use crate::upload::{DatasetDiff, FileSummary, Row, RowChange};
+use crate::watch::format_timestamp;
+use crate::wizard::{location_field, location_of, Combine, Dialect, Draft, FieldSource, SourceMode, CURRENCY, LOCATION, TARGET_FIELDS};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
+    async fn listen(event: &str, handler: &Closure<dyn FnMut(JsValue)>) -> JsValue;
+}
+
+#[derive(Deserialize)]
+struct TauriEvent<T> {
+    payload: T,
+}
+
+#[derive(Deserialize)]
+struct DragDropPayload {
+    paths: Vec<String>,
+}
 
 #[component]
 pub fn ColumnMappingList(
-    is_new_vendor: ReadSignal<bool>,
-    file_signal: RwSignal<Option<Vec<u8>>>,
-    column_headers: ReadSignal<Vec<String>>,
-    update_column_mapping: Callback<(usize, String)>,
+    #[prop(into)] column_headers: Signal<Vec<String>>,
+    #[prop(into)] mappings: Signal<BTreeMap<String, String>>,
+    update_column_mapping: Callback<(String, String)>,
 ) -> impl IntoView {
-    let pre_selected_columns = vec![
-        "File Key".to_string(),
-        "Catalog".to_string(),
-        "Brand".to_string(),
-        "Description".to_string(),
-        "Quantity".to_string(),
-        "Ecommerce".to_string(),  
-    ];
     view! {
-        {move || {
-            if file_signal.get().is_some() && !column_headers.get().is_empty()
-                && is_new_vendor.get()
-            {
-                Some(
+        <div class="column-mappings">
+            {TARGET_FIELDS
+                .into_iter()
+                .map(|name| {
                     view! {
-                        <div class="column-mappings">
-                            // Clone the vector
-                            {pre_selected_columns
-                                .clone()
-                                .into_iter()
-                                .enumerate()
-                                .map(|(index, name)| {
-                                    view! {
-                                        <MappingComponent
-                                            name=name.clone()
-                                            index=index
-                                            column_headers=column_headers
-                                            update_column_mapping=update_column_mapping.clone()
-                                        />
-                                    }
-                                })
-                                .collect_view()}
-                        </div>
-                    },
-                )
-            } else {
-                None
-            }
-        }}
+                        <MappingComponent
+                            name=name.to_string()
+                            column_headers=column_headers
+                            selected=Signal::derive(move || mappings.with(|m| m.get(name).cloned().unwrap_or_default()))
+                            update_column_mapping=update_column_mapping
+                        />
+                    }
+                })
+                .collect_view()}
+            <LocationMapping
+                column_headers=column_headers
+                mappings=mappings
+                update_column_mapping=update_column_mapping
+            />
+            <div class="currency-mapping">
+                <p class="batch-detail">
+                    "Prices may use either decimal separator and a currency symbol. Map a column of currency codes, or a constant such as EUR when the vendor prices everything in one currency."
+                </p>
+                <MappingComponent
+                    name=CURRENCY.to_string()
+                    column_headers=column_headers
+                    selected=Signal::derive(move || mappings.with(|m| m.get(CURRENCY).cloned().unwrap_or_default()))
+                    update_column_mapping=update_column_mapping
+                />
+            </div>
+        </div>
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StockLayout {
+    /// One quantity per item.
+    Single,
+    /// A column per warehouse.
+    Warehouses,
+    /// A row per item and location.
+    ByLocation,
+}
+
+/// How a file spreads an item's stock over warehouses.
+#[component]
+fn LocationMapping(
+    column_headers: Signal<Vec<String>>,
+    mappings: Signal<BTreeMap<String, String>>,
+    update_column_mapping: Callback<(String, String)>,
+) -> impl IntoView {
+    let warehouses = Memo::new(move |_| {
+        mappings.with(|m| m.keys().filter_map(|target| location_of(target).map(String::from)).collect::<Vec<String>>())
+    });
+    let layout = RwSignal::new(StockLayout::Single);
+    Effect::new(move |_| {
+        if !warehouses.with(Vec::is_empty) {
+            layout.set(StockLayout::Warehouses);
+        } else if mappings.with(|m| m.contains_key(LOCATION)) {
+            layout.set(StockLayout::ByLocation);
+        }
+    });
+    let unmap = move |target: String| update_column_mapping.run((target, String::new()));
+    let on_layout = move |ev| {
+        let next = match event_target_value(&ev).as_str() {
+            "warehouses" => StockLayout::Warehouses,
+            "location" => StockLayout::ByLocation,
+            _ => StockLayout::Single,
+        };
+        // Leaving a layout unmaps what only it used.
+        if next != StockLayout::Warehouses {
+            warehouses.get_untracked().iter().for_each(|warehouse| unmap(location_field(warehouse)));
+        }
+        if next != StockLayout::ByLocation && mappings.with_untracked(|m| m.contains_key(LOCATION)) {
+            unmap(LOCATION.to_string());
+        }
+        layout.set(next);
+    };
+
+    let new_warehouse = RwSignal::new(String::new());
+    let new_column = RwSignal::new(String::new());
+    let add = move |_| {
+        let (name, column) = (new_warehouse.get_untracked().trim().to_string(), new_column.get_untracked());
+        if !name.is_empty() && !column.is_empty() {
+            update_column_mapping.run((location_field(&name), column));
+            new_warehouse.set(String::new());
+            new_column.set(String::new());
+        }
+    };
+
+    view! {
+        <div class="location-mapping">
+            <label>
+                "Stock "
+                <select on:change=on_layout>
+                    <option value="single" selected=move || layout.get() == StockLayout::Single>"One quantity per item"</option>
+                    <option value="warehouses" selected=move || layout.get() == StockLayout::Warehouses>"A column per warehouse"</option>
+                    <option value="location" selected=move || layout.get() == StockLayout::ByLocation>"A row per item and location"</option>
+                </select>
+            </label>
+            {move || match layout.get() {
+                StockLayout::Single => ().into_any(),
+                StockLayout::Warehouses => view! {
+                    <p class="batch-detail">"Quantity is the total of the warehouses."</p>
+                    {move || warehouses
+                        .get()
+                        .into_iter()
+                        .map(|warehouse| {
+                            let target = location_field(&warehouse);
+                            let remove = target.clone();
+                            view! {
+                                <div class="location-warehouse">
+                                    <MappingComponent
+                                        name=target.clone()
+                                        column_headers=column_headers
+                                        selected=Signal::derive(move || mappings.with(|m| m.get(&target).cloned().unwrap_or_default()))
+                                        update_column_mapping=update_column_mapping
+                                    />
+                                    <button title="Remove" on:click=move |_| unmap(remove.clone())>"×"</button>
+                                </div>
+                            }
+                        })
+                        .collect_view()}
+                    <div class="location-add">
+                        <input
+                            type="text"
+                            placeholder="Warehouse"
+                            prop:value=move || new_warehouse.get()
+                            on:input=move |ev| new_warehouse.set(event_target_value(&ev))
+                        />
+                        {move || view! {
+                            <ColumnSelect
+                                column_headers=column_headers
+                                selected=new_column.get()
+                                placeholder="Select a Column"
+                                on_pick=Callback::new(move |column: String| new_column.set(column))
+                            />
+                        }}
+                        <button on:click=add>"Add warehouse"</button>
+                    </div>
+                }
+                .into_any(),
+                StockLayout::ByLocation => view! {
+                    <p class="batch-detail">"Rows sharing a File Key are one item, and each row's Quantity is its stock at that location."</p>
+                    <MappingComponent
+                        name=LOCATION.to_string()
+                        column_headers=column_headers
+                        selected=Signal::derive(move || mappings.with(|m| m.get(LOCATION).cloned().unwrap_or_default()))
+                        update_column_mapping=update_column_mapping
+                    />
+                }
+                .into_any(),
+            }}
+        </div>
+    }
+}
+
+/// How a combined field is built while it is edited: the function, its separator and the columns.
+type CombineParts = (Combine, String, Vec<String>);
+
 #[component]
 pub fn MappingComponent(
     name: String,
-    index: usize,
-    column_headers: ReadSignal<Vec<String>>,
-    update_column_mapping: Callback<(usize, String)>,
+    column_headers: Signal<Vec<String>>,
+    selected: Signal<String>,
+    update_column_mapping: Callback<(String, String)>,
 ) -> impl IntoView {
-    let selected_header = RwSignal::new("Select a Column".to_string());
+    let mode = RwSignal::new(FieldSource::parse(&selected.get_untracked()).mode());
+    Effect::new(move |_| {
+        let spec = selected.get();
+        if !spec.is_empty() {
+            mode.set(FieldSource::parse(&spec).mode());
+        }
+    });
+    // Picking a mode clears the mapping until something is filled in, so the
+    // editor falls back to an empty source of that mode.
+    let source = Memo::new(move |_| {
+        let source = FieldSource::parse(&selected.get());
+        if source.mode() == mode.get() { source } else { mode.get().empty() }
+    });
+    let target = StoredValue::new(name.clone());
+    let set = move |source: FieldSource| update_column_mapping.run((target.get_value(), source.spec()));
+
+    let editor = move || match source.get() {
+        FieldSource::Column(column) => view! {
+            <ColumnSelect
+                column_headers=column_headers
+                selected=column
+                placeholder="Select a Column"
+                on_pick=Callback::new(move |column: String| set(FieldSource::Column(column)))
+            />
+        }
+        .into_any(),
+        FieldSource::Combine { combine, separator, columns } => {
+            let current = StoredValue::new((combine, separator.clone(), columns.clone()));
+            let update = move |change: &dyn Fn(&mut CombineParts)| {
+                let mut next = current.get_value();
+                change(&mut next);
+                let (combine, separator, columns) = next;
+                set(FieldSource::Combine { combine, separator, columns });
+            };
+            view! {
+                <div class="mapping-combine">
+                    <select on:change=move |ev| {
+                        let picked = Combine::named(&event_target_value(&ev)).unwrap_or(Combine::Concat);
+                        update(&|current| current.0 = picked);
+                    }>
+                        {Combine::ALL
+                            .into_iter()
+                            .map(|option| view! {
+                                <option value=option.name() selected=option == combine>{option.label()}</option>
+                            })
+                            .collect_view()}
+                    </select>
+                    {(combine == Combine::Concat).then(|| view! {
+                        <input
+                            type="text"
+                            placeholder="Separator"
+                            prop:value=separator
+                            on:change=move |ev| {
+                                let separator = event_target_value(&ev);
+                                update(&|current| current.1 = separator.clone());
+                            }
+                        />
+                    })}
+                    <ul class="mapping-sources">
+                        {columns
+                            .iter()
+                            .enumerate()
+                            .map(|(index, column)| view! {
+                                <li>
+                                    {column.clone()}
+                                    <button
+                                        title="Remove"
+                                        on:click=move |_| update(&|current| {
+                                            current.2.remove(index);
+                                        })
+                                    >
+                                        "×"
+                                    </button>
+                                </li>
+                            })
+                            .collect_view()}
+                    </ul>
+                    <ColumnSelect
+                        column_headers=column_headers
+                        selected=String::new()
+                        placeholder="Add a column"
+                        on_pick=Callback::new(move |column: String| {
+                            if !column.is_empty() {
+                                update(&|current| current.2.push(column.clone()));
+                            }
+                        })
+                    />
+                </div>
+            }
+            .into_any()
+        }
+        FieldSource::Constant(text) => view! {
+            <input
+                type="text"
+                placeholder="Value for every row"
+                prop:value=text
+                on:change=move |ev| set(FieldSource::Constant(event_target_value(&ev)))
+            />
+        }
+        .into_any(),
+        FieldSource::Formula(formula) => view! {
+            <input
+                type="text"
+                class="mapping-formula"
+                placeholder="[Qty] - [Reserved]"
+                prop:value=formula
+                on:change=move |ev| set(FieldSource::Formula(event_target_value(&ev)))
+            />
+            <small>"Columns in [brackets], text in \"quotes\", + - * / and concat, sum, first"</small>
+        }
+        .into_any(),
+    };
+
     view! {
         <div class="column-mapping-item">
             <div class="mapping-content">
@@ -68,32 +329,55 @@ pub fn MappingComponent(
                     value=name.clone()
                     readonly=true
                 />
-                // <CustomSelect options=column_headers selected=selected_header />
-                <select
-                    on:change=move |ev| {
-                        let selected_value = event_target_value(&ev);
-                        selected_header.set(selected_value.clone());
-                        update_column_mapping.run((index, selected_value));
-                    }
-                >
-                    <option value="">"Select a Column"</option>
-                    {move || column_headers
-                        .get()
-                        .iter()
-                        .map(|header| {
-                            view! {
-                                <option value=header.clone()>
-                                    {header.clone()}
-                                </option>
-                            }
-                        })
-                        .collect_view()}
-                </select>
+                <div class="mapping-source">
+                    <select
+                        class="mapping-mode"
+                        on:change=move |ev| {
+                            let next = SourceMode::named(&event_target_value(&ev));
+                            mode.set(next);
+                            set(source.get_untracked().into_mode(next));
+                        }
+                    >
+                        {SourceMode::ALL
+                            .into_iter()
+                            .map(|option| view! {
+                                <option value=option.name() selected=move || mode.get() == option>{option.label()}</option>
+                            })
+                            .collect_view()}
+                    </select>
+                    {editor}
+                </div>
             </div>
         </div>
     }
 }
 
+#[component]
+fn ColumnSelect(
+    column_headers: Signal<Vec<String>>,
+    selected: String,
+    placeholder: &'static str,
+    on_pick: Callback<String>,
+) -> impl IntoView {
+    view! {
+        <select on:change=move |ev| on_pick.run(event_target_value(&ev))>
+            <option value="" selected=selected.is_empty()>{placeholder}</option>
+            {move || column_headers
+                .get()
+                .into_iter()
+                .map(|header| {
+                    let is_selected = header == selected;
+                    view! {
+                        <option value=header.clone() selected=is_selected>
+                            {header.clone()}
+                        </option>
+                    }
+                })
+                .collect_view()}
+        </select>
+    }
+}
+
 // #[component]
 // pub fn CustomSelect(
 //     options: ReadSignal<Vec<String>>, 
@@ -128,9 +412,8 @@ pub fn MappingComponent(
 #[component]
 pub fn RadioButtonList(
     options: Vec<String>,  // List of labels
-    selected_index: RwSignal<usize>,  // Reactive signal to track selected index
+    #[prop(into)] selected_index: Signal<usize>,
     on_select: Callback<usize>,
-    set_is_new_vendor: WriteSignal<bool>,
 ) -> impl IntoView {
     view! {
         <div style="display: flex; justify-content: center; margin-bottom: 25px;">
@@ -143,8 +426,7 @@ pub fn RadioButtonList(
                             label=label
                             index=index
                             selected_index=selected_index
-                            set_is_new_vendor=set_is_new_vendor
-                            on_select=on_select.clone()
+                            on_select=on_select
                         />
                     }
                 })
@@ -157,24 +439,14 @@ pub fn RadioButtonList(
 pub fn RadioButton(
     label: String,
     index: usize,
-    selected_index: RwSignal<usize>,
-    set_is_new_vendor: WriteSignal<bool>,
+    selected_index: Signal<usize>,
     on_select: Callback<usize>,
 ) -> impl IntoView {
     let is_selected = move || selected_index.get() == index;
-    let label_clone = label.clone();
     view! {
         <label class="radio-button-label">
             <div
-                on:click=move |_| {
-                    selected_index.set(index);
-                    on_select.run(index);
-                    if label_clone == "New Vendor" {
-                        set_is_new_vendor.set(true);
-                    } else {
-                        set_is_new_vendor.set(false);
-                    }
-                }
+                on:click=move |_| on_select.run(index)
                 style=move || {
                     format!(
                         "width: 20px; height: 20px; border: 2px solid #bdbdbd; border-radius: 50%;
@@ -200,14 +472,20 @@ pub fn RadioButton(
 #[component]
 pub fn FileUploadLabel(
     on_file_change: Callback<JsValue>,
+    #[prop(default = "file-upload")] id: &'static str,
+    #[prop(default = "Choose File")] text: &'static str,
+    #[prop(optional)] multiple: bool,
+    #[prop(optional)] directory: bool,
 ) -> impl IntoView {
     view! {
-        <label class="file-upload-label" for="file-upload">
-            <span class="file-upload-text">"Choose File"</span>
+        <label class="file-upload-label" for=id>
+            <span class="file-upload-text">{text}</span>
             <input
                 type="file"
-                id="file-upload"
+                id=id
                 accept=".csv"
+                multiple=multiple
+                prop:webkitdirectory=directory
                 on:change=move |ev| on_file_change.run(ev.into())
                 class="file-upload-input"
             />
@@ -215,21 +493,144 @@ pub fn FileUploadLabel(
     }
 }
 
+fn is_csv(name: &str) -> bool {
+    name.to_lowercase().ends_with(".csv")
+}
+
+/// Subscribes to a Tauri window event for as long as the calling component lives.
+pub fn listen_while_mounted(event: &'static str, handler: impl FnMut(JsValue) + 'static) {
+    let unlisten = StoredValue::new_local(None::<js_sys::Function>);
+    spawn_local(async move {
+        let closure = Closure::<dyn FnMut(JsValue)>::new(handler);
+        let result = listen(event, &closure).await;
+        closure.forget();
+        unlisten.set_value(result.dyn_into::<js_sys::Function>().ok());
+    });
+    on_cleanup(move || {
+        if let Some(unlisten) = unlisten.try_get_value().flatten() {
+            let _ = unlisten.call0(&JsValue::NULL);
+        }
+    });
+}
+
+/// Accepts a stock file dragged from the OS, either through Tauri's native
+/// drag-drop events (real paths) or an HTML5 drop inside the webview.
 #[component]
-pub fn FileDisplaySection(
-    file_signal: RwSignal<Option<Vec<u8>>>,
-    file_name: RwSignal<String>,
-    clear_selected_file: Callback<MouseEvent>,
+pub fn DropZone(
+    on_drop_file: Callback<(String, Vec<u8>)>,
+    on_drop_path: Callback<FileSummary>,
+    on_reject: Callback<String>,
+    children: Children,
 ) -> impl IntoView {
+    let hovering = RwSignal::new(false);
+
+    listen_while_mounted("tauri://drag-enter", move |_| hovering.set(true));
+    listen_while_mounted("tauri://drag-leave", move |_| hovering.set(false));
+    listen_while_mounted("tauri://drag-drop", move |event| {
+        hovering.set(false);
+        let Ok(event) = serde_wasm_bindgen::from_value::<TauriEvent<DragDropPayload>>(event) else {
+            return;
+        };
+        let Some(path) = event.payload.paths.into_iter().next() else {
+            return;
+        };
+        let name = path.rsplit(['/', '\\']).next().unwrap_or_default().to_string();
+        if !is_csv(&name) {
+            on_reject.run(format!("{} is not a CSV file", name));
+            return;
+        }
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "path": path })).unwrap();
+            match try_invoke("inspect_path", args).await {
+                Ok(summary) => match serde_wasm_bindgen::from_value::<FileSummary>(summary) {
+                    Ok(summary) => on_drop_path.run(summary),
+                    Err(err) => on_reject.run(err.to_string()),
+                },
+                Err(err) => on_reject.run(err.as_string().unwrap_or_default()),
+            }
+        });
+    });
+
+    let on_drag_over = move |ev: DragEvent| {
+        ev.prevent_default();
+        hovering.set(true);
+    };
+
+    let on_drop = move |ev: DragEvent| {
+        ev.prevent_default();
+        hovering.set(false);
+        let Some(file) = ev.data_transfer().and_then(|dt| dt.files()).and_then(|files| files.get(0)) else {
+            return;
+        };
+        let name = file.name();
+        if !is_csv(&name) {
+            on_reject.run(format!("{} is not a CSV file", name));
+            return;
+        }
+        let gloo_blob = GlooBlob::from(file);
+        spawn_local(async move {
+            match read_as_bytes(&gloo_blob).await {
+                Ok(bytes) => on_drop_file.run((name, bytes)),
+                Err(err) => on_reject.run(format!("File read error: {:?}", err)),
+            }
+        });
+    };
+
+    view! {
+        <div
+            class="drop-zone"
+            class:hovering=move || hovering.get()
+            on:dragenter=on_drag_over
+            on:dragover=on_drag_over
+            on:dragleave=move |_| hovering.set(false)
+            on:drop=on_drop
+        >
+            <span class="drop-zone-text">
+                {move || if hovering.get() { "Release to load the file" } else { "Drag a CSV file here or" }}
+            </span>
+            {children()}
+        </div>
+    }
+}
+
+/// Opens the native file dialog; the backend reads the picked file by path.
+#[component]
+pub fn FilePickerButton(on_click: Callback<MouseEvent>) -> impl IntoView {
+    view! {
+        <button class="file-upload-label file-picker-button" on:click=move |ev: MouseEvent| on_click.run(ev)>
+            <span class="file-upload-text">"Choose File"</span>
+        </button>
+    }
+}
+
+#[component]
+pub fn FilePreview(#[prop(into)] file_summary: Signal<Option<FileSummary>>) -> impl IntoView {
     move || {
-        file_signal.get().map(|_| {
-            view! { <FileDisplay file_name=file_name on_clear=clear_selected_file /> }
+        file_summary.get().map(|summary| {
+            let stats = format!("{} rows · {:.1} KB", summary.row_count, summary.size as f64 / 1024.0);
+            view! {
+                <div class="file-preview">
+                    <p class="file-stats">{stats}</p>
+                    <div class="file-sample">
+                        <table>
+                            <tr>
+                                {summary.headers.into_iter().map(|h| view! { <th>{h}</th> }).collect_view()}
+                            </tr>
+                            {summary.sample.into_iter().map(|row| view! {
+                                <tr>
+                                    {row.into_iter().map(|field| view! { <td>{field}</td> }).collect_view()}
+                                </tr>
+                            }).collect_view()}
+                        </table>
+                    </div>
+                </div>
+            }
         })
     }
 }
 
 #[component]
-pub fn FileDisplay(file_name: RwSignal<String>, on_clear: Callback<MouseEvent>) -> impl IntoView {
+pub fn FileDisplay(#[prop(into)] file_name: Signal<String>, on_clear: Callback<MouseEvent>) -> impl IntoView {
     view! {
         <div class="file-display">
             <span class="file-name">{move || file_name.get()}</span>
@@ -249,15 +650,17 @@ pub fn CloseButton(on_click: Callback<MouseEvent>) -> impl IntoView {
 
 #[component]
 pub fn VendorForm(
-    is_new_vendor: ReadSignal<bool>,
-    vendor_name: RwSignal<String>,
-    password: RwSignal<String>,
+    #[prop(into)] is_new_vendor: Signal<bool>,
+    #[prop(into)] vendor_name: Signal<String>,
+    #[prop(into)] password: Signal<String>,
     update_vendor_name: Callback<Event>,
     update_password: Callback<Event>,
 ) -> impl IntoView {
+    let session = use_session();
+
     view! {
         <Show
-            when=move || is_new_vendor.get()
+            when=move || is_new_vendor.get() && session.can(Permission::CreateVendor)
             fallback=|| view! { <div style="display: none;"></div> }
         >
             <div style="margin-bottom: 25px;">
@@ -279,7 +682,7 @@ pub fn VendorForm(
 #[component]
 pub fn TextInput(
     placeholder: String,
-    value: RwSignal<String>,
+    value: Signal<String>,
     on_input: Callback<Event>,
 ) -> impl IntoView {
     view! {
@@ -296,21 +699,404 @@ pub fn TextInput(
 #[component]
 pub fn SubmitButton(
     on_submit: Callback<MouseEvent>,
+) -> impl IntoView {
+    let session = use_session();
+
+    view! {
+        <Show
+            when=move || session.can(Permission::Upload)
+            fallback=|| view! { <p class="permission-note">"Your role can view vendors but not upload files."</p> }
+        >
+            <button class="submit-button" on:click=move |ev: MouseEvent| on_submit.run(ev)>
+                "Upload File"
+            </button>
+        </Show>
+    }
+}
+
+#[component]
+pub fn VendorSelect(
+    #[prop(into)] is_new_vendor: Signal<bool>,
+    vendors: RwSignal<Vec<String>>,
+    #[prop(into)] vendor_name: Signal<String>,
+    on_change: Callback<String>,
 ) -> impl IntoView {
     view! {
-        <button class="submit-button" on:click=move |ev: MouseEvent| on_submit.run(ev)>
-            "Upload File"
-        </button>
+        <Show when=move || !is_new_vendor.get()>
+            <div style="margin-bottom: 25px;">
+                <select
+                    class="vendor-select"
+                    on:change=move |ev| on_change.run(event_target_value(&ev))
+                >
+                    <option value="">"Select a Vendor"</option>
+                    {move || vendors
+                        .get()
+                        .into_iter()
+                        .map(|vendor| {
+                            let selected = vendor == vendor_name.get_untracked();
+                            view! {
+                                <option value=vendor.clone() selected=selected>
+                                    {vendor.clone()}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </select>
+            </div>
+        </Show>
     }
 }
 
 #[component]
-pub fn NotifyPopup(notification: RwSignal<Option<String>>) -> impl IntoView {
+pub fn DiffView(
+    #[prop(into)] diff: Signal<Option<DatasetDiff>>,
+    #[prop(into)] delta_only: Signal<bool>,
+    on_delta_only: Callback<bool>,
+    on_preview: Callback<MouseEvent>,
+) -> impl IntoView {
     view! {
-        {move || notification.get().map(|msg| view! {
-            <div class="notification">
-                {msg}
+        <div class="diff-view">
+            <button class="preview-button" on:click=move |ev: MouseEvent| on_preview.run(ev)>
+                "Preview Changes"
+            </button>
+            {move || diff.get().map(|diff| {
+                let summary = format!(
+                    "{} added, {} removed, {} changed",
+                    diff.added.len(),
+                    diff.removed.len(),
+                    diff.changed.len(),
+                );
+                view! {
+                    <p class="diff-summary">{summary}</p>
+                    <DiffRows title="Added" class="diff-added" rows=diff.added />
+                    <DiffRows title="Removed" class="diff-removed" rows=diff.removed />
+                    <ChangedRows changes=diff.changed />
+                    <label class="delta-option">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || delta_only.get()
+                            on:change=move |ev| on_delta_only.run(event_target_checked(&ev))
+                        />
+                        "Send only changes"
+                    </label>
+                }
+            })}
+        </div>
+    }
+}
+
+#[component]
+pub fn DiffRows(title: &'static str, class: &'static str, rows: Vec<Row>) -> impl IntoView {
+    if rows.is_empty() {
+        return None;
+    }
+    Some(view! {
+        <h3>{title}</h3>
+        <table class=format!("diff-table {}", class)>
+            {rows.into_iter().map(|row| {
+                let fields = row
+                    .fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                view! {
+                    <tr>
+                        <td>{row.key}</td>
+                        <td>{fields}</td>
+                    </tr>
+                }
+            }).collect_view()}
+        </table>
+    })
+}
+
+#[component]
+pub fn ChangedRows(changes: Vec<RowChange>) -> impl IntoView {
+    if changes.is_empty() {
+        return None;
+    }
+    Some(view! {
+        <h3>"Changed"</h3>
+        <table class="diff-table diff-changed">
+            {changes.into_iter().map(|change| {
+                view! {
+                    <tr>
+                        <td>{change.key}</td>
+                        <td>
+                            {change.fields.into_iter().map(|field| {
+                                view! {
+                                    <div>{format!("{}: {} → {}", field.field, field.old, field.new)}</div>
+                                }
+                            }).collect_view()}
+                        </td>
+                    </tr>
+                }
+            }).collect_view()}
+        </table>
+    })
+}
+
+const DELIMITERS: [(char, &str); 4] = [(',', "Comma"), (';', "Semicolon"), ('\t', "Tab"), ('|', "Pipe")];
+
+const QUOTES: [(char, &str); 2] = [('"', "Double quote"), ('\'', "Single quote")];
+
+const DECIMALS: [(&str, &str); 3] = [("", "Detect from the prices"), (".", "Point (1,234.50)"), (",", "Comma (1.234,50)")];
+
+/// Labels the backend understands, with what to show for them.
+const ENCODINGS: [(&str, &str); 7] = [
+    ("utf-8", "UTF-8"),
+    ("utf-16le", "UTF-16 LE"),
+    ("utf-16be", "UTF-16 BE"),
+    ("windows-1252", "Windows-1252 (Western)"),
+    ("iso-8859-2", "ISO-8859-2 (Central European)"),
+    ("iso-8859-15", "ISO-8859-15 (Western, with €)"),
+    ("shift_jis", "Shift JIS"),
+];
+
+#[component]
+pub fn DialectForm(#[prop(into)] dialect: Signal<Dialect>, on_change: Callback<Dialect>) -> impl IntoView {
+    let update = move |set: fn(&mut Dialect, String)| {
+        move |ev: Event| {
+            let mut changed = dialect.get_untracked();
+            set(&mut changed, event_target_value(&ev));
+            on_change.run(changed);
+        }
+    };
+
+    view! {
+        <div class="dialect-form">
+            <label>
+                "Delimiter"
+                <select class="vendor-select" on:change=update(|d, v| d.delimiter = v.chars().next().unwrap_or(','))>
+                    {DELIMITERS.map(|(c, label)| view! {
+                        <option value=c.to_string() prop:selected=move || dialect.get().delimiter == c>{label}</option>
+                    }).collect_view()}
+                </select>
+            </label>
+            <label>
+                "Quote"
+                <select class="vendor-select" on:change=update(|d, v| d.quote = v.chars().next().unwrap_or('"'))>
+                    {QUOTES.map(|(c, label)| view! {
+                        <option value=c.to_string() prop:selected=move || dialect.get().quote == c>{label}</option>
+                    }).collect_view()}
+                </select>
+            </label>
+            <label>
+                "Encoding"
+                <select class="vendor-select" on:change=update(|d, v| d.encoding = v)>
+                    {ENCODINGS.map(|(value, label)| view! {
+                        <option value=value prop:selected=move || dialect.get().encoding == value>{label}</option>
+                    }).collect_view()}
+                </select>
+            </label>
+            <label>
+                "Decimal separator"
+                <select class="vendor-select" on:change=update(|d, v| d.decimal = v.chars().next())>
+                    {DECIMALS.map(|(value, label)| view! {
+                        <option value=value prop:selected=move || dialect.get().decimal == value.chars().next()>{label}</option>
+                    }).collect_view()}
+                </select>
+            </label>
+        </div>
+    }
+}
+
+#[component]
+pub fn ValidationSummary(#[prop(into)] report: Signal<Option<ValidationReport>>) -> impl IntoView {
+    move || {
+        report.get().map(|report| {
+            let verdict = if report.errors.is_empty() { "Ready to upload" } else { "Not ready to upload" };
+            view! {
+                <div class="validation-summary">
+                    <p class="file-stats">{format!("{} rows · {}", report.row_count, verdict)}</p>
+                    {(!report.locations.is_empty()).then(|| view! {
+                        <p class="file-stats">{format!("Stock at {} locations: {}", report.locations.len(), report.locations.join(", "))}</p>
+                    })}
+                    {(!report.currencies.is_empty()).then(|| view! {
+                        <p class="file-stats">{format!("Prices in {}", report.currencies.join(", "))}</p>
+                    })}
+                    <ul class="validation-errors">
+                        {report.errors.into_iter().map(|e| view! { <li>{e}</li> }).collect_view()}
+                    </ul>
+                    <ul class="validation-warnings">
+                        {report.warnings.into_iter().map(|w| view! { <li>{w}</li> }).collect_view()}
+                    </ul>
+                </div>
+            }
+        })
+    }
+}
+
+fn draft_label(draft: &Draft) -> (String, String) {
+    let name = if draft.vendor_name.is_empty() { "Unnamed vendor".to_string() } else { draft.vendor_name.clone() };
+    (name, format!("{} · saved {}", draft.step.title(), format_timestamp(draft.updated)))
+}
+
+#[component]
+pub fn DraftList(#[prop(into)] drafts: Signal<Vec<Draft>>, on_resume: Callback<Draft>, on_discard: Callback<String>) -> impl IntoView {
+    view! {
+        <Show when=move || drafts.with(|drafts| !drafts.is_empty())>
+            <div class="draft-list">
+                <h3>"Saved Drafts"</h3>
+                {move || drafts
+                    .get()
+                    .into_iter()
+                    .map(|draft| {
+                        let id = draft.id.clone();
+                        let (name, detail) = draft_label(&draft);
+                        view! {
+                            <div class="draft-item">
+                                <span class="file-name">{name}</span>
+                                <span class="batch-detail">{detail}</span>
+                                <button class="preview-button" on:click=move |_: MouseEvent| on_resume.run(draft.clone())>"Resume"</button>
+                                <button class="preview-button" on:click=move |_: MouseEvent| on_discard.run(id.clone())>"Discard"</button>
+                            </div>
+                        }
+                    })
+                    .collect_view()}
+            </div>
+        </Show>
+    }
+}
+
+/// Offers the setup left unfinished when the app last closed.
+#[component]
+pub fn DraftRecovery(#[prop(into)] draft: Signal<Option<Draft>>, on_resume: Callback<Draft>, on_dismiss: Callback<()>) -> impl IntoView {
+    move || {
+        draft.get().map(|draft| {
+            let (name, detail) = draft_label(&draft);
+            view! {
+                <div class="draft-recovery">
+                    <span>{format!("The setup of {} was not finished.", name)}</span>
+                    <span class="batch-detail">{detail}</span>
+                    <button class="submit-button" on:click=move |_: MouseEvent| on_resume.run(draft.clone())>"Resume draft"</button>
+                    <button class="preview-button" on:click=move |_: MouseEvent| on_dismiss.run(())>"Not now"</button>
+                </div>
+            }
+        })
+    }
+}
+
+const EXPORT_FORMATS: [(&str, &str); 3] = [("csv", "CSV"), ("jsonl", "JSON Lines"), ("xlsx", "Excel workbook")];
+
+/// Picks the columns to export, their order and the file format. Columns
+/// start out included, in the order given.
+#[component]
+pub fn ExportPanel(#[prop(into)] available: Signal<Vec<String>>, on_export: Callback<(Vec<String>, String)>) -> impl IntoView {
+    let columns = RwSignal::new(Vec::<(String, bool)>::new());
+    let format = RwSignal::new(EXPORT_FORMATS[0].0.to_string());
+
+    // Keeps the operator's order and choices for columns that are still there.
+    Effect::new(move |_| {
+        let available = available.get();
+        columns.update(|columns| {
+            columns.retain(|(name, _)| available.contains(name));
+            for name in available {
+                if !columns.iter().any(|(existing, _)| *existing == name) {
+                    columns.push((name, true));
+                }
+            }
+        });
+    });
+    let swap = move |a: usize, b: usize| {
+        columns.update(|columns| {
+            if b < columns.len() {
+                columns.swap(a, b);
+            }
+        })
+    };
+    let picked = move || columns.with(|columns| columns.iter().filter(|(_, on)| *on).map(|(name, _)| name.clone()).collect::<Vec<String>>());
+
+    view! {
+        <details class="export-panel">
+            <summary>"Export"</summary>
+            <ol class="export-columns">
+                {move || columns
+                    .get()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (name, included))| view! {
+                        <li class="export-column">
+                            <label>
+                                <input
+                                    type="checkbox"
+                                    prop:checked=included
+                                    on:change=move |ev| {
+                                        let checked = event_target_checked(&ev);
+                                        columns.update(|columns| columns[i].1 = checked);
+                                    }
+                                />
+                                {name}
+                            </label>
+                            <button class="preview-button" disabled=i == 0 on:click=move |_: MouseEvent| swap(i.saturating_sub(1), i)>"↑"</button>
+                            <button class="preview-button" on:click=move |_: MouseEvent| swap(i, i + 1)>"↓"</button>
+                        </li>
+                    })
+                    .collect_view()}
+            </ol>
+            <div class="export-actions">
+                <select class="vendor-select" on:change=move |ev| format.set(event_target_value(&ev))>
+                    {EXPORT_FORMATS.map(|(value, label)| view! { <option value=value>{label}</option> }).collect_view()}
+                </select>
+                <button
+                    class="submit-button"
+                    disabled=move || picked().is_empty()
+                    on:click=move |_: MouseEvent| on_export.run((picked(), format.get_untracked()))
+                >
+                    "Export"
+                </button>
+            </div>
+        </details>
+    }
+}
+
+#[component]
+pub fn BatchFileList(files: RwSignal<Vec<BatchFile>>, on_remove: Callback<usize>) -> impl IntoView {
+    view! {
+        <div class="batch-files">
+            {move || files
+                .get()
+                .into_iter()
+                .map(|file| view! { <BatchFileItem file=file on_remove=on_remove /> })
+                .collect_view()}
+        </div>
+    }
+}
+
+#[component]
+pub fn BatchFileItem(file: BatchFile, on_remove: Callback<usize>) -> impl IntoView {
+    let id = file.id;
+    let (status_class, status_text) = match &file.status {
+        JobStatus::Pending => ("pending", "Pending".to_string()),
+        JobStatus::Running => ("running", "Uploading...".to_string()),
+        JobStatus::Uploaded(msg) => ("uploaded", format!("Uploaded: {}", msg)),
+        JobStatus::Failed(err) => ("failed", format!("Failed: {}", err)),
+        JobStatus::Skipped => ("skipped", "Skipped: validation errors".to_string()),
+    };
+    let report = file.report.clone();
+    view! {
+        <div class="batch-file">
+            <div class="batch-file-header">
+                <span class="file-name">{file.name.clone()}</span>
+                <span class=format!("job-status {}", status_class)>{status_text}</span>
+                <CloseButton on_click=Callback::new(move |_: MouseEvent| on_remove.run(id)) />
             </div>
-        })}
+            {match report {
+                None => view! { <p class="batch-detail">"Checking..."</p> }.into_any(),
+                Some(report) => view! {
+                    <p class="batch-detail">
+                        {format!("{} rows", report.validation.row_count)}
+                    </p>
+                    <p class="batch-detail">{report.file_mappings}</p>
+                    <ul class="validation-errors">
+                        {report.validation.errors.into_iter().map(|e| view! { <li>{e}</li> }).collect_view()}
+                    </ul>
+                    <ul class="validation-warnings">
+                        {report.validation.warnings.into_iter().map(|w| view! { <li>{w}</li> }).collect_view()}
+                    </ul>
+                }.into_any(),
+            }}
+        </div>
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,215 @@
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use leptos_router::hooks::use_navigate;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::components::{listen_while_mounted, DraftRecovery};
+use crate::upload::take_recovery_offer;
+use crate::watch::format_timestamp;
+use crate::wizard::Draft;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+const CHART_WIDTH: f64 = 280.0;
+const CHART_HEIGHT: f64 = 100.0;
+const BAR_HEIGHT: f64 = 22.0;
+
+#[derive(Serialize, Debug, Clone, Copy)]
+struct Periods {
+    today: u64,
+    week: u64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+struct Snapshot {
+    timestamp: u64,
+    rows: usize,
+    quantity: f64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+struct VendorVolume {
+    vendor_name: String,
+    uploads: usize,
+    rows: usize,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+struct VendorSeries {
+    vendor_name: String,
+    points: Vec<Snapshot>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+struct Metrics {
+    vendors: usize,
+    uploads_today: usize,
+    uploads_this_week: usize,
+    failed_this_week: usize,
+    rows_ingested: usize,
+    top_vendors: Vec<VendorVolume>,
+    series: Vec<VendorSeries>,
+}
+
+/// Local midnight today and on the Monday of this week.
+fn periods() -> Periods {
+    let now = js_sys::Date::new_0();
+    let midnight = |days_back: u32| {
+        let date = js_sys::Date::new_with_year_month_day(now.get_full_year(), now.get_month() as i32, now.get_date() as i32 - days_back as i32);
+        (date.get_time() / 1000.0) as u64
+    };
+    Periods {
+        today: midnight(0),
+        week: midnight((now.get_day() + 6) % 7),
+    }
+}
+
+/// Home page: how ingestion is going, kept current by the backend's events.
+#[component]
+pub fn Dashboard() -> impl IntoView {
+    let metrics = RwSignal::new(Metrics::default());
+    let status = RwSignal::new(None::<String>);
+    let recovered = RwSignal::new(None::<Draft>);
+    let navigate = use_navigate();
+
+    let load = move || {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "periods": periods() })).unwrap();
+            match try_invoke("dashboard_metrics", args).await {
+                Ok(result) => {
+                    metrics.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+                    status.set(None);
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+    load();
+    listen_while_mounted("upload://finished", move |_| load());
+    listen_while_mounted("watch://result", move |_| load());
+
+    // The app opens here, so this is where an unfinished setup is first offered.
+    spawn_local(async move {
+        if let Ok(result) = try_invoke("list_drafts", JsValue::NULL).await {
+            let drafts: Vec<Draft> = serde_wasm_bindgen::from_value(result).unwrap_or_default();
+            recovered.set(take_recovery_offer(&drafts));
+        }
+    });
+    let on_resume = Callback::new(move |draft: Draft| navigate(&format!("/upload?draft={}", draft.id), Default::default()));
+
+    let card = move |label: &'static str, value: fn(&Metrics) -> usize| view! {
+        <div class="metric-card">
+            <span class="metric-value">{move || metrics.with(value)}</span>
+            <span class="metric-label">{label}</span>
+        </div>
+    };
+
+    view! {
+        <div class="upload-container dashboard-container">
+            <h1>"Dashboard"</h1>
+            <DraftRecovery draft=recovered on_resume=on_resume on_dismiss=Callback::new(move |()| recovered.set(None)) />
+            {move || status.get().map(|status| view! { <p class="batch-summary">{status}</p> })}
+            <div class="metric-cards">
+                {card("Vendors", |m| m.vendors)}
+                {card("Uploads today", |m| m.uploads_today)}
+                {card("Uploads this week", |m| m.uploads_this_week)}
+                {card("Failed this week", |m| m.failed_this_week)}
+                {card("Rows ingested", |m| m.rows_ingested)}
+            </div>
+            <h2>"Top vendors by rows"</h2>
+            {move || view! { <VolumeChart volumes=metrics.with(|m| m.top_vendors.clone()) /> }}
+            <h2>"Stock over time"</h2>
+            <div class="stock-charts">
+                {move || metrics
+                    .with(|m| m.series.clone())
+                    .into_iter()
+                    .map(|series| view! { <StockChart series=series /> })
+                    .collect_view()}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn VolumeChart(volumes: Vec<VendorVolume>) -> impl IntoView {
+    if volumes.is_empty() {
+        return view! { <p class="notification-empty">"No uploads yet"</p> }.into_any();
+    }
+    let most = volumes.iter().map(|v| v.rows).max().unwrap_or_default().max(1) as f64;
+    let height = BAR_HEIGHT * volumes.len() as f64;
+    view! {
+        <svg class="volume-chart" viewBox=format!("0 0 {} {}", CHART_WIDTH * 2.0, height) role="img">
+            {volumes
+                .into_iter()
+                .enumerate()
+                .map(|(i, volume)| {
+                    let y = i as f64 * BAR_HEIGHT;
+                    let width = (CHART_WIDTH * volume.rows as f64 / most).max(1.0);
+                    view! {
+                        <text class="chart-label" x="0" y=y + BAR_HEIGHT * 0.7>{volume.vendor_name}</text>
+                        <rect class="chart-bar" x=CHART_WIDTH * 0.6 y=y + 3.0 width=width height=BAR_HEIGHT - 6.0 />
+                        <text class="chart-label" x=CHART_WIDTH * 0.6 + width + 6.0 y=y + BAR_HEIGHT * 0.7>
+                            {format!("{} rows, {} uploads", volume.rows, volume.uploads)}
+                        </text>
+                    }
+                })
+                .collect_view()}
+        </svg>
+    }
+    .into_any()
+}
+
+/// Total quantity at each accepted upload of one vendor.
+#[component]
+fn StockChart(series: VendorSeries) -> impl IntoView {
+    let points = series.points;
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+        _ => (0, 0),
+    };
+    let span = last.saturating_sub(first).max(1) as f64;
+    let low = points.iter().map(|p| p.quantity).fold(f64::INFINITY, f64::min).min(0.0);
+    let high = points.iter().map(|p| p.quantity).fold(f64::NEG_INFINITY, f64::max);
+    let range = (high - low).max(1.0);
+    let coordinates: Vec<(f64, f64)> = points
+        .iter()
+        .map(|p| {
+            // A single upload sits in the middle.
+            let x = if points.len() == 1 { CHART_WIDTH / 2.0 } else { CHART_WIDTH * p.timestamp.saturating_sub(first) as f64 / span };
+            (x, CHART_HEIGHT - CHART_HEIGHT * (p.quantity - low) / range)
+        })
+        .collect();
+    let line = coordinates.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect::<Vec<String>>().join(" ");
+    let latest = points.last().map(|p| p.quantity).unwrap_or_default();
+
+    view! {
+        <div class="stock-chart">
+            <div class="stock-chart-header">
+                <span class="file-name">{series.vendor_name}</span>
+                <span class="batch-detail">{format!("{} in stock", latest)}</span>
+            </div>
+            <svg viewBox=format!("-4 -4 {} {}", CHART_WIDTH + 8.0, CHART_HEIGHT + 8.0) role="img">
+                <line class="chart-axis" x1="0" y1=CHART_HEIGHT x2=CHART_WIDTH y2=CHART_HEIGHT />
+                <polyline class="chart-line" points=line />
+                {coordinates
+                    .into_iter()
+                    .zip(points)
+                    .map(|((x, y), point)| view! {
+                        <circle class="chart-point" cx=x cy=y r="3">
+                            <title>{format!("{}: {} in stock, {} rows", format_timestamp(point.timestamp), point.quantity, point.rows)}</title>
+                        </circle>
+                    })
+                    .collect_view()}
+            </svg>
+            <div class="stock-chart-range batch-detail">
+                <span>{format_timestamp(first)}</span>
+                <span>{format_timestamp(last)}</span>
+            </div>
+        </div>
+    }
+}
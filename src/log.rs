@@ -0,0 +1,33 @@
+use leptos::task::spawn_local;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+/// Sends `message` to the backend's log file, so it ends up in diagnostics.
+fn log(level: &'static str, message: String) {
+    spawn_local(async move {
+        let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+            "level": level,
+            "message": message,
+        }))
+        .unwrap();
+        // Nowhere left to report a failure to log.
+        let _ = try_invoke("log_frontend", args).await;
+    });
+}
+
+pub fn info(message: impl Into<String>) {
+    log("info", message.into());
+}
+
+pub fn warn(message: impl Into<String>) {
+    log("warn", message.into());
+}
+
+pub fn error(message: impl Into<String>) {
+    log("error", message.into());
+}
@@ -0,0 +1,181 @@
+use leptos::ev;
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use leptos_router::hooks::use_navigate;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+use web_sys::SubmitEvent;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+/// How often the backend is asked whether the session has timed out.
+const POLL_MS: u32 = 30_000;
+
+/// Activity is reported at most this often.
+const TOUCH_MS: f64 = 60_000.0;
+
+/// Mirrors the backend's permissions, which it enforces; the UI only hides what a role cannot use.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Upload,
+    CreateVendor,
+    EditMapping,
+    ManageWatchFolders,
+    ViewAudit,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Operator {
+    pub username: String,
+    pub role: String,
+    pub permissions: Vec<Permission>,
+}
+
+/// The signed-in operator, as last reported by the backend, which holds the session.
+#[derive(Clone, Copy)]
+pub struct SessionContext {
+    pub operator: RwSignal<Option<Operator>>,
+    /// Whether the backend has been asked yet.
+    pub checked: RwSignal<bool>,
+}
+
+impl SessionContext {
+    pub fn signed_in(&self) -> Option<bool> {
+        self.checked.get().then(|| self.operator.get().is_some())
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.operator.with(|operator| operator.as_ref().is_some_and(|o| o.permissions.contains(&permission)))
+    }
+
+    pub fn can_untracked(&self, permission: Permission) -> bool {
+        self.operator.with_untracked(|operator| operator.as_ref().is_some_and(|o| o.permissions.contains(&permission)))
+    }
+
+    pub fn refresh(self) {
+        spawn_local(async move {
+            let operator = match try_invoke("current_operator", JsValue::NULL).await {
+                Ok(result) => serde_wasm_bindgen::from_value(result).unwrap_or_default(),
+                Err(_) => None,
+            };
+            self.operator.set(operator);
+            self.checked.set(true);
+        });
+    }
+
+    pub fn sign_out(self) {
+        spawn_local(async move {
+            let _ = try_invoke("logout", JsValue::NULL).await;
+            self.operator.set(None);
+        });
+    }
+}
+
+pub fn use_session() -> SessionContext {
+    expect_context::<SessionContext>()
+}
+
+/// Provides the session to the app, polls for idle timeouts and reports
+/// keyboard and pointer activity so the backend keeps the session alive.
+pub fn provide_session() -> SessionContext {
+    let session = SessionContext {
+        operator: RwSignal::new(None),
+        checked: RwSignal::new(false),
+    };
+    provide_context(session);
+    session.refresh();
+
+    let poll = gloo_timers::callback::Interval::new(POLL_MS, move || session.refresh());
+    let poll = StoredValue::new_local(Some(poll));
+    on_cleanup(move || {
+        if let Some(poll) = poll.try_update_value(Option::take).flatten() {
+            poll.cancel();
+        }
+    });
+
+    let last_touch = StoredValue::new(0.0);
+    let touch = move || {
+        let now = js_sys::Date::now();
+        if session.operator.get_untracked().is_none() || now - last_touch.get_value() < TOUCH_MS {
+            return;
+        }
+        last_touch.set_value(now);
+        spawn_local(async move {
+            if try_invoke("touch_session", JsValue::NULL).await.is_err() {
+                session.operator.set(None);
+            }
+        });
+    };
+    let keydown = window_event_listener(ev::keydown, move |_| touch());
+    let pointerdown = window_event_listener(ev::pointerdown, move |_| touch());
+    on_cleanup(move || {
+        keydown.remove();
+        pointerdown.remove();
+    });
+
+    session
+}
+
+#[component]
+pub fn Login() -> impl IntoView {
+    let session = use_session();
+    let navigate = use_navigate();
+    let username = RwSignal::new(String::new());
+    let password = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+    let pending = RwSignal::new(false);
+
+    let on_submit = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let navigate = navigate.clone();
+        pending.set(true);
+        error.set(None);
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "username": username.get_untracked(),
+                "password": password.get_untracked(),
+            }))
+            .unwrap();
+            match try_invoke("login", args).await {
+                Ok(result) => {
+                    password.set(String::new());
+                    session.operator.set(serde_wasm_bindgen::from_value(result).ok());
+                    navigate("/", Default::default());
+                }
+                Err(err) => error.set(Some(err.as_string().unwrap_or_else(|| "Could not sign in".to_string()))),
+            }
+            pending.set(false);
+        });
+    };
+
+    view! {
+        <div class="login-container">
+            <form class="login-form" on:submit=on_submit>
+                <h1>"Sign in"</h1>
+                <input
+                    type="text"
+                    placeholder="Username"
+                    autocomplete="username"
+                    prop:value=move || username.get()
+                    on:input=move |ev| username.set(event_target_value(&ev))
+                />
+                <input
+                    type="password"
+                    placeholder="Password"
+                    autocomplete="current-password"
+                    prop:value=move || password.get()
+                    on:input=move |ev| password.set(event_target_value(&ev))
+                />
+                {move || error.get().map(|error| view! { <p class="login-error">{error}</p> })}
+                <button type="submit" disabled=move || pending.get() || username.get().is_empty()>
+                    {move || if pending.get() { "Signing in..." } else { "Sign in" }}
+                </button>
+            </form>
+        </div>
+    }
+}
@@ -1,6 +1,17 @@
 mod app;
+mod audit;
+mod batch;
+mod catalog;
+mod log;
+mod login;
+mod notify;
+mod profiles;
+mod rates;
 mod upload;
+mod watch;
+mod wizard;
 mod components;
+mod dashboard;
 mod template;
 
 // use app::*;
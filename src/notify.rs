@@ -0,0 +1,297 @@
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+use web_sys::MouseEvent;
+
+use crate::components::listen_while_mounted;
+use crate::watch::{format_timestamp, IngestResult, IngestStatus};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+#[derive(Deserialize)]
+struct TauriEvent<T> {
+    payload: T,
+}
+
+/// Toasts shown at once; older ones stay in the history.
+const MAX_TOASTS: usize = 5;
+
+/// Notifications kept in the history drawer.
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn class(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Success => "success",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// How long the toast stays up; errors stay until dismissed.
+    fn timeout_ms(self) -> Option<u32> {
+        match self {
+            Severity::Info | Severity::Success => Some(4_000),
+            Severity::Warning => Some(8_000),
+            Severity::Error => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Notice {
+    pub id: usize,
+    pub severity: Severity,
+    pub message: String,
+    pub details: Option<String>,
+    pub action: Option<(String, Callback<()>)>,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl Notice {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Notice {
+            id: 0,
+            severity,
+            message: message.into(),
+            details: None,
+            action: None,
+            timestamp: (js_sys::Date::now() / 1000.0) as u64,
+        }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Notice::new(Severity::Info, message)
+    }
+
+    pub fn success(message: impl Into<String>) -> Self {
+        Notice::new(Severity::Success, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Notice::new(Severity::Warning, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Notice::new(Severity::Error, message)
+    }
+
+    /// Shown behind a "Details" toggle, e.g. the full list of validation errors.
+    pub fn details(mut self, details: impl Into<String>) -> Self {
+        let details = details.into();
+        self.details = (!details.is_empty()).then_some(details);
+        self
+    }
+
+    /// A button on the toast, such as "Retry"; running it dismisses the toast.
+    pub fn action(mut self, label: impl Into<String>, run: Callback<()>) -> Self {
+        self.action = Some((label.into(), run));
+        self
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct NotificationCenter {
+    pub toasts: RwSignal<Vec<Notice>>,
+    pub history: RwSignal<Vec<Notice>>,
+    /// History entries added since the drawer was last opened.
+    pub unread: RwSignal<usize>,
+    pub drawer_open: RwSignal<bool>,
+    next_id: StoredValue<usize>,
+}
+
+impl NotificationCenter {
+    pub fn notify(self, mut notice: Notice) {
+        notice.id = self.next_id.get_value();
+        self.next_id.set_value(notice.id + 1);
+        let (id, timeout) = (notice.id, notice.severity.timeout_ms());
+
+        self.history.update(|history| {
+            history.push(notice.clone());
+            let overflow = history.len().saturating_sub(MAX_HISTORY);
+            history.drain(..overflow);
+        });
+        if !self.drawer_open.get_untracked() {
+            self.unread.update(|unread| *unread += 1);
+        }
+        self.toasts.update(|toasts| {
+            toasts.push(notice);
+            let overflow = toasts.len().saturating_sub(MAX_TOASTS);
+            toasts.drain(..overflow);
+        });
+
+        if let Some(ms) = timeout {
+            spawn_local(async move {
+                gloo_timers::future::TimeoutFuture::new(ms).await;
+                self.dismiss(id);
+            });
+        }
+    }
+
+    pub fn dismiss(self, id: usize) {
+        self.toasts.update(|toasts| toasts.retain(|toast| toast.id != id));
+    }
+
+    pub fn toggle_drawer(self) {
+        self.drawer_open.update(|open| *open = !*open);
+        self.unread.set(0);
+    }
+
+    pub fn clear_history(self) {
+        self.history.set(Vec::new());
+        self.unread.set(0);
+    }
+
+    /// Raises a native notification as well, for jobs that finish while the
+    /// window is in the background.
+    pub fn notify_in_background(self, notice: Notice) {
+        if !document().has_focus().unwrap_or(true) {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "title": "fs_ui",
+                "body": notice.message,
+            }))
+            .unwrap();
+            spawn_local(async move {
+                let _ = try_invoke("notify_desktop", args).await;
+            });
+        }
+        self.notify(notice);
+    }
+}
+
+pub fn provide_notifications() {
+    provide_context(NotificationCenter {
+        toasts: RwSignal::new(Vec::new()),
+        history: RwSignal::new(Vec::new()),
+        unread: RwSignal::new(0),
+        drawer_open: RwSignal::new(false),
+        next_id: StoredValue::new(0),
+    });
+}
+
+pub fn use_notifications() -> NotificationCenter {
+    expect_context::<NotificationCenter>()
+}
+
+/// The toasts, bottom right. Also reports watch folder results from anywhere in the app.
+#[component]
+pub fn ToastStack() -> impl IntoView {
+    let center = use_notifications();
+
+    // The backend raises the native notification for these itself.
+    listen_while_mounted("watch://result", move |event| {
+        let Ok(event) = serde_wasm_bindgen::from_value::<TauriEvent<IngestResult>>(event) else {
+            return;
+        };
+        let result = event.payload;
+        let severity = match result.status {
+            IngestStatus::Submitted => Severity::Success,
+            IngestStatus::Quarantined => Severity::Warning,
+            IngestStatus::Failed => Severity::Error,
+        };
+        center.notify(Notice::new(severity, format!("{}: {:?}", result.file, result.status)).details(result.message));
+    });
+
+    view! {
+        <div class="toast-stack">
+            <For each=move || center.toasts.get() key=|toast| toast.id let:toast>
+                <Toast notice=toast />
+            </For>
+        </div>
+    }
+}
+
+#[component]
+fn Toast(notice: Notice) -> impl IntoView {
+    let center = use_notifications();
+    let show_details = RwSignal::new(false);
+    let id = notice.id;
+    let details = notice.details.clone();
+
+    view! {
+        <div class=format!("toast {}", notice.severity.class())>
+            <div class="toast-body">
+                <span class="toast-message">{notice.message}</span>
+                {details.is_some().then(|| view! {
+                    <button class="toast-link" on:click=move |_: MouseEvent| show_details.update(|show| *show = !*show)>
+                        {move || if show_details.get() { "Hide details" } else { "Details" }}
+                    </button>
+                })}
+                {notice.action.map(|(label, run)| view! {
+                    <button class="toast-action" on:click=move |_: MouseEvent| {
+                        center.dismiss(id);
+                        run.run(());
+                    }>{label}</button>
+                })}
+                <button class="toast-close" title="Dismiss" on:click=move |_: MouseEvent| center.dismiss(id)>"×"</button>
+            </div>
+            <Show when=move || show_details.get()>
+                <pre class="toast-details">{details.clone()}</pre>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+pub fn NotificationBell() -> impl IntoView {
+    let center = use_notifications();
+
+    view! {
+        <button class="notification-bell" title="Notifications" on:click=move |_: MouseEvent| center.toggle_drawer()>
+            "Notifications"
+            {move || (center.unread.get() > 0).then(|| view! {
+                <span class="notification-count">{center.unread.get()}</span>
+            })}
+        </button>
+    }
+}
+
+#[component]
+pub fn NotificationDrawer() -> impl IntoView {
+    let center = use_notifications();
+
+    view! {
+        <Show when=move || center.drawer_open.get()>
+            <aside class="notification-drawer">
+                <div class="notification-drawer-header">
+                    <h2>"Notifications"</h2>
+                    <button on:click=move |_: MouseEvent| center.clear_history()>"Clear"</button>
+                    <button on:click=move |_: MouseEvent| center.toggle_drawer()>"Close"</button>
+                </div>
+                {move || center.history.with(|history| history.is_empty()).then(|| view! {
+                    <p class="notification-empty">"Nothing yet."</p>
+                })}
+                <ul>
+                    {move || center
+                        .history
+                        .get()
+                        .into_iter()
+                        .rev()
+                        .map(|notice| view! {
+                            <li class=format!("notification-entry {}", notice.severity.class())>
+                                <span class="notification-time">{format_timestamp(notice.timestamp)}</span>
+                                <span>{notice.message}</span>
+                                {notice.details.map(|details| view! { <pre class="toast-details">{details}</pre> })}
+                            </li>
+                        })
+                        .collect_view()}
+                </ul>
+            </aside>
+        </Show>
+    }
+}
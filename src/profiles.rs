@@ -0,0 +1,524 @@
+use std::collections::{BTreeMap, BTreeSet};
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+use web_sys::MouseEvent;
+
+use crate::login::{use_session, Permission};
+use crate::upload::{describe_dialect, FieldChange};
+use crate::watch::format_timestamp;
+use crate::wizard::{Dialect, TARGET_FIELDS};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+const FORMATS: [(&str, &str); 2] = [("json", "JSON"), ("toml", "TOML")];
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+struct Profile {
+    vendor_name: String,
+    #[serde(default)]
+    dialect: Dialect,
+    #[serde(default)]
+    mapping: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct ProfileImport {
+    incoming: Profile,
+    current: Option<Profile>,
+    status: String,
+    warnings: Vec<String>,
+    errors: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct ImportPreview {
+    warnings: Vec<String>,
+    profiles: Vec<ProfileImport>,
+}
+
+/// A profile version with the uploads mapped with it.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct VersionUse {
+    version: u32,
+    author: String,
+    timestamp: u64,
+    note: String,
+    uploads: usize,
+    last_upload: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+struct ImportSummary {
+    imported: Vec<Value>,
+    skipped: Vec<String>,
+}
+
+/// Resolutions offered for each import status, the first being the default.
+/// New vendors can only be skipped by operators who cannot create vendors.
+fn resolutions(status: &str, can_create: bool) -> &'static [(&'static str, &'static str)] {
+    match status {
+        "new" if can_create => &[("replace", "Import"), ("skip", "Skip")],
+        "new" => &[("skip", "Skip (creating vendors is not allowed)")],
+        "conflict" => &[("skip", "Keep saved"), ("replace", "Use imported"), ("merge", "Merge")],
+        _ => &[],
+    }
+}
+
+/// Unmapped fields show as a dash.
+fn or_dash(value: String) -> String {
+    if value.is_empty() { "—".to_string() } else { value }
+}
+
+/// Mapped fields in schema order, as `(target, source)`.
+/// Schema fields first, in schema order, then the location and warehouses.
+fn mapped(mapping: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    TARGET_FIELDS
+        .into_iter()
+        .filter_map(|target| Some((target.to_string(), mapping.get(target)?.clone())))
+        .chain(mapping.iter().filter(|(target, _)| !TARGET_FIELDS.contains(&target.as_str())).map(|(t, s)| (t.clone(), s.clone())))
+        .collect()
+}
+
+/// Shares vendors' mappings and dialects with other installations, as a
+/// versioned document of profiles.
+#[component]
+pub fn MappingProfiles() -> impl IntoView {
+    let session = use_session();
+    let profiles = RwSignal::new(Vec::<Profile>::new());
+    let selected = RwSignal::new(BTreeSet::<String>::new());
+    let format = RwSignal::new(FORMATS[0].0.to_string());
+    // The document read, sent back as it was once conflicts are resolved.
+    let pending = RwSignal::new(None::<(Value, ImportPreview)>);
+    let chosen = RwSignal::new(BTreeMap::<String, String>::new());
+    let status = RwSignal::new(None::<String>);
+    let history = RwSignal::new(None::<String>);
+
+    let load = move || {
+        spawn_local(async move {
+            match try_invoke("list_profiles", JsValue::NULL).await {
+                Ok(result) => profiles.set(serde_wasm_bindgen::from_value(result).unwrap_or_default()),
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+    load();
+
+    let export = move |_: MouseEvent| {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "vendors": selected.get_untracked(),
+                "format": format.get_untracked(),
+            }))
+            .unwrap();
+            match try_invoke("export_profiles", args).await {
+                Ok(result) => {
+                    if let Some(path) = result.as_string() {
+                        status.set(Some(format!("Exported to {}", path)));
+                    }
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+
+    let open = move |_: MouseEvent| {
+        spawn_local(async move {
+            match try_invoke("open_profiles", JsValue::NULL).await {
+                Ok(result) => {
+                    let Ok(Some((document, preview))) = serde_wasm_bindgen::from_value::<Option<(Value, ImportPreview)>>(result) else {
+                        return;
+                    };
+                    chosen.set(
+                        preview
+                            .profiles
+                            .iter()
+                            .filter_map(|p| {
+                                let options = resolutions(&p.status, session.can_untracked(Permission::CreateVendor));
+                                Some((p.incoming.vendor_name.clone(), options.first()?.0.to_string()))
+                            })
+                            .collect(),
+                    );
+                    pending.set(Some((document, preview)));
+                    status.set(None);
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+
+    let apply = move |_: MouseEvent| {
+        let Some((document, _)) = pending.get_untracked() else {
+            return;
+        };
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "document": document,
+                "resolutions": chosen.get_untracked(),
+            }))
+            .unwrap();
+            match try_invoke("import_profiles", args).await {
+                Ok(result) => {
+                    let summary: ImportSummary = serde_wasm_bindgen::from_value(result).unwrap_or_default();
+                    status.set(Some(format!("Imported {} profiles, skipped {}", summary.imported.len(), summary.skipped.len())));
+                    pending.set(None);
+                    load();
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+
+    view! {
+        <div class="upload-container profiles-container">
+            <h1>"Mapping Profiles"</h1>
+            <div class="audit-actions">
+                <select class="vendor-select" on:change=move |ev| format.set(event_target_value(&ev))>
+                    {FORMATS.map(|(value, label)| view! { <option value=value>{label}</option> }).collect_view()}
+                </select>
+                <button class="preview-button" on:click=export>
+                    {move || match selected.with(BTreeSet::len) {
+                        0 => "Export all".to_string(),
+                        n => format!("Export {} selected", n),
+                    }}
+                </button>
+                <Show when=move || session.can(Permission::EditMapping)>
+                    <button class="preview-button" on:click=open>"Import…"</button>
+                </Show>
+            </div>
+            {move || status.get().map(|status| view! { <p class="batch-summary">{status}</p> })}
+            {move || pending.get().map(|(_, preview)| view! {
+                <ImportReview
+                    preview=preview
+                    chosen=chosen
+                    on_apply=Callback::new(apply)
+                    on_cancel=Callback::new(move |_: MouseEvent| pending.set(None))
+                />
+            })}
+            {move || history.get().map(|vendor| view! {
+                <ProfileHistory
+                    vendor=vendor
+                    can_rollback=session.can_untracked(Permission::EditMapping)
+                    on_changed=Callback::new(move |()| load())
+                    on_close=Callback::new(move |_: MouseEvent| history.set(None))
+                />
+            })}
+            <table class="diff-table">
+                <tr>
+                    <th></th>
+                    <th>"Vendor"</th>
+                    <th>"Dialect"</th>
+                    <th>"Mapping"</th>
+                    <th></th>
+                </tr>
+                {move || profiles
+                    .get()
+                    .into_iter()
+                    .map(|profile| {
+                        let name = profile.vendor_name.clone();
+                        let checked = {
+                            let name = name.clone();
+                            move || selected.with(|selected| selected.contains(&name))
+                        };
+                        view! {
+                            <tr>
+                                <td>
+                                    <input
+                                        type="checkbox"
+                                        prop:checked=checked
+                                        on:change=move |ev| {
+                                            let on = event_target_checked(&ev);
+                                            selected.update(|selected| {
+                                                if on {
+                                                    selected.insert(name.clone());
+                                                } else {
+                                                    selected.remove(&name);
+                                                }
+                                            });
+                                        }
+                                    />
+                                </td>
+                                <td>{profile.vendor_name.clone()}</td>
+                                <td class="batch-detail">{describe_dialect(&profile.dialect)}</td>
+                                <td><MappingLines mapping=profile.mapping other=None /></td>
+                                <td>
+                                    <button
+                                        class="preview-button"
+                                        on:click=move |_: MouseEvent| history.set(Some(profile.vendor_name.clone()))
+                                    >
+                                        "History"
+                                    </button>
+                                </td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </table>
+        </div>
+    }
+}
+
+/// What importing a document would do, with a resolution to pick for each
+/// vendor that is new or differs from its saved profile.
+#[component]
+fn ImportReview(preview: ImportPreview, chosen: RwSignal<BTreeMap<String, String>>, on_apply: Callback<MouseEvent>, on_cancel: Callback<MouseEvent>) -> impl IntoView {
+    let can_create = use_session().can_untracked(Permission::CreateVendor);
+    view! {
+        <div class="profile-import">
+            <h2>"Import"</h2>
+            <ul class="validation-warnings">
+                {preview.warnings.into_iter().map(|warning| view! { <li>{warning}</li> }).collect_view()}
+            </ul>
+            <table class="diff-table">
+                <tr>
+                    <th>"Vendor"</th>
+                    <th>"Saved"</th>
+                    <th>"Incoming"</th>
+                    <th>"Action"</th>
+                </tr>
+                {preview
+                    .profiles
+                    .into_iter()
+                    .map(|checked| {
+                        let name = checked.incoming.vendor_name.clone();
+                        let options = resolutions(&checked.status, can_create);
+                        let current_mapping = checked.current.as_ref().map(|current| current.mapping.clone());
+                        let action = if options.is_empty() {
+                            view! { <span class=format!("import-status {}", checked.status)>{checked.status.clone()}</span> }.into_any()
+                        } else {
+                            let picked = chosen.with_untracked(|chosen| chosen.get(&name).cloned()).unwrap_or_default();
+                            view! {
+                                <select class="vendor-select" on:change=move |ev| {
+                                    let value = event_target_value(&ev);
+                                    chosen.update(|chosen| {
+                                        chosen.insert(name.clone(), value);
+                                    });
+                                }>
+                                    {options
+                                        .iter()
+                                        .map(|(value, label)| view! { <option value=*value selected=picked == *value>{*label}</option> })
+                                        .collect_view()}
+                                </select>
+                            }
+                            .into_any()
+                        };
+                        view! {
+                            <tr>
+                                <td>
+                                    {checked.incoming.vendor_name.clone()}
+                                    <ul class="validation-errors">
+                                        {checked.errors.into_iter().map(|error| view! { <li>{error}</li> }).collect_view()}
+                                    </ul>
+                                    <ul class="validation-warnings">
+                                        {checked.warnings.into_iter().map(|warning| view! { <li>{warning}</li> }).collect_view()}
+                                    </ul>
+                                </td>
+                                <td>
+                                    {checked.current.map(|current| view! {
+                                        <MappingLines mapping=current.mapping other=Some(checked.incoming.mapping.clone()) />
+                                    })}
+                                </td>
+                                <td><MappingLines mapping=checked.incoming.mapping other=current_mapping /></td>
+                                <td>{action}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </table>
+            <div class="audit-actions">
+                <button class="submit-button" on:click=move |ev| on_apply.run(ev)>"Apply"</button>
+                <button class="preview-button" on:click=move |ev| on_cancel.run(ev)>"Cancel"</button>
+            </div>
+        </div>
+    }
+}
+
+/// One line per mapped field; lines that differ from `other` are marked.
+#[component]
+fn MappingLines(mapping: BTreeMap<String, String>, other: Option<BTreeMap<String, String>>) -> impl IntoView {
+    view! {
+        <ul class="mapping-lines">
+            {mapped(&mapping)
+                .into_iter()
+                .map(|(target, source)| {
+                    let changed = other.as_ref().is_some_and(|other| other.get(&target) != Some(&source));
+                    view! { <li class:changed=changed>{format!("{} ← {}", target, source)}</li> }
+                })
+                .collect_view()}
+        </ul>
+    }
+}
+
+/// Every version of one vendor's profile, what changed between any two of
+/// them, and rolling back to an earlier one.
+#[component]
+fn ProfileHistory(vendor: String, can_rollback: bool, on_changed: Callback<()>, on_close: Callback<MouseEvent>) -> impl IntoView {
+    let vendor = StoredValue::new(vendor);
+    let versions = RwSignal::new(Vec::<VersionUse>::new());
+    let from = RwSignal::new(0u32);
+    let to = RwSignal::new(0u32);
+    let changes = RwSignal::new(Vec::<FieldChange>::new());
+    let note = RwSignal::new(String::new());
+    let status = RwSignal::new(None::<String>);
+
+    // Starts by comparing the profile in use with the one before it.
+    let load = move || {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "vendorName": vendor.get_value() })).unwrap();
+            match try_invoke("profile_versions", args).await {
+                Ok(result) => {
+                    let loaded: Vec<VersionUse> = serde_wasm_bindgen::from_value(result).unwrap_or_default();
+                    to.set(loaded.first().map_or(0, |v| v.version));
+                    from.set(loaded.get(1).or(loaded.first()).map_or(0, |v| v.version));
+                    versions.set(loaded);
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+    load();
+
+    Effect::new(move |_| {
+        let (from, to) = (from.get(), to.get());
+        if from == 0 || from == to {
+            changes.set(Vec::new());
+            return;
+        }
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "vendorName": vendor.get_value(),
+                "from": from,
+                "to": to,
+            }))
+            .unwrap();
+            match try_invoke("profile_diff", args).await {
+                Ok(result) => changes.set(serde_wasm_bindgen::from_value(result).unwrap_or_default()),
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    });
+
+    let rollback = move |version: u32| {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "vendorName": vendor.get_value(),
+                "version": version,
+                "note": note.get_untracked(),
+            }))
+            .unwrap();
+            match try_invoke("rollback_profile", args).await {
+                Ok(result) => {
+                    let current = serde_wasm_bindgen::from_value::<u32>(result).unwrap_or_default();
+                    status.set(Some(format!("Version {} is now in use, as version {}", version, current)));
+                    note.set(String::new());
+                    load();
+                    on_changed.run(());
+                }
+                Err(err) => status.set(err.as_string()),
+            }
+        });
+    };
+
+    let version_select = move |picked: RwSignal<u32>| view! {
+        <select class="vendor-select" on:change=move |ev| picked.set(event_target_value(&ev).parse().unwrap_or_default())>
+            {move || versions
+                .get()
+                .into_iter()
+                .map(|v| view! { <option value=v.version selected=picked.get_untracked() == v.version>{format!("Version {}", v.version)}</option> })
+                .collect_view()}
+        </select>
+    };
+
+    view! {
+        <div class="profile-history">
+            <div class="catalog-detail-header">
+                <h2>{format!("{} · profile history", vendor.get_value())}</h2>
+                <button class="preview-button" on:click=move |ev| on_close.run(ev)>"Close"</button>
+            </div>
+            {move || status.get().map(|status| view! { <p class="batch-summary">{status}</p> })}
+            <Show when=move || versions.with(Vec::is_empty)>
+                <p class="notification-empty">"No versions are recorded until the mapping is next saved"</p>
+            </Show>
+            <table class="diff-table">
+                <tr>
+                    <th>"Version"</th>
+                    <th>"Saved"</th>
+                    <th>"By"</th>
+                    <th>"Note"</th>
+                    <th>"Uploads"</th>
+                    <th></th>
+                </tr>
+                {move || {
+                    let current = versions.with(|versions| versions.first().map(|v| v.version));
+                    versions
+                        .get()
+                        .into_iter()
+                        .map(|v| {
+                            let saved = if v.timestamp == 0 { "Before versions were kept".to_string() } else { format_timestamp(v.timestamp) };
+                            let uploads = match v.last_upload {
+                                Some(last) => format!("{}, last {}", v.uploads, format_timestamp(last)),
+                                None => v.uploads.to_string(),
+                            };
+                            let version = v.version;
+                            view! {
+                                <tr>
+                                    <td>{version}{(current == Some(version)).then_some(" (in use)")}</td>
+                                    <td class="batch-detail">{saved}</td>
+                                    <td>{if v.author.is_empty() { "unknown".to_string() } else { v.author }}</td>
+                                    <td>{v.note}</td>
+                                    <td class="batch-detail">{uploads}</td>
+                                    <td>
+                                        {(can_rollback && current != Some(version)).then(|| view! {
+                                            <button class="preview-button" on:click=move |_: MouseEvent| rollback(version)>"Roll back"</button>
+                                        })}
+                                    </td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </table>
+            <Show when=move || can_rollback>
+                <input
+                    type="text"
+                    class="text-input"
+                    placeholder="Rollback note (optional)"
+                    prop:value=move || note.get()
+                    on:input=move |ev| note.set(event_target_value(&ev))
+                />
+            </Show>
+            <div class="audit-actions">
+                "Compare " {version_select(from)} " with " {version_select(to)}
+            </div>
+            <Show
+                when=move || !changes.with(Vec::is_empty)
+                fallback=|| view! { <p class="diff-summary">"No differences"</p> }
+            >
+                <table class="diff-table diff-changed">
+                    <tr>
+                        <th>"Field"</th>
+                        <th>"Before"</th>
+                        <th>"After"</th>
+                    </tr>
+                    {move || changes
+                        .get()
+                        .into_iter()
+                        .map(|change| view! {
+                            <tr>
+                                <td>{change.field}</td>
+                                <td>{or_dash(change.old)}</td>
+                                <td>{or_dash(change.new)}</td>
+                            </tr>
+                        })
+                        .collect_view()}
+                </table>
+            </Show>
+        </div>
+    }
+}
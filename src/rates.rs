@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use leptos::prelude::Callback;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::MouseEvent;
+
+use crate::components::*;
+use crate::login::{use_session, Permission};
+use crate::notify::{use_notifications, Notice};
+use crate::watch::format_timestamp;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RateTable {
+    pub base: String,
+    pub convert_to: String,
+    pub rates: BTreeMap<String, f64>,
+    pub updated: u64,
+}
+
+/// A rate as typed, kept as text until it is saved.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct RateRow {
+    currency: String,
+    rate: String,
+}
+
+#[component]
+pub fn CurrencyRates() -> impl IntoView {
+    let table = RwSignal::new(RateTable::default());
+    let rows = RwSignal::new(Vec::<RateRow>::new());
+    let notifications = use_notifications();
+    let session = use_session();
+
+    let show = move |loaded: RateTable| {
+        rows.set(
+            loaded
+                .rates
+                .iter()
+                .map(|(currency, rate)| RateRow { currency: currency.clone(), rate: rate.to_string() })
+                .collect(),
+        );
+        table.set(loaded);
+    };
+
+    spawn_local(async move {
+        match try_invoke("get_rates", JsValue::NULL).await {
+            Ok(result) => show(serde_wasm_bindgen::from_value(result).unwrap_or_default()),
+            Err(err) => notifications.notify(Notice::error("Loading currency rates failed").details(err.as_string().unwrap_or_default())),
+        }
+    });
+
+    let on_change = Callback::new(move |(index, row): (usize, RateRow)| {
+        rows.update(|rows| rows[index] = row);
+    });
+
+    let on_remove = Callback::new(move |index: usize| {
+        rows.update(|rows| {
+            rows.remove(index);
+        });
+    });
+
+    let on_add = move |_: MouseEvent| {
+        rows.update(|rows| rows.push(RateRow::default()));
+    };
+
+    let on_save = move |_: MouseEvent| {
+        let mut rates = BTreeMap::new();
+        for row in rows.get_untracked().into_iter().filter(|row| !row.currency.trim().is_empty()) {
+            match row.rate.trim().parse::<f64>() {
+                Ok(rate) => {
+                    rates.insert(row.currency.trim().to_string(), rate);
+                }
+                Err(_) => {
+                    notifications.notify(Notice::error("Saving currency rates failed").details(format!(
+                        "The rate for {} is not a number",
+                        row.currency.trim()
+                    )));
+                    return;
+                }
+            }
+        }
+        let current = RateTable { rates, ..table.get_untracked() };
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "rates": current })).unwrap();
+            match try_invoke("save_rates", args).await {
+                Ok(result) => {
+                    show(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+                    notifications.notify(Notice::info("Currency rates saved"));
+                }
+                Err(err) => notifications.notify(Notice::error("Saving currency rates failed").details(err.as_string().unwrap_or_default())),
+            }
+        });
+    };
+
+    view! {
+        <div class="upload-container rates-container">
+            <h1>"Currency Rates"</h1>
+            <p class="batch-detail">
+                "Each rate is how many units of the base currency one unit of that currency is worth. Uploads convert prices with the rates below when a currency to convert to is set."
+            </p>
+            <div class="rates-settings">
+                <label>
+                    "Base currency "
+                    <input
+                        type="text"
+                        class="text-input"
+                        prop:value=move || table.with(|table| table.base.clone())
+                        on:change=move |ev| table.update(|table| table.base = event_target_value(&ev))
+                    />
+                </label>
+                <label>
+                    "Convert prices to "
+                    <input
+                        type="text"
+                        class="text-input"
+                        placeholder="Leave empty to send prices as they are"
+                        prop:value=move || table.with(|table| table.convert_to.clone())
+                        on:change=move |ev| table.update(|table| table.convert_to = event_target_value(&ev))
+                    />
+                </label>
+            </div>
+            <div class="rates-list">
+                {move || rows
+                    .get()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, row)| view! { <RateRowEditor index=index row=row on_change=on_change on_remove=on_remove /> })
+                    .collect_view()}
+            </div>
+            <p class="file-stats">
+                {move || match table.with(|table| table.updated) {
+                    0 => "Never saved".to_string(),
+                    updated => format!("Updated {}", format_timestamp(updated)),
+                }}
+            </p>
+            <Show
+                when=move || session.can(Permission::EditMapping)
+                fallback=|| view! { <p class="permission-note">"Only operators who can edit mappings can change rates."</p> }
+            >
+                <button class="preview-button" on:click=on_add>"+ Add Rate"</button>
+                <button class="submit-button" on:click=on_save>"Save"</button>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+fn RateRowEditor(index: usize, row: RateRow, on_change: Callback<(usize, RateRow)>, on_remove: Callback<usize>) -> impl IntoView {
+    let row = StoredValue::new(row);
+    let update = move |f: fn(&mut RateRow, String), value: String| {
+        let mut updated = row.get_value();
+        f(&mut updated, value);
+        on_change.run((index, updated));
+    };
+
+    view! {
+        <div class="rate-row">
+            <input
+                type="text"
+                class="text-input"
+                placeholder="Currency, e.g. EUR"
+                prop:value=row.get_value().currency
+                on:change=move |ev| update(|row, currency| row.currency = currency, event_target_value(&ev))
+            />
+            <input
+                type="text"
+                class="text-input"
+                placeholder="Rate"
+                prop:value=row.get_value().rate
+                on:change=move |ev| update(|row, rate| row.rate = rate, event_target_value(&ev))
+            />
+            <CloseButton on_click=Callback::new(move |_: MouseEvent| on_remove.run(index)) />
+        </div>
+    }
+}
@@ -1,25 +1,43 @@
 use leptos::*;
-use leptos_router::{components::{Outlet, Route, Router, Routes, A}, *};
+use leptos_router::{components::{Outlet, ProtectedParentRoute, Route, Router, Routes, A}, *};
+use leptos::task::spawn_local;
 use leptos::prelude::*;
 use leptos::prelude::Callback;
 use wasm_bindgen::prelude::*;
 use web_sys::{Event, MouseEvent};
 
+use crate::audit::AuditTrail;
+use crate::batch::BatchUpload;
+use crate::catalog::CatalogBrowser;
+use crate::dashboard::Dashboard;
+use crate::login::{provide_session, use_session, Login, Permission};
+use crate::profiles::MappingProfiles;
+use crate::rates::CurrencyRates;
+use crate::notify::{provide_notifications, use_notifications, Notice, NotificationBell, NotificationDrawer, ToastStack};
 use crate::upload::Upload;
+use crate::watch::WatchFolders;
 
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
     async fn invoke(cmd: &str, args: JsValue) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
 }
 
 #[component]
 pub fn Landingpage() -> impl IntoView {
+    provide_session();
+    provide_notifications();
+
     view! {
         <Router>
             <div class="home-page">
                 <TopBar/>
                 <Body/>
+                <NotificationDrawer/>
+                <ToastStack/>
             </div>
         </Router>
     }
@@ -27,18 +45,46 @@ pub fn Landingpage() -> impl IntoView {
 
 #[component]
 pub fn TopBar() -> impl IntoView {
+    let session = use_session();
+    let notifications = use_notifications();
+
+    let collect_diagnostics = move |_| {
+        spawn_local(async move {
+            match try_invoke("collect_diagnostics", JsValue::NULL).await {
+                Ok(result) => {
+                    if let Ok(Some(path)) = serde_wasm_bindgen::from_value::<Option<String>>(result) {
+                        notifications.notify(Notice::success(format!("Diagnostics saved to {}", path)));
+                    }
+                }
+                Err(err) => notifications.notify(Notice::error("Collecting diagnostics failed").details(err.as_string().unwrap_or_default())),
+            }
+        });
+    };
+
     view! {
         <div class="top-bar">
-            Top Bar
+            <span>Top Bar</span>
+            <div class="top-bar-operator">
+                <NotificationBell/>
+                {move || session.operator.get().map(|operator| view! {
+                    <button on:click=collect_diagnostics>"Collect diagnostics"</button>
+                    <span>"Signed in as " {operator.username} " (" {operator.role} ")"</span>
+                    <button on:click=move |_| session.sign_out()>"Sign out"</button>
+                })}
+            </div>
         </div>
     }
 }
 
 #[component]
 pub fn Body() -> impl IntoView {
+    let session = use_session();
+
     view! {
         <div class="main-content">
-            <Sidebar/>
+            <Show when=move || session.operator.get().is_some()>
+                <Sidebar/>
+            </Show>
             <ContentArea/>
         </div>
     }
@@ -46,11 +92,27 @@ pub fn Body() -> impl IntoView {
 
 #[component]
 pub fn ContentArea() -> impl IntoView {
+    let session = use_session();
+
     view! {
         <div class="content-area">
             <Routes fallback=|| view! { <NotFound/> }>
-                <Route path=path!("/") view=Upload/>
-                <Route path=path!("/apps") view=AppsPageContent/>
+                <Route path=path!("/login") view=Login/>
+                <ProtectedParentRoute
+                    path=path!("")
+                    view=Outlet
+                    condition=move || session.signed_in()
+                    redirect_path=|| "/login"
+                >
+                    <Route path=path!("/") view=Dashboard/>
+                    <Route path=path!("/upload") view=Upload/>
+                    <Route path=path!("/batch") view=BatchUpload/>
+                    <Route path=path!("/watch") view=WatchFolders/>
+                    <Route path=path!("/catalog") view=CatalogBrowser/>
+                    <Route path=path!("/profiles") view=MappingProfiles/>
+                    <Route path=path!("/rates") view=CurrencyRates/>
+                    <Route path=path!("/audit") view=AuditTrail/>
+                </ProtectedParentRoute>
             </Routes>
         </div>
     }
@@ -58,24 +120,68 @@ pub fn ContentArea() -> impl IntoView {
 
 #[component]
 pub fn Sidebar() -> impl IntoView {
+    let session = use_session();
+
     view! {
         <div class="sidebar">
-            <button>
-                + New Vendor
-            </button>
-            <div class="separator"></div>
+            <Show when=move || session.can(Permission::CreateVendor)>
+                <A href="/upload">
+                    <button>
+                        + New Vendor
+                    </button>
+                </A>
+                <div class="separator"></div>
+            </Show>
             <A href="/">
                 <div class="menu-item link">
                     <span>HOME</span>
                     <span>Home</span>
                 </div>
             </A>
-            <A href="/apps">
+            <A href="/upload">
+                <div class="menu-item link">
+                    <span>ICON</span>
+                    <span>Vendor Setup</span>
+                </div>
+            </A>
+            <A href="/batch">
+                <div class="menu-item link">
+                    <span>ICON</span>
+                    <span>Batch Upload</span>
+                </div>
+            </A>
+            <A href="/watch">
+                <div class="menu-item link">
+                    <span>ICON</span>
+                    <span>Watch Folders</span>
+                </div>
+            </A>
+            <A href="/catalog">
+                <div class="menu-item link">
+                    <span>ICON</span>
+                    <span>Catalog</span>
+                </div>
+            </A>
+            <A href="/profiles">
+                <div class="menu-item link">
+                    <span>ICON</span>
+                    <span>Mapping Profiles</span>
+                </div>
+            </A>
+            <A href="/rates">
                 <div class="menu-item link">
                     <span>ICON</span>
-                    <span>Apps</span>
+                    <span>Currency Rates</span>
                 </div>
-            </A>        
+            </A>
+            <Show when=move || session.can(Permission::ViewAudit)>
+                <A href="/audit">
+                    <div class="menu-item link">
+                        <span>ICON</span>
+                        <span>Audit Log</span>
+                    </div>
+                </A>
+            </Show>        
         </div>
     }
 }
@@ -90,18 +196,3 @@ pub fn NotFound() -> impl IntoView {
     }
 }
 
-#[component]
-fn HomePageContent() -> impl IntoView {
-    view! { 
-        <h1>"Home Page"</h1>
-        <p>"Welcome to the Home Page!"</p>
-    }
-}
-
-#[component]
-fn AppsPageContent() -> impl IntoView {
-    view! {
-        <h1>"Apps Page"</h1>
-        <p>"Here are your Apps!"</p>    
-    }
-}
\ No newline at end of file
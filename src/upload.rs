@@ -1,202 +1,534 @@
-use std::io::Cursor;
-use csv::ReaderBuilder;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use gloo_timers::future::TimeoutFuture;
 use leptos::task::spawn_local;
 use leptos::prelude::*;
+use leptos_router::hooks::use_query_map;
 use leptos::prelude::Callback;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
-use web_sys::{console, FileList, HtmlInputElement, MouseEvent};
-use gloo_file::futures::read_as_bytes;
-use gloo_file:: Blob as GlooBlob;
+use web_sys::{Event, MouseEvent};
 
 use crate::components::*;
+use crate::log;
+use crate::login::{use_session, Permission};
+use crate::notify::{use_notifications, Notice};
+use crate::wizard::{location_field, Dialect, Draft, Inspection, Step, Wizard, WizardEvent, CURRENCY, TARGET_FIELDS};
 
 #[wasm_bindgen]
 extern "C" {
-    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
-    async fn invoke(cmd: &str, args: JsValue) -> JsValue;
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct VendorInfo {
-    vendor_name: String,
-    password: String,
-    file_mappings: String,
-    file_bytes: Vec<u8>,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VendorInfo {
+    pub vendor_name: String,
+    pub password: String,
+    pub file_mappings: String,
+    pub file_bytes: Vec<u8>,
+    pub file_path: Option<String>,
+    pub delta_only: bool,
+    /// Unset to use the vendor's saved dialect.
+    pub dialect: Option<Dialect>,
+    pub change_note: String,
 }
 
+/// The selected stock file. Files picked through the native dialog or dropped
+/// from the OS stay on disk and are read by the backend; only files dropped
+/// inside the webview are held in memory.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileRef {
+    Path(String),
+    Bytes(Vec<u8>),
+}
+
+impl FileRef {
+    pub fn into_parts(self) -> (Vec<u8>, Option<String>) {
+        match self {
+            FileRef::Path(path) => (Vec::new(), Some(path)),
+            FileRef::Bytes(bytes) => (bytes, None),
+        }
+    }
+}
+
+/// Header, first rows and counts of the selected file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FileSummary {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub headers: Vec<String>,
+    pub sample: Vec<Vec<String>>,
+    pub row_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Row {
+    pub key: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RowChange {
+    pub key: String,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct DatasetDiff {
+    pub added: Vec<Row>,
+    pub removed: Vec<Row>,
+    pub changed: Vec<RowChange>,
+}
+
+/// How long the setup must stay unchanged before it is autosaved.
+const AUTOSAVE_DELAY_MS: u32 = 1_000;
+
+/// Set once the draft left by the last run has been offered, so coming back
+/// to a page does not offer it again. A reload starts over.
+static RECOVERY_OFFERED: AtomicBool = AtomicBool::new(false);
+
+/// The newest draft, the first time any page asks.
+pub fn take_recovery_offer(drafts: &[Draft]) -> Option<Draft> {
+    if RECOVERY_OFFERED.swap(true, Ordering::Relaxed) {
+        return None;
+    }
+    drafts.first().cloned()
+}
+
+fn new_draft_id() -> String {
+    (js_sys::Date::now() as u64).to_string()
+}
+
+pub fn describe_dialect(dialect: &Dialect) -> String {
+    let delimiter = match dialect.delimiter {
+        '\t' => "tab".to_string(),
+        c => format!("\"{}\"", c),
+    };
+    let decimal = match dialect.decimal {
+        Some(decimal) => format!("\"{}\" decimals", decimal),
+        None => "guessed decimals".to_string(),
+    };
+    format!("{} separated, {} quotes, {}, {}", delimiter, dialect.quote, dialect.encoding, decimal)
+}
+
+/// Vendor setup as a wizard; `Wizard` holds all of its state.
 #[component]
 pub fn Upload() -> impl IntoView {
+    let wizard = RwSignal::new(Wizard::new(new_draft_id()));
+    let vendors = RwSignal::new(Vec::<String>::new());
+    let drafts = RwSignal::new(Vec::<Draft>::new());
+    let inspect_error = RwSignal::new(None::<String>);
+    let recovered = RwSignal::new(None::<Draft>);
+    let notifications = use_notifications();
+    let session = use_session();
 
-    let (is_new_vendor, set_is_new_vendor) = signal(false);
-    let selected_index = RwSignal::new(0 as usize);
-    let vendor_name = RwSignal::new(String::new());
-    let password = RwSignal::new(String::new());
-    let file_name = RwSignal::new(String::new());
-    let file_signal = RwSignal::new(None::<Vec<u8>>);
-    let (column_mappings, set_column_mappings) = signal(String::new());
-    let (column_headers, set_column_headers) = signal(Vec::<String>::new());
-    let notifications = RwSignal::new(None::<String>);
-
-    let on_select = Callback::new(move |index: usize| {
-        if index == 1 {
-            set_is_new_vendor.set(!is_new_vendor.get()); // This will toggle between true and false
-        } else {
-            set_is_new_vendor.set(false); // Optionally handle other indexes
+    let send = move |event: WizardEvent| wizard.update(|wizard| wizard.apply(event));
+    let step = Memo::new(move |_| wizard.with(|wizard| wizard.step));
+    let is_new_vendor = Signal::derive(move || wizard.with(|wizard| wizard.is_new_vendor));
+    let payload_args = move || {
+        let payload = wizard.with_untracked(|wizard| wizard.payload());
+        serde_wasm_bindgen::to_value(&serde_json::json!({ "payload": payload })).unwrap()
+    };
+
+    let load_vendors = move || {
+        spawn_local(async move {
+            if let Ok(result) = try_invoke("list_vendors", JsValue::NULL).await {
+                vendors.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+            }
+        });
+    };
+    let load_drafts = move || {
+        spawn_local(async move {
+            if let Ok(result) = try_invoke("list_drafts", JsValue::NULL).await {
+                drafts.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+            }
+        });
+    };
+    load_vendors();
+    // `?draft=` comes from the dashboard's offer to resume.
+    let requested = use_query_map().with_untracked(|query| query.get("draft"));
+    spawn_local(async move {
+        if let Ok(result) = try_invoke("list_drafts", JsValue::NULL).await {
+            let list: Vec<Draft> = serde_wasm_bindgen::from_value(result).unwrap_or_default();
+            match requested.and_then(|id| list.iter().find(|draft| draft.id == id).cloned()) {
+                Some(draft) => {
+                    RECOVERY_OFFERED.store(true, Ordering::Relaxed);
+                    send(WizardEvent::Resume(draft));
+                }
+                None => recovered.set(take_recovery_offer(&list)),
+            }
+            drafts.set(list);
         }
     });
 
-    let options = vec![
-        "Existing Vendor".to_string(),
-        "New Vendor".to_string(),
-    ];
+    // Saves the setup once it has stopped changing, so closing or reloading
+    // the window loses at most the last moment. The password is never saved.
+    let draft = Memo::new(move |_| wizard.with(|wizard| (!wizard.is_blank()).then(|| wizard.draft())));
+    let autosave_generation = Rc::new(Cell::new(0u64));
+    Effect::new(move |_| {
+        let draft = draft.get();
+        let generation = autosave_generation.get() + 1;
+        autosave_generation.set(generation);
+        let Some(draft) = draft else { return };
+        let autosave_generation = autosave_generation.clone();
+        spawn_local(async move {
+            TimeoutFuture::new(AUTOSAVE_DELAY_MS).await;
+            if autosave_generation.get() != generation {
+                return;
+            }
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "draft": draft })).unwrap();
+            if let Err(err) = try_invoke("save_draft", args).await {
+                log::warn(format!("Autosaving draft {} failed: {}", draft.id, err.as_string().unwrap_or_default()));
+            }
+        });
+    });
 
-    let update_vendor_name = Callback::new(move |ev:web_sys::Event | {
-        let v = event_target_value(&ev);
-        vendor_name.set(v);
+    // Reads the file again whenever what it looks like may have changed. A
+    // reply to anything but the latest request is dropped.
+    let inspect_key = Memo::new(move |_| {
+        wizard.with(|wizard| {
+            (wizard.file.is_some() && (Step::Dialect..=Step::Validation).contains(&wizard.step))
+                .then(|| (wizard.vendor_name.clone(), wizard.file.clone(), wizard.dialect.clone(), wizard.file_mappings()))
+        })
+    });
+    Effect::new(move |_| {
+        let Some(key) = inspect_key.get() else { return };
+        let args = payload_args();
+        spawn_local(async move {
+            let result = try_invoke("inspect_upload", args).await;
+            if inspect_key.get_untracked().as_ref() != Some(&key) {
+                return;
+            }
+            match result.map(serde_wasm_bindgen::from_value::<Inspection>) {
+                Ok(Ok(inspection)) => {
+                    inspect_error.set(None);
+                    send(WizardEvent::Inspected(inspection));
+                }
+                Ok(Err(err)) => log::error(format!("Unexpected inspect_upload reply: {}", err)),
+                Err(err) => {
+                    let err = err.as_string().unwrap_or_default();
+                    log::warn(format!("Could not read {}: {}", wizard.with_untracked(|wizard| wizard.file_name.clone()), err));
+                    inspect_error.set(Some(err));
+                }
+            }
+        });
     });
 
-    let update_password = Callback::new(move |ev: web_sys::Event| {
-        let v = event_target_value(&ev);
-        password.set(v);
+    let choose_file = move |name: String, file: FileRef| {
+        send(WizardEvent::FileChosen(name, file));
+        let args = payload_args();
+        spawn_local(async move {
+            if let Ok(result) = try_invoke("sniff_dialect", args).await {
+                if let Ok(dialect) = serde_wasm_bindgen::from_value(result) {
+                    send(WizardEvent::DialectChanged(dialect));
+                }
+            }
+        });
+    };
+
+    let load_path = Callback::new(move |summary: FileSummary| choose_file(summary.name, FileRef::Path(summary.path)));
+
+    let load_bytes = Callback::new(move |(name, bytes): (String, Vec<u8>)| choose_file(name, FileRef::Bytes(bytes)));
+
+    let on_choose_file = Callback::new(move |_: MouseEvent| {
+        spawn_local(async move {
+            match try_invoke("open_file_dialog", JsValue::NULL).await {
+                Ok(result) => {
+                    if let Ok(Some(summary)) = serde_wasm_bindgen::from_value::<Option<FileSummary>>(result) {
+                        load_path.run(summary);
+                    }
+                }
+                Err(err) => notifications.notify(Notice::error("Could not open the file").details(err.as_string().unwrap_or_default())),
+            }
+        });
     });
 
-    let clear_selected_file = Callback::new(move |_: MouseEvent| {
-        file_signal.set(None);
-        file_name.set("".to_string());
+    let on_drop_rejected = Callback::new(move |msg: String| {
+        notifications.notify(Notice::warning(msg));
     });
 
-    let on_file_change = Callback::new(move |ev: JsValue| {
-        let ev: web_sys::Event = ev.into();
-        let input: HtmlInputElement = event_target(&ev);
-        let files: FileList = input.files().unwrap();
-        if let Some(f) = files.get(0) {
-            file_name.set(f.name());
-            let gloo_blob = GlooBlob::from(f); 
-            spawn_local(async move {
-                match read_as_bytes(&gloo_blob).await {
-                    Ok(bytes) => {
-                        file_signal.set(Some(bytes.clone()));
-                        // Read CSV headers
-                        let cursor = Cursor::new(bytes.clone());
-                        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(cursor);
-                        if let Ok(headers) = rdr.headers() {
-                            set_column_headers.set(headers.iter().map(|h| h.to_string()).collect());
-                        } else {
-                            eprintln!("Failed to read CSV headers");
-                        }
+    let on_preview = Callback::new(move |_: MouseEvent| {
+        let args = payload_args();
+        spawn_local(async move {
+            match try_invoke("preview_upload_diff", args).await {
+                Ok(result) => {
+                    if let Ok(diff) = serde_wasm_bindgen::from_value(result) {
+                        send(WizardEvent::Diffed(diff));
                     }
-                    Err(err) => eprintln!("File read error: {:?}", err),
                 }
-            });
-        } else {
-            file_name.set("".to_string());
-            file_signal.set(None);
-            set_column_headers.set(Vec::new());
-        }
+                Err(err) => notifications.notify(Notice::error("Preview failed").details(err.as_string().unwrap_or_default())),
+            }
+        });
     });
 
-    let update_column_mapping = Callback::new(move |(index, ev): (usize, String)| {
-        let pre_selected_columns = vec![
-            "File Key".to_string(),
-            "Catalog".to_string(),
-            "Brand".to_string(),
-            "Description".to_string(),
-            "Quantity".to_string(),
-            "Ecommerce".to_string(),
-        ];
-        set_column_mappings.update(move |mappings_string| {
-            let column_name = pre_selected_columns[index].clone();
-
-            let mut updated_mappings = mappings_string
-                .split(", ")
-                .filter(|s| !s.is_empty())
-                .map(|s| {
-                    let parts: Vec<&str> = s.split(':').collect();
-                    if parts.len() == 2 {
-                        (parts[0].to_string(), parts[1].to_string())
-                    } else {
-                        ("".to_string(), "".to_string())
-                    }
-                })
-                .collect::<std::collections::HashMap<String, String>>();
+    let on_save_draft = move |_: MouseEvent| {
+        let draft = wizard.with_untracked(|wizard| wizard.draft());
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "draft": draft })).unwrap();
+            match try_invoke("save_draft", args).await {
+                Ok(_) => {
+                    notifications.notify(Notice::success("Draft saved"));
+                    load_drafts();
+                }
+                Err(err) => notifications.notify(Notice::error("Saving the draft failed").details(err.as_string().unwrap_or_default())),
+            }
+        });
+    };
 
-            updated_mappings.insert(column_name, ev);
+    let on_resume = Callback::new(move |draft: Draft| {
+        inspect_error.set(None);
+        recovered.set(None);
+        send(WizardEvent::Resume(draft));
+    });
 
-            let new_mappings_string = updated_mappings
-                .iter()
-                .map(|(key, value)| format!("{}:{}", key, value))
-                .collect::<Vec<String>>()
-                .join(", ");
+    let on_discard = Callback::new(move |id: String| {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "id": id })).unwrap();
+            if let Err(err) = try_invoke("delete_draft", args).await {
+                notifications.notify(Notice::error("Discarding the draft failed").details(err.as_string().unwrap_or_default()));
+            }
+            load_drafts();
+        });
+    });
 
-            *mappings_string = new_mappings_string;
+    // Filled in below, so a failed upload's toast can offer to send it again.
+    let retry_submit = StoredValue::new(None::<Callback<(VendorInfo, String)>>);
+    let submit = Callback::new(move |(payload, draft_id): (VendorInfo, String)| {
+        let vendor = payload.vendor_name.clone();
+        let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "payload": payload })).unwrap();
+        spawn_local(async move {
+            match try_invoke("setup_new_user", args).await {
+                Ok(result) => {
+                    log::info(format!("Uploaded file for vendor {}", vendor));
+                    notifications.notify(Notice::success(result.as_string().unwrap_or_default()));
+                    // Restart first, so a pending autosave does not bring the draft back. A
+                    // retry that went through after the operator moved on leaves the wizard be.
+                    if wizard.with_untracked(|wizard| wizard.draft_id == draft_id) {
+                        send(WizardEvent::Restart(new_draft_id()));
+                    }
+                    let args = serde_wasm_bindgen::to_value(&serde_json::json!({ "id": draft_id })).unwrap();
+                    let _ = try_invoke("delete_draft", args).await;
+                    load_vendors();
+                    load_drafts();
+                }
+                Err(err) => {
+                    let err = err.as_string().unwrap_or_default();
+                    log::error(format!("setup_new_user failed: {}", err));
+                    // Sends what failed, whatever the wizard holds by then.
+                    let retry = Callback::new(move |()| {
+                        if let Some(submit) = retry_submit.get_value() {
+                            submit.run((payload.clone(), draft_id.clone()));
+                        }
+                    });
+                    notifications.notify(
+                        Notice::error(format!("Upload for {} failed", vendor))
+                            .details(err)
+                            .action("Retry", retry),
+                    );
+                }
+            }
         });
     });
+    retry_submit.set_value(Some(submit));
 
-    let on_submit = Callback::new(move |_: MouseEvent| {
-        spawn_local(async move {   
-            if let Some(file_bytes) = file_signal.get_untracked() {
-                let vendor_info = VendorInfo {
-                    vendor_name: vendor_name.get_untracked(),
-                    password: password.get_untracked(),
-                    file_mappings: column_mappings.get_untracked(),
-                    file_bytes,
-                }; 
-                let args = serde_wasm_bindgen::to_value(&serde_json::json!({
-                    "payload": vendor_info  // Wrap inside payload key
-                }))
-                .unwrap();
-                console::log_1(&format!("Serialized Args: {:?}", args).into()); 
-                let result = invoke("setup_new_user", args).await;  
-                console::log_1(&result);
-                vendor_name.set("".to_string());
-                password.set("".to_string());
-                file_name.set("".to_string());
-                file_signal.set(None);
-                set_column_headers.set(Vec::new());
-
-                notifications.set(Some(result.as_string().unwrap()));
-
-                spawn_local(async move {
-                    gloo_timers::future::TimeoutFuture::new(3000).await;
-                    notifications.set(None);
-                });
-            } else {
-                eprintln!("No file selected!");
-            }           
-        });      
+    let on_submit = Callback::new(move |_: MouseEvent| submit.run(wizard.with_untracked(|wizard| (wizard.payload(), wizard.draft_id.clone()))));
+
+    let on_export = Callback::new(move |(columns, format): (Vec<String>, String)| {
+        let payload = wizard.with_untracked(|wizard| wizard.payload());
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "payload": payload,
+                "columns": columns,
+                "format": format,
+            }))
+            .unwrap();
+            match try_invoke("export_upload", args).await {
+                Ok(result) => {
+                    if let Some(path) = result.as_string() {
+                        notifications.notify(Notice::success(format!("Exported to {}", path)));
+                    }
+                }
+                Err(err) => notifications.notify(Notice::error("Export failed").details(err.as_string().unwrap_or_default())),
+            }
+        });
     });
 
-    view! {
-        <div class="upload-container">
-            <h1>"New Vendor Setup"</h1>
+    let mut options = vec!["Existing Vendor".to_string()];
+    if session.can_untracked(Permission::CreateVendor) {
+        options.push("New Vendor".to_string());
+    }
+
+    let step_view = move |step: Step| match step {
+        Step::Vendor => view! {
             <RadioButtonList
-                options=options
-                selected_index=selected_index
-                set_is_new_vendor=set_is_new_vendor
-                on_select=on_select
+                options=options.clone()
+                selected_index=Signal::derive(move || usize::from(is_new_vendor.get()))
+                on_select=Callback::new(move |index: usize| send(WizardEvent::NewVendor(index == 1)))
             />
             <VendorForm
                 is_new_vendor=is_new_vendor
-                vendor_name=vendor_name
-                password=password
-                update_vendor_name=update_vendor_name
-                update_password=update_password
+                vendor_name=Signal::derive(move || wizard.with(|wizard| wizard.vendor_name.clone()))
+                password=Signal::derive(move || wizard.with(|wizard| wizard.password.clone()))
+                update_vendor_name=Callback::new(move |ev: Event| send(WizardEvent::VendorName(event_target_value(&ev))))
+                update_password=Callback::new(move |ev: Event| send(WizardEvent::Password(event_target_value(&ev))))
             />
-            <FileUploadLabel on_file_change=on_file_change />
-            <FileDisplaySection
-                file_signal=file_signal
-                file_name=file_name
-                clear_selected_file=clear_selected_file
+            <VendorSelect
+                is_new_vendor=is_new_vendor
+                vendors=vendors
+                vendor_name=Signal::derive(move || wizard.with(|wizard| wizard.vendor_name.clone()))
+                on_change=Callback::new(move |name: String| send(WizardEvent::VendorName(name)))
+            />
+            <DraftList
+                drafts=Signal::derive(move || {
+                    // The setup in progress is autosaved too; it is not offered to itself.
+                    let current = wizard.with(|wizard| wizard.draft_id.clone());
+                    drafts.get().into_iter().filter(|draft| draft.id != current).collect::<Vec<Draft>>()
+                })
+                on_resume=on_resume
+                on_discard=on_discard
             />
+        }
+        .into_any(),
+        Step::File => view! {
+            <DropZone on_drop_file=load_bytes on_drop_path=load_path on_reject=on_drop_rejected>
+                <FilePickerButton on_click=on_choose_file />
+            </DropZone>
+            {move || wizard.with(|wizard| wizard.file.is_some()).then(|| view! {
+                <FileDisplay
+                    file_name=Signal::derive(move || wizard.with(|wizard| wizard.file_name.clone()))
+                    on_clear=Callback::new(move |_: MouseEvent| send(WizardEvent::FileCleared))
+                />
+            })}
+        }
+        .into_any(),
+        Step::Dialect => view! {
+            <DialectForm
+                dialect=Signal::derive(move || wizard.with(|wizard| wizard.dialect.clone().unwrap_or_default()))
+                on_change=Callback::new(move |dialect: Dialect| send(WizardEvent::DialectChanged(dialect)))
+            />
+            <FilePreview file_summary=Signal::derive(move || wizard.with(|wizard| wizard.summary.clone())) />
+        }
+        .into_any(),
+        Step::Mapping => view! {
             <ColumnMappingList
-                is_new_vendor=is_new_vendor
-                file_signal=file_signal
-                column_headers=column_headers
-                update_column_mapping=update_column_mapping
+                column_headers=Signal::derive(move || {
+                    wizard.with(|wizard| wizard.summary.as_ref().map(|summary| summary.headers.clone()).unwrap_or_default())
+                })
+                mappings=Signal::derive(move || wizard.with(|wizard| wizard.mappings.clone()))
+                update_column_mapping=Callback::new(move |(target, source): (String, String)| send(WizardEvent::Mapped(target, source)))
             />
-            <SubmitButton on_submit=on_submit />
-            <NotifyPopup notification=notifications />
+        }
+        .into_any(),
+        Step::Validation => view! {
+            <ValidationSummary report=Signal::derive(move || wizard.with(|wizard| wizard.report.clone())) />
+        }
+        .into_any(),
+        Step::Review => {
+            let review = wizard.get_untracked();
+            let vendor = if review.is_new_vendor { format!("{} (new)", review.vendor_name) } else { review.vendor_name.clone() };
+            let rows = review.report.as_ref().map(|report| report.row_count).unwrap_or_default();
+            view! {
+                <dl class="wizard-review">
+                    <dt>"Vendor"</dt>
+                    <dd>{vendor}</dd>
+                    <dt>"File"</dt>
+                    <dd>{format!("{} ({} rows)", review.file_name, rows)}</dd>
+                    <dt>"Dialect"</dt>
+                    <dd>{describe_dialect(&review.dialect.clone().unwrap_or_default())}</dd>
+                    <dt>"Mapping"</dt>
+                    <dd>{review.file_mappings()}</dd>
+                </dl>
+                <Show when=move || !is_new_vendor.get()>
+                    <DiffView
+                        diff=Signal::derive(move || wizard.with(|wizard| wizard.diff.clone()))
+                        delta_only=Signal::derive(move || wizard.with(|wizard| wizard.delta_only))
+                        on_delta_only=Callback::new(move |delta_only: bool| send(WizardEvent::DeltaOnly(delta_only)))
+                        on_preview=on_preview
+                    />
+                </Show>
+                <label class="wizard-note">
+                    "Change note"
+                    <input
+                        type="text"
+                        class="text-input"
+                        placeholder="Why the mapping or dialect changed (optional)"
+                        prop:value=move || wizard.with(|wizard| wizard.change_note.clone())
+                        on:input=move |ev| send(WizardEvent::ChangeNote(event_target_value(&ev)))
+                    />
+                </label>
+                <ExportPanel
+                    available=Signal::derive(move || wizard.with(|wizard| {
+                        let locations = wizard.report.as_ref().map(|report| report.locations.clone()).unwrap_or_default();
+                        let priced = wizard.report.as_ref().is_some_and(|report| !report.currencies.is_empty());
+                        TARGET_FIELDS
+                            .into_iter()
+                            .filter(|field| wizard.mappings.contains_key(*field) || (*field == "Quantity" && !locations.is_empty()))
+                            .chain(priced.then_some(CURRENCY))
+                            .map(String::from)
+                            .chain(locations.iter().map(|location| location_field(location)))
+                            .collect::<Vec<String>>()
+                    }))
+                    on_export=on_export
+                />
+                <SubmitButton on_submit=on_submit />
+            }
+            .into_any()
+        }
+    };
+
+    view! {
+        <div class="upload-container wizard">
+            <h1>"Vendor Setup"</h1>
+            <DraftRecovery draft=recovered on_resume=on_resume on_dismiss=Callback::new(move |()| recovered.set(None)) />
+            <ol class="wizard-steps">
+                {Step::ALL.map(|s| view! {
+                    <li>
+                        <button
+                            class="wizard-step"
+                            class:current=move || step.get() == s
+                            disabled=move || !wizard.with(|wizard| wizard.can_reach(s))
+                            on:click=move |_: MouseEvent| send(WizardEvent::GoTo(s))
+                        >
+                            {s.title()}
+                        </button>
+                    </li>
+                }).collect_view()}
+            </ol>
+            <div class="wizard-body">{move || step_view(step.get())}</div>
+            {move || inspect_error.get().map(|err| view! { <p class="validation-errors">{err}</p> })}
+            <div class="wizard-nav">
+                <button
+                    class="preview-button"
+                    disabled=move || step.get() == Step::Vendor
+                    on:click=move |_: MouseEvent| send(WizardEvent::Back)
+                >
+                    "Back"
+                </button>
+                <button class="preview-button" on:click=on_save_draft>"Save Draft"</button>
+                <Show when=move || step.get() != Step::Review>
+                    <button
+                        class="submit-button"
+                        disabled=move || wizard.with(|wizard| wizard.blocker(wizard.step).is_some())
+                        on:click=move |_: MouseEvent| send(WizardEvent::Next)
+                    >
+                        "Next"
+                    </button>
+                </Show>
+            </div>
+            {move || wizard
+                .with(|wizard| wizard.blocker(wizard.step))
+                .map(|blocker| view! { <p class="wizard-hint">{blocker}</p> })}
         </div>
     }
 }
@@ -0,0 +1,232 @@
+use leptos::task::spawn_local;
+use leptos::prelude::*;
+use leptos::prelude::Callback;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::MouseEvent;
+
+use crate::components::*;
+use crate::login::{use_session, Permission};
+use crate::notify::{use_notifications, Notice};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"], js_name = invoke, catch)]
+    async fn try_invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WatchFolder {
+    pub path: String,
+    pub vendor_name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub configured_by: String,
+}
+
+impl Default for WatchFolder {
+    fn default() -> Self {
+        WatchFolder {
+            path: String::new(),
+            vendor_name: String::new(),
+            pattern: "*.csv".to_string(),
+            configured_by: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum IngestStatus {
+    Submitted,
+    Quarantined,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IngestResult {
+    pub file: String,
+    pub vendor_name: String,
+    pub status: IngestStatus,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize)]
+struct TauriEvent<T> {
+    payload: T,
+}
+
+pub fn format_timestamp(seconds: u64) -> String {
+    let date = js_sys::Date::new(&JsValue::from_f64(seconds as f64 * 1000.0));
+    date.to_locale_string("default", &JsValue::UNDEFINED).into()
+}
+
+#[component]
+pub fn WatchFolders() -> impl IntoView {
+    let vendors = RwSignal::new(Vec::<String>::new());
+    let folders = RwSignal::new(Vec::<WatchFolder>::new());
+    let results = RwSignal::new(Vec::<IngestResult>::new());
+    let notifications = use_notifications();
+    let session = use_session();
+
+    spawn_local(async move {
+        if let Ok(result) = try_invoke("list_vendors", JsValue::NULL).await {
+            vendors.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+        }
+        if let Ok(result) = try_invoke("get_watch_folders", JsValue::NULL).await {
+            folders.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+        }
+        if let Ok(result) = try_invoke("list_ingest_results", JsValue::NULL).await {
+            results.set(serde_wasm_bindgen::from_value(result).unwrap_or_default());
+        }
+    });
+
+    listen_while_mounted("watch://result", move |event| {
+        if let Ok(event) = serde_wasm_bindgen::from_value::<TauriEvent<IngestResult>>(event) {
+            results.update(|results| results.push(event.payload));
+        }
+    });
+
+    let on_change = Callback::new(move |(index, folder): (usize, WatchFolder)| {
+        folders.update(|folders| folders[index] = folder);
+    });
+
+    let on_remove = Callback::new(move |index: usize| {
+        folders.update(|folders| {
+            folders.remove(index);
+        });
+    });
+
+    let on_add = move |_: MouseEvent| {
+        folders.update(|folders| folders.push(WatchFolder::default()));
+    };
+
+    let on_save = move |_: MouseEvent| {
+        spawn_local(async move {
+            let args = serde_wasm_bindgen::to_value(&serde_json::json!({
+                "folders": folders.get_untracked(),
+            }))
+            .unwrap();
+            match try_invoke("set_watch_folders", args).await {
+                Ok(_) => notifications.notify(Notice::info("Watching folders")),
+                Err(err) => notifications.notify(Notice::error("Saving watch folders failed").details(err.as_string().unwrap_or_default())),
+            }
+        });
+    };
+
+    view! {
+        <div class="upload-container watch-container">
+            <h1>"Watch Folders"</h1>
+            <div class="watch-folders">
+                {move || folders
+                    .get()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, folder)| view! {
+                        <WatchFolderRow
+                            index=index
+                            folder=folder
+                            vendors=vendors
+                            on_change=on_change
+                            on_remove=on_remove
+                        />
+                    })
+                    .collect_view()}
+            </div>
+            <Show
+                when=move || session.can(Permission::ManageWatchFolders)
+                fallback=|| view! { <p class="permission-note">"Only admins can change watch folders."</p> }
+            >
+                <button class="preview-button" on:click=on_add>"+ Add Folder"</button>
+                <button class="submit-button" on:click=on_save>"Save"</button>
+            </Show>
+            <h3>"Recent Files"</h3>
+            <table class="diff-table ingest-results">
+                {move || results
+                    .get()
+                    .into_iter()
+                    .rev()
+                    .map(|result| {
+                        let class = match result.status {
+                            IngestStatus::Submitted => "uploaded",
+                            IngestStatus::Quarantined => "skipped",
+                            IngestStatus::Failed => "failed",
+                        };
+                        view! {
+                            <tr>
+                                <td>{format_timestamp(result.timestamp)}</td>
+                                <td>{result.vendor_name}</td>
+                                <td>{result.file}</td>
+                                <td class=format!("job-status {}", class)>{format!("{:?}", result.status)}</td>
+                                <td>{result.message}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </table>
+        </div>
+    }
+}
+
+#[component]
+pub fn WatchFolderRow(
+    index: usize,
+    folder: WatchFolder,
+    vendors: RwSignal<Vec<String>>,
+    on_change: Callback<(usize, WatchFolder)>,
+    on_remove: Callback<usize>,
+) -> impl IntoView {
+    let folder = StoredValue::new(folder);
+    let update = move |f: fn(&mut WatchFolder, String), value: String| {
+        let mut updated = folder.get_value();
+        f(&mut updated, value);
+        on_change.run((index, updated));
+    };
+
+    let on_browse = move |_: MouseEvent| {
+        spawn_local(async move {
+            if let Ok(result) = try_invoke("pick_folder", JsValue::NULL).await {
+                if let Ok(Some(path)) = serde_wasm_bindgen::from_value::<Option<String>>(result) {
+                    update(|folder, path| folder.path = path, path);
+                }
+            }
+        });
+    };
+
+    view! {
+        <div class="watch-folder">
+            <div class="watch-folder-path">
+                <input
+                    type="text"
+                    class="text-input"
+                    placeholder="Folder"
+                    prop:value=folder.get_value().path
+                    on:change=move |ev| update(|folder, path| folder.path = path, event_target_value(&ev))
+                />
+                <button on:click=on_browse>"Browse"</button>
+            </div>
+            <select
+                class="vendor-select"
+                on:change=move |ev| update(|folder, vendor| folder.vendor_name = vendor, event_target_value(&ev))
+            >
+                <option value="">"Select a Vendor"</option>
+                {move || vendors
+                    .get()
+                    .into_iter()
+                    .map(|vendor| {
+                        let selected = vendor == folder.get_value().vendor_name;
+                        view! { <option value=vendor.clone() selected=selected>{vendor.clone()}</option> }
+                    })
+                    .collect_view()}
+            </select>
+            <input
+                type="text"
+                class="text-input"
+                placeholder="File pattern, e.g. stock_*.csv"
+                prop:value=folder.get_value().pattern
+                on:change=move |ev| update(|folder, pattern| folder.pattern = pattern, event_target_value(&ev))
+            />
+            <CloseButton on_click=Callback::new(move |_: MouseEvent| on_remove.run(index)) />
+        </div>
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::ValidationReport;
+use crate::upload::{DatasetDiff, FileRef, FileSummary, VendorInfo};
+
+/// Fields of the ingest schema, in the order the mapping step lists them.
+pub const TARGET_FIELDS: [&str; 9] = ["File Key", "Catalog", "Brand", "Description", "Quantity", "Ecommerce", "Cost", "MSRP", "MAP"];
+
+/// Optional target with the currency of a row's prices.
+pub const CURRENCY: &str = "Currency";
+
+/// Optional target naming the column with each row's warehouse, for files
+/// that list an item once per location.
+pub const LOCATION: &str = "Location";
+
+/// Target field of the quantity at one warehouse, as in `Quantity@North`.
+pub fn location_field(location: &str) -> String {
+    format!("Quantity@{}", location)
+}
+
+pub fn location_of(field: &str) -> Option<&str> {
+    field.strip_prefix("Quantity@")
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    #[default]
+    Vendor,
+    File,
+    Dialect,
+    Mapping,
+    Validation,
+    Review,
+}
+
+impl Step {
+    pub const ALL: [Step; 6] = [Step::Vendor, Step::File, Step::Dialect, Step::Mapping, Step::Validation, Step::Review];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Step::Vendor => "Vendor",
+            Step::File => "Sample file",
+            Step::Dialect => "Dialect & encoding",
+            Step::Mapping => "Mapping",
+            Step::Validation => "Validation",
+            Step::Review => "Review",
+        }
+    }
+
+    fn offset(self, by: isize) -> Option<Step> {
+        let index = Step::ALL.iter().position(|step| *step == self)? as isize + by;
+        usize::try_from(index).ok().and_then(|index| Step::ALL.get(index).copied())
+    }
+}
+
+/// Mirrors the backend's dialect; the default is UTF-8 with commas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Dialect {
+    pub delimiter: char,
+    pub quote: char,
+    pub encoding: String,
+    /// Decimal separator of prices; the backend offers the one the file shows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimal: Option<char>,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect {
+            delimiter: ',',
+            quote: '"',
+            encoding: "utf-8".to_string(),
+            decimal: None,
+        }
+    }
+}
+
+/// The backend's reading of the file in the chosen dialect.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Inspection {
+    pub summary: FileSummary,
+    pub dialect: Dialect,
+    /// Target field -> source, as the backend detected or was given it.
+    pub mapping: BTreeMap<String, String>,
+    pub validation: ValidationReport,
+}
+
+/// A setup saved part way through; the backend keeps it. No password, and a
+/// dropped file only by name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Draft {
+    pub id: String,
+    pub updated: u64,
+    pub step: Step,
+    pub is_new_vendor: bool,
+    pub vendor_name: String,
+    pub file_name: String,
+    pub file_path: Option<String>,
+    pub dialect: Option<Dialect>,
+    pub mapping: BTreeMap<String, String>,
+    pub delta_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum WizardEvent {
+    NewVendor(bool),
+    VendorName(String),
+    Password(String),
+    FileChosen(String, FileRef),
+    FileCleared,
+    DialectChanged(Dialect),
+    Inspected(Inspection),
+    Mapped(String, String),
+    Diffed(DatasetDiff),
+    DeltaOnly(bool),
+    ChangeNote(String),
+    Next,
+    Back,
+    GoTo(Step),
+    Resume(Draft),
+    Restart(String),
+}
+
+/// Everything the vendor setup wizard knows. Only `apply` changes it, so each
+/// step's inputs invalidate what later steps derived from them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Wizard {
+    pub step: Step,
+    /// The draft this setup is saved as.
+    pub draft_id: String,
+    pub is_new_vendor: bool,
+    pub vendor_name: String,
+    pub password: String,
+    pub file_name: String,
+    pub file: Option<FileRef>,
+    /// Unset until the file is sniffed or read; the backend then falls back to
+    /// the vendor's saved dialect.
+    pub dialect: Option<Dialect>,
+    pub summary: Option<FileSummary>,
+    /// Target field -> source column; empty until detected or picked.
+    pub mappings: BTreeMap<String, String>,
+    pub report: Option<ValidationReport>,
+    pub diff: Option<DatasetDiff>,
+    pub delta_only: bool,
+    /// Kept with the profile version the upload saves.
+    pub change_note: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combine {
+    Concat,
+    Sum,
+    First,
+}
+
+impl Combine {
+    pub const ALL: [Combine; 3] = [Combine::Concat, Combine::Sum, Combine::First];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Combine::Concat => "concat",
+            Combine::Sum => "sum",
+            Combine::First => "first",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Combine::Concat => "Join with a separator",
+            Combine::Sum => "Sum",
+            Combine::First => "First non-empty",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        Combine::ALL.into_iter().find(|combine| combine.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceMode {
+    Column,
+    Combine,
+    Constant,
+    Formula,
+}
+
+impl SourceMode {
+    pub const ALL: [SourceMode; 4] = [SourceMode::Column, SourceMode::Combine, SourceMode::Constant, SourceMode::Formula];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SourceMode::Column => "column",
+            SourceMode::Combine => "combine",
+            SourceMode::Constant => "constant",
+            SourceMode::Formula => "formula",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SourceMode::Column => "Column",
+            SourceMode::Combine => "Several columns",
+            SourceMode::Constant => "Constant",
+            SourceMode::Formula => "Formula",
+        }
+    }
+
+    pub fn named(name: &str) -> Self {
+        SourceMode::ALL.into_iter().find(|mode| mode.name() == name).unwrap_or(SourceMode::Column)
+    }
+
+    pub fn empty(self) -> FieldSource {
+        match self {
+            SourceMode::Column => FieldSource::Column(String::new()),
+            SourceMode::Combine => FieldSource::Combine { combine: Combine::Concat, separator: " ".to_string(), columns: Vec::new() },
+            SourceMode::Constant => FieldSource::Constant(String::new()),
+            SourceMode::Formula => FieldSource::Formula(String::new()),
+        }
+    }
+}
+
+/// One target field's source as the mapping step edits it. The saved form is
+/// a column name or a formula starting with `=`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldSource {
+    Column(String),
+    Combine { combine: Combine, separator: String, columns: Vec<String> },
+    Constant(String),
+    /// Without the leading `=`.
+    Formula(String),
+}
+
+fn quote(text: &str, open: char, close: char) -> String {
+    format!("{}{}{}", open, text.replace(close, &format!("{}{}", close, close)), close)
+}
+
+/// Reads a `"text"` or `[column]` at the start of `input`, returning it and the rest.
+fn unquote(input: &str, open: char, close: char) -> Option<(String, &str)> {
+    let body = input.trim_start().strip_prefix(open)?;
+    let mut chars = body.char_indices().peekable();
+    let mut text = String::new();
+    while let Some((index, c)) = chars.next() {
+        if c != close {
+            text.push(c);
+        } else if chars.peek().map(|(_, c)| *c) == Some(close) {
+            chars.next();
+            text.push(close);
+        } else {
+            return Some((text, &body[index + 1..]));
+        }
+    }
+    None
+}
+
+impl FieldSource {
+    pub fn parse(spec: &str) -> Self {
+        let Some(formula) = spec.strip_prefix('=') else {
+            return FieldSource::Column(spec.to_string());
+        };
+        if let Some((text, rest)) = unquote(formula, '"', '"') {
+            if rest.trim().is_empty() {
+                return FieldSource::Constant(text);
+            }
+        }
+        Self::parse_combine(formula).unwrap_or_else(|| FieldSource::Formula(formula.to_string()))
+    }
+
+    /// `name("separator", [A], [B])` with nothing but columns after the separator.
+    fn parse_combine(formula: &str) -> Option<Self> {
+        let (name, mut rest) = formula.trim().split_once('(')?;
+        let combine = Combine::named(name.trim())?;
+        let separator = if combine == Combine::Concat {
+            let (separator, after) = unquote(rest, '"', '"')?;
+            rest = after.trim_start().strip_prefix(',')?;
+            separator
+        } else {
+            " ".to_string()
+        };
+        let mut columns = Vec::new();
+        loop {
+            let (column, after) = unquote(rest, '[', ']')?;
+            columns.push(column);
+            let after = after.trim_start();
+            if let Some(after) = after.strip_prefix(',') {
+                rest = after;
+            } else {
+                return after.strip_prefix(')')?.trim().is_empty().then_some(FieldSource::Combine { combine, separator, columns });
+            }
+        }
+    }
+
+    pub fn mode(&self) -> SourceMode {
+        match self {
+            FieldSource::Column(_) => SourceMode::Column,
+            FieldSource::Combine { .. } => SourceMode::Combine,
+            FieldSource::Constant(_) => SourceMode::Constant,
+            FieldSource::Formula(_) => SourceMode::Formula,
+        }
+    }
+
+    /// The saved form; empty when nothing is picked yet.
+    pub fn spec(&self) -> String {
+        match self {
+            FieldSource::Column(column) => column.clone(),
+            FieldSource::Combine { columns, .. } if columns.is_empty() => String::new(),
+            FieldSource::Combine { combine, separator, columns } => {
+                let separator = (*combine == Combine::Concat).then(|| quote(separator, '"', '"'));
+                let args: Vec<String> = separator.into_iter().chain(columns.iter().map(|c| quote(c, '[', ']'))).collect();
+                format!("={}({})", combine.name(), args.join(", "))
+            }
+            FieldSource::Constant(text) if text.is_empty() => String::new(),
+            FieldSource::Constant(text) => format!("={}", quote(text, '"', '"')),
+            FieldSource::Formula(formula) if formula.trim().is_empty() => String::new(),
+            FieldSource::Formula(formula) => format!("={}", formula),
+        }
+    }
+
+    /// Switches to `mode`, keeping the columns already picked where it can.
+    pub fn into_mode(self, mode: SourceMode) -> Self {
+        match (self, mode) {
+            (source, mode) if source.mode() == mode => source,
+            (FieldSource::Column(column), SourceMode::Combine) if !column.is_empty() => {
+                FieldSource::Combine { combine: Combine::Concat, separator: " ".to_string(), columns: vec![column] }
+            }
+            (FieldSource::Combine { columns, .. }, SourceMode::Column) => {
+                FieldSource::Column(columns.into_iter().next().unwrap_or_default())
+            }
+            (FieldSource::Column(column), SourceMode::Formula) if !column.is_empty() => FieldSource::Formula(quote(&column, '[', ']')),
+            (source @ (FieldSource::Combine { .. } | FieldSource::Constant(_)), SourceMode::Formula) => {
+                FieldSource::Formula(source.spec().trim_start_matches('=').to_string())
+            }
+            (_, mode) => mode.empty(),
+        }
+    }
+}
+
+impl Wizard {
+    pub fn new(draft_id: String) -> Self {
+        Wizard { draft_id, ..Wizard::default() }
+    }
+
+    /// The file was read differently, so nothing derived from it holds.
+    fn forget_reading(&mut self) {
+        self.summary = None;
+        self.mappings.clear();
+        self.report = None;
+        self.diff = None;
+    }
+
+    pub fn apply(&mut self, event: WizardEvent) {
+        match event {
+            WizardEvent::NewVendor(is_new) => {
+                if is_new != self.is_new_vendor {
+                    self.is_new_vendor = is_new;
+                    self.vendor_name.clear();
+                    self.password.clear();
+                    self.forget_reading();
+                }
+            }
+            WizardEvent::VendorName(name) => {
+                self.vendor_name = name;
+                // An existing vendor brings its saved mapping and dialect.
+                if !self.is_new_vendor {
+                    self.forget_reading();
+                }
+                self.diff = None;
+            }
+            WizardEvent::Password(password) => self.password = password,
+            WizardEvent::FileChosen(name, file) => {
+                self.file_name = name;
+                self.file = Some(file);
+                self.dialect = None;
+                self.forget_reading();
+            }
+            WizardEvent::FileCleared => {
+                self.file_name.clear();
+                self.file = None;
+                self.forget_reading();
+            }
+            WizardEvent::DialectChanged(dialect) => {
+                if self.dialect.as_ref() != Some(&dialect) {
+                    self.dialect = Some(dialect);
+                    self.forget_reading();
+                }
+            }
+            WizardEvent::Inspected(inspection) => {
+                // Keeps the dialect the file was read in, e.g. the saved one, from being lost,
+                // and the decimal separator found in its prices for the operator to confirm.
+                let dialect = self.dialect.get_or_insert_with(|| inspection.dialect.clone());
+                dialect.decimal = dialect.decimal.or(inspection.dialect.decimal);
+                if self.mappings.is_empty() {
+                    self.mappings = inspection.mapping;
+                }
+                self.summary = Some(inspection.summary);
+                self.report = Some(inspection.validation);
+            }
+            WizardEvent::Mapped(target, source) => {
+                if source.is_empty() {
+                    self.mappings.remove(&target);
+                } else {
+                    self.mappings.insert(target, source);
+                }
+                self.report = None;
+                self.diff = None;
+            }
+            WizardEvent::Diffed(diff) => self.diff = Some(diff),
+            WizardEvent::DeltaOnly(delta_only) => self.delta_only = delta_only,
+            WizardEvent::ChangeNote(note) => self.change_note = note,
+            WizardEvent::Next => {
+                if self.blocker(self.step).is_none() {
+                    self.step = self.step.offset(1).unwrap_or(self.step);
+                }
+            }
+            WizardEvent::Back => self.step = self.step.offset(-1).unwrap_or(self.step),
+            WizardEvent::GoTo(step) => {
+                if self.can_reach(step) {
+                    self.step = step;
+                }
+            }
+            WizardEvent::Resume(draft) => {
+                let file = draft.file_path.map(FileRef::Path);
+                *self = Wizard {
+                    draft_id: draft.id,
+                    is_new_vendor: draft.is_new_vendor,
+                    vendor_name: draft.vendor_name,
+                    file_name: if file.is_some() { draft.file_name } else { String::new() },
+                    file,
+                    dialect: draft.dialect,
+                    mappings: draft.mapping,
+                    delta_only: draft.delta_only,
+                    ..Wizard::default()
+                };
+                // Nothing the file told the last session is kept, so it is read
+                // again from the first step that needs it. A dropped file has
+                // to be chosen again.
+                self.step = Step::ALL
+                    .into_iter()
+                    .take_while(|step| *step <= draft.step && self.can_reach(*step))
+                    .last()
+                    .unwrap_or_default();
+            }
+            WizardEvent::Restart(draft_id) => *self = Wizard::new(draft_id),
+        }
+    }
+
+    /// Why `step` cannot be left forwards yet, if it cannot.
+    pub fn blocker(&self, step: Step) -> Option<&'static str> {
+        match step {
+            Step::Vendor if self.vendor_name.trim().is_empty() => Some("Enter or pick a vendor"),
+            Step::File if self.file.is_none() => Some("Choose a sample file"),
+            Step::Dialect => match &self.summary {
+                None => Some("Reading the file..."),
+                Some(summary) if summary.headers.is_empty() => Some("The file has no header row"),
+                Some(_) => None,
+            },
+            Step::Mapping if !self.mappings.contains_key(TARGET_FIELDS[0]) => Some("Map the File Key"),
+            Step::Validation => match &self.report {
+                None => Some("Validating..."),
+                Some(report) if !report.errors.is_empty() => Some("Fix the errors before going on"),
+                Some(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether every step before `step` is complete.
+    pub fn can_reach(&self, step: Step) -> bool {
+        Step::ALL.into_iter().take_while(|s| *s < step).all(|s| self.blocker(s).is_none())
+    }
+
+    /// Nothing entered yet, so nothing worth keeping as a draft.
+    pub fn is_blank(&self) -> bool {
+        self.vendor_name.trim().is_empty() && self.file.is_none()
+    }
+
+    pub fn file_mappings(&self) -> String {
+        self.mappings
+            .iter()
+            .map(|(target, source)| format!("{}:{}", target, source))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    pub fn payload(&self) -> VendorInfo {
+        let (file_bytes, file_path) = self.file.clone().map(FileRef::into_parts).unwrap_or_default();
+        VendorInfo {
+            vendor_name: self.vendor_name.trim().to_string(),
+            password: self.password.clone(),
+            file_mappings: self.file_mappings(),
+            file_bytes,
+            file_path,
+            delta_only: self.delta_only,
+            dialect: self.dialect.clone(),
+            change_note: self.change_note.clone(),
+        }
+    }
+
+    pub fn draft(&self) -> Draft {
+        Draft {
+            id: self.draft_id.clone(),
+            updated: 0,
+            step: self.step,
+            is_new_vendor: self.is_new_vendor,
+            vendor_name: self.vendor_name.clone(),
+            file_name: self.file_name.clone(),
+            file_path: match &self.file {
+                Some(FileRef::Path(path)) => Some(path.clone()),
+                _ => None,
+            },
+            dialect: self.dialect.clone(),
+            mapping: self.mappings.clone(),
+            delta_only: self.delta_only,
+        }
+    }
+}